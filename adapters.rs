@@ -0,0 +1,627 @@
+//! Adapters over the primes produced by a sieve.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops;
+
+use crate::{CheckedOps, FromSmall, Infinite, Sieve, SieveInt};
+
+/// An iterator over twin primes, which are pairs of primes `(p, p + 2)`.
+///
+/// See [`Sieve::twins`][crate::Sieve::twins].
+pub struct Twins<T>
+where
+    T: Iterator,
+{
+    iter: T,
+    last: Option<T::Item>,
+}
+
+impl<T> Twins<T>
+where
+    T: Iterator,
+{
+    pub(crate) fn new(iter: T) -> Self {
+        Self { iter, last: None }
+    }
+}
+
+impl<T> Iterator for Twins<T>
+where
+    T: Iterator,
+    T::Item: Copy + PartialEq + FromSmall + ops::Sub<Output = T::Item>,
+{
+    type Item = (T::Item, T::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let two = T::Item::from_small(2);
+
+        let mut last = match self.last.take() {
+            Some(last) => last,
+            None => self.iter.next()?,
+        };
+
+        loop {
+            let p = self.iter.next()?;
+            self.last = Some(p);
+
+            if p - last == two {
+                return Some((last, p));
+            }
+
+            last = p;
+        }
+    }
+}
+
+/// An iterator over primes and the gap to the next prime.
+///
+/// See [`Sieve::gaps`][crate::Sieve::gaps].
+pub struct Gaps<T>
+where
+    T: Iterator,
+{
+    iter: T,
+    last: Option<T::Item>,
+}
+
+impl<T> Gaps<T>
+where
+    T: Iterator,
+{
+    pub(crate) fn new(iter: T) -> Self {
+        Self { iter, last: None }
+    }
+}
+
+impl<T> Iterator for Gaps<T>
+where
+    T: Iterator,
+    T::Item: Copy + ops::Sub<Output = T::Item>,
+{
+    type Item = (T::Item, T::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let last = match self.last.take() {
+            Some(last) => last,
+            None => self.iter.next()?,
+        };
+
+        let p = self.iter.next()?;
+        self.last = Some(p);
+        Some((last, p - last))
+    }
+}
+
+/// The error raised when a constellation pattern is invalid.
+///
+/// See [`constellations()`][crate::constellations()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidPattern {
+    /// The pattern has no offsets.
+    Empty,
+    /// The first offset isn't zero.
+    NotFromZero,
+    /// The offsets aren't strictly increasing.
+    NotIncreasing,
+}
+
+impl fmt::Display for InvalidPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidPattern::Empty => write!(f, "pattern is empty"),
+            InvalidPattern::NotFromZero => write!(f, "pattern doesn't start at zero"),
+            InvalidPattern::NotIncreasing => write!(f, "pattern isn't strictly increasing"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidPattern {}
+
+/// An iterator over prime constellations, which are groups of primes at fixed
+/// offsets from each other.
+///
+/// See [`Sieve::constellations`][crate::Sieve::constellations].
+pub struct Constellations<T>
+where
+    T: Iterator,
+{
+    iter: T,
+    offsets: Vec<T::Item>,
+    /// Primes from the first candidate member up to the end of its pattern.
+    window: VecDeque<T::Item>,
+    done: bool,
+}
+
+impl<T> Constellations<T>
+where
+    T: Iterator,
+    T::Item: Copy + Ord + FromSmall,
+{
+    pub(crate) fn new(iter: T, offsets: &[T::Item]) -> Result<Self, InvalidPattern> {
+        match offsets.first() {
+            None => return Err(InvalidPattern::Empty),
+            Some(&first) if first != T::Item::from_small(0) => {
+                return Err(InvalidPattern::NotFromZero)
+            }
+            Some(..) => {}
+        }
+
+        if offsets.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(InvalidPattern::NotIncreasing);
+        }
+
+        Ok(Self {
+            iter,
+            offsets: offsets.to_vec(),
+            window: VecDeque::new(),
+            done: false,
+        })
+    }
+}
+
+impl<T> Iterator for Constellations<T>
+where
+    T: Iterator,
+    T::Item: Copy + Ord + CheckedOps,
+{
+    type Item = Vec<T::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let span = *self.offsets.last()?;
+
+        loop {
+            let p = match self.window.front() {
+                Some(&p) => p,
+                None if self.done => return None,
+                None => match self.iter.next() {
+                    Some(p) => {
+                        self.window.push_back(p);
+                        p
+                    }
+                    None => {
+                        self.done = true;
+                        return None;
+                    }
+                },
+            };
+
+            // A pattern which overflows can't be made up of primes.
+            let end = match p.checked_add(span) {
+                Some(end) => end,
+                None => {
+                    self.window.pop_front();
+                    continue;
+                }
+            };
+
+            while !self.done && self.window.back().map_or(true, |&q| q < end) {
+                match self.iter.next() {
+                    Some(q) => self.window.push_back(q),
+                    None => self.done = true,
+                }
+            }
+
+            let window = &self.window;
+
+            let found = self.offsets.iter().all(|&o| match p.checked_add(o) {
+                Some(n) => window.binary_search(&n).is_ok(),
+                None => false,
+            });
+
+            self.window.pop_front();
+
+            if found {
+                return Some(
+                    self.offsets
+                        .iter()
+                        .filter_map(|&o| p.checked_add(o))
+                        .collect(),
+                );
+            }
+        }
+    }
+}
+
+/// An iterator over the primes which are sums of two squares, along with the
+/// squares' roots.
+///
+/// See [`Sieve::two_squares`][crate::Sieve::two_squares].
+pub struct TwoSquares<T> {
+    iter: T,
+}
+
+impl<T> TwoSquares<T> {
+    pub(crate) fn new(iter: T) -> Self {
+        Self { iter }
+    }
+}
+
+impl<T> Iterator for TwoSquares<T>
+where
+    T: Iterator,
+    T::Item: SieveInt,
+{
+    type Item = (T::Item, T::Item, T::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let p = self.iter.next()?;
+            let n = p.to_u64()?;
+
+            if n == 2 || n % 4 == 1 {
+                let (a, b) = crate::residue::decompose(n);
+                return Some((p, T::Item::from_u64(a)?, T::Item::from_u64(b)?));
+            }
+        }
+    }
+}
+
+/// An iterator over primes along with their 1-based index among all primes.
+///
+/// See [`Sieve::indexed`][crate::Sieve::indexed].
+pub struct Indexed<T> {
+    sieve: T,
+}
+
+impl<T> Indexed<T> {
+    pub(crate) fn new(sieve: T) -> Self {
+        Self { sieve }
+    }
+}
+
+impl<I, U, S> Iterator for Indexed<Sieve<I, U, S>>
+where
+    Sieve<I, U, S>: Iterator<Item = I>,
+{
+    type Item = (u64, I);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let p = self.sieve.next()?;
+        Some((self.sieve.count as u64, p))
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let p = self.sieve.nth(n)?;
+        Some((self.sieve.count as u64, p))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sieve.size_hint()
+    }
+}
+
+/// Membership tests against an infinite sieve for numbers which never
+/// decrease, so that each prime is only produced once.
+struct Lookahead<I> {
+    sieve: Infinite<I>,
+    last: Option<I>,
+}
+
+impl<I> Lookahead<I>
+where
+    ops::RangeFrom<I>: Iterator<Item = I>,
+    I: SieveInt,
+{
+    fn new() -> Self {
+        Self {
+            sieve: crate::infinite(),
+            last: None,
+        }
+    }
+
+    /// Test if `n` is prime, where `n` is at least as large as in the last
+    /// call.
+    fn contains(&mut self, n: I) -> bool {
+        loop {
+            match self.last {
+                Some(last) if last >= n => return last == n,
+                _ => match self.sieve.next() {
+                    Some(p) => self.last = Some(p),
+                    None => return false,
+                },
+            }
+        }
+    }
+}
+
+/// An iterator over Sophie Germain primes, which are primes `p` such that `2p
+/// + 1` is also prime.
+///
+/// See [`Sieve::sophie_germain`][crate::Sieve::sophie_germain].
+pub struct SophieGermain<T>
+where
+    T: Iterator,
+{
+    iter: T,
+    lookahead: Lookahead<T::Item>,
+}
+
+impl<T> SophieGermain<T>
+where
+    T: Iterator,
+    ops::RangeFrom<T::Item>: Iterator<Item = T::Item>,
+    T::Item: SieveInt,
+{
+    pub(crate) fn new(iter: T) -> Self {
+        Self {
+            iter,
+            lookahead: Lookahead::new(),
+        }
+    }
+}
+
+impl<T> Iterator for SophieGermain<T>
+where
+    T: Iterator,
+    ops::RangeFrom<T::Item>: Iterator<Item = T::Item>,
+    T::Item: SieveInt,
+{
+    type Item = T::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let p = self.iter.next()?;
+
+            // Primes only increase, so once `2p + 1` overflows there are no
+            // more to be found.
+            let q = p
+                .checked_mul(T::Item::from_small(2))?
+                .checked_add(T::Item::from_small(1))?;
+
+            if self.lookahead.contains(q) {
+                return Some(p);
+            }
+        }
+    }
+}
+
+/// An iterator over safe primes, which are primes `p` such that `(p - 1) / 2`
+/// is also prime.
+///
+/// See [`Sieve::safe_primes`][crate::Sieve::safe_primes].
+pub struct SafePrimes<T>
+where
+    T: Iterator,
+{
+    iter: T,
+    lookahead: Lookahead<T::Item>,
+}
+
+impl<T> SafePrimes<T>
+where
+    T: Iterator,
+    ops::RangeFrom<T::Item>: Iterator<Item = T::Item>,
+    T::Item: SieveInt,
+{
+    pub(crate) fn new(iter: T) -> Self {
+        Self {
+            iter,
+            lookahead: Lookahead::new(),
+        }
+    }
+}
+
+impl<T> Iterator for SafePrimes<T>
+where
+    T: Iterator,
+    ops::RangeFrom<T::Item>: Iterator<Item = T::Item>,
+    T::Item: SieveInt,
+{
+    type Item = T::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let p = self.iter.next()?;
+
+            // For odd `p` this is `(p - 1) / 2`, and for 2 it's 1 which
+            // correctly rules it out.
+            if self.lookahead.contains(p / T::Item::from_small(2)) {
+                return Some(p);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InvalidPattern;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn twins() {
+        let twins = crate::infinite::<u32>().twins().take(6).collect::<Vec<_>>();
+        assert_eq!(
+            twins,
+            [(3, 5), (5, 7), (11, 13), (17, 19), (29, 31), (41, 43)]
+        );
+
+        assert_eq!(crate::bounded::<u32>(1_000_000).twins().count(), 8169);
+
+        // The upper member never exceeds the bound.
+        assert_eq!(crate::bounded::<u32>(42).twins().last(), Some((29, 31)));
+        assert_eq!(crate::bounded::<u32>(43).twins().last(), Some((41, 43)));
+        assert_eq!(crate::bounded::<u32>(4).twins().next(), None);
+    }
+
+    #[test]
+    fn gaps() {
+        let gaps = crate::infinite::<u32>().gaps().take(8).collect::<Vec<_>>();
+        let expected = [
+            (2, 1),
+            (3, 2),
+            (5, 2),
+            (7, 4),
+            (11, 2),
+            (13, 4),
+            (17, 2),
+            (19, 4),
+        ];
+        assert_eq!(gaps, expected);
+
+        let max = crate::bounded::<u32>(100_000)
+            .gaps()
+            .max_by_key(|&(_, gap)| gap);
+        assert_eq!(max, Some((31_397, 72)));
+
+        // The last prime of a bounded sieve has no successor.
+        assert_eq!(crate::bounded::<u32>(100).gaps().last(), Some((89, 8)));
+        assert_eq!(crate::bounded::<u32>(2).gaps().next(), None);
+    }
+
+    #[test]
+    fn constellations() {
+        let triplets = crate::bounded::<u32>(100_000)
+            .constellations(&[0, 2, 6])
+            .unwrap();
+        let triplets = triplets.map(|c| c[0]).collect::<Vec<_>>();
+        assert_eq!(triplets.len(), 259);
+        assert_eq!(triplets[..5], [5, 11, 17, 41, 101]);
+        assert_eq!(triplets.last(), Some(&99_707));
+
+        let quads = crate::bounded::<u32>(100_000)
+            .constellations(&[0, 2, 6, 8])
+            .unwrap();
+        let quads = quads.map(|c| c[0]).collect::<Vec<_>>();
+        assert_eq!(quads.len(), 38);
+        assert_eq!(quads[..6], [5, 11, 101, 191, 821, 1481]);
+        assert_eq!(quads.last(), Some(&99_131));
+
+        let cousins = crate::bounded::<u32>(100_000)
+            .constellations(&[0, 4])
+            .unwrap();
+        assert_eq!(cousins.count(), 1216);
+        let sexy = crate::bounded::<u32>(100_000)
+            .constellations(&[0, 6])
+            .unwrap();
+        assert_eq!(sexy.count(), 2447);
+
+        // Twins agree with the dedicated adapter.
+        let twins = crate::constellations::<u32>(&[0, 2]).unwrap().take(1000);
+        let expected = crate::infinite::<u32>().twins().take(1000);
+        assert!(twins.eq(expected.map(|(p, q)| vec![p, q])));
+
+        // Members beyond the bound aren't produced.
+        let quads = crate::bounded::<u32>(16)
+            .constellations(&[0, 2, 6, 8])
+            .unwrap();
+        assert!(quads.eq([vec![5, 7, 11, 13]]));
+        let quads = crate::bounded::<u32>(18)
+            .constellations(&[0, 2, 6, 8])
+            .unwrap();
+        assert!(quads.eq([vec![5, 7, 11, 13]]));
+        let quads = crate::bounded::<u32>(19)
+            .constellations(&[0, 2, 6, 8])
+            .unwrap();
+        assert!(quads.eq([vec![5, 7, 11, 13], vec![11, 13, 17, 19]]));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        let err = |offsets: &[u32]| crate::constellations(offsets).err();
+        assert_eq!(err(&[]), Some(InvalidPattern::Empty));
+        assert_eq!(err(&[2, 6]), Some(InvalidPattern::NotFromZero));
+        assert_eq!(err(&[0, 6, 2]), Some(InvalidPattern::NotIncreasing));
+        assert_eq!(err(&[0, 0, 2]), Some(InvalidPattern::NotIncreasing));
+        assert_eq!(err(&[0]), None);
+    }
+
+    #[test]
+    fn sophie_germain() {
+        let primes = crate::sophie_germain::<u32>().take(10).collect::<Vec<_>>();
+        assert_eq!(primes, [2, 3, 5, 11, 23, 29, 41, 53, 83, 89]);
+
+        let expected = crate::bounded::<u64>(10_000)
+            .filter(|&p| crate::is_prime_u64(2 * p + 1))
+            .collect::<Vec<_>>();
+        let actual = crate::bounded::<u64>(10_000)
+            .sophie_germain()
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+        assert_eq!(actual.len(), 190);
+
+        // `2p + 1` is tested even if it's beyond the bound.
+        assert_eq!(crate::bounded::<u32>(89).sophie_germain().last(), Some(89));
+        assert_eq!(crate::bounded::<u32>(88).sophie_germain().last(), Some(83));
+    }
+
+    #[test]
+    fn safe_primes() {
+        let primes = crate::safe_primes::<u32>().take(12).collect::<Vec<_>>();
+        assert_eq!(primes, [5, 7, 11, 23, 47, 59, 83, 107, 167, 179, 227, 263]);
+
+        let expected = crate::bounded::<u64>(100_000)
+            .filter(|&p| crate::is_prime_u64((p - 1) / 2))
+            .collect::<Vec<_>>();
+        let actual = crate::bounded::<u64>(100_000)
+            .safe_primes()
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+        assert_eq!(actual.len(), 670);
+
+        // Every safe prime is `2q + 1` for a Sophie Germain prime `q`.
+        let germain = crate::bounded::<u64>(50_000).sophie_germain();
+        assert!(germain.map(|q| 2 * q + 1).eq(expected));
+
+        assert_eq!(crate::bounded::<u32>(6).safe_primes().next(), Some(5));
+        assert_eq!(crate::bounded::<u32>(4).safe_primes().next(), None);
+    }
+
+    #[test]
+    fn two_squares() {
+        let squares = crate::bounded::<u64>(100_000).two_squares();
+        let expected = crate::bounded::<u64>(100_000).filter(|&p| p == 2 || p % 4 == 1);
+        let mut count = 0;
+
+        for ((p, a, b), q) in squares.zip(expected) {
+            assert_eq!(p, q);
+            assert_eq!(a * a + b * b, p);
+            assert_eq!(crate::two_squares(p), Some((a, b)));
+            count += 1;
+        }
+
+        assert_eq!(count, 4_784);
+        assert_eq!(
+            crate::bounded(u8::MAX).two_squares().last(),
+            Some((241, 4, 15))
+        );
+    }
+
+    #[test]
+    fn indexed() {
+        let mut indexed = crate::infinite::<u32>().indexed();
+        assert_eq!(indexed.nth(24), Some((25, 97)));
+        assert_eq!(indexed.nth(74), Some((100, 541)));
+        assert_eq!(indexed.next(), Some((101, 547)));
+
+        let mut sieve = crate::bounded::<u64>(1000);
+        assert!(sieve.by_ref().take(24).eq(crate::bounded(89)));
+        let mut indexed = sieve.indexed();
+        assert_eq!(indexed.next(), Some((25, 97)));
+        assert!(indexed
+            .by_ref()
+            .step_by(25)
+            .take(3)
+            .eq([(26, 101), (51, 233), (76, 383)]));
+        assert_eq!(indexed.nth(23), Some((100, 541)));
+        assert_eq!(indexed.last(), Some((168, 997)));
+
+        // Primes skipped in bulk are still counted.
+        let mut sieve = crate::infinite::<u64>();
+        sieve.skip_to(1_000_000);
+        assert!(!sieve.is_prime(1_000_001));
+        let mut indexed = sieve.indexed();
+        assert_eq!(indexed.next(), Some((78_499, 1_000_003)));
+        assert_eq!(indexed.nth(9_999), Some((88_499, 1_138_589)));
+
+        let mut indexed = crate::bounded(u8::MAX).indexed();
+        assert_eq!(indexed.by_ref().last(), Some((54, 251)));
+        assert_eq!(indexed.next(), None);
+    }
+}
@@ -0,0 +1,196 @@
+//! Numbers with a fixed number of prime factors.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::marker;
+
+use crate::tables::big_omega_table;
+use crate::FromSmall;
+
+/// An iterator over the numbers with exactly `k` prime factors counted with
+/// multiplicity.
+///
+/// See [`almost_primes()`] and [`semiprimes()`].
+pub struct AlmostPrimes<I> {
+    /// The number of prime factors of every number up to the bound, counted
+    /// with multiplicity.
+    omega: Vec<u8>,
+    k: u32,
+    /// The next number to test.
+    n: usize,
+    _marker: marker::PhantomData<I>,
+}
+
+impl<I> AlmostPrimes<I> {
+    fn new<U>(k: u32, upper: U) -> Self
+    where
+        U: Copy + Ord + FromSmall,
+        usize: TryFrom<U>,
+    {
+        let omega = if k == 0 || upper < U::from_small(2) {
+            Vec::new()
+        } else {
+            match usize::try_from(upper) {
+                Ok(upper) => big_omega_table(upper),
+                Err(..) => panic!("bound doesn't fit in a usize"),
+            }
+        };
+
+        Self {
+            omega,
+            k,
+            n: 2,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+/// Construct an iterator over the `k`-almost-primes up to and including
+/// `upper` in increasing order, which are the numbers with exactly `k` prime
+/// factors counted with multiplicity.
+///
+/// The 1-almost-primes are the primes, and the 2-almost-primes are the
+/// [`semiprimes()`]. Like those, the number of prime factors of every number
+/// up to `upper` is computed ahead of time using a linear sieve. Only 1 has no
+/// prime factors, but it isn't produced so the iterator is empty when `k` is
+/// zero.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `usize`.
+///
+/// # Examples
+///
+/// ```
+/// let numbers = sieve::almost_primes(3, 50u32);
+/// assert!(numbers.eq([8, 12, 18, 20, 27, 28, 30, 42, 44, 45, 50]));
+///
+/// assert!(sieve::almost_primes(1, 20u32).eq(sieve::bounded(20u32)));
+/// assert_eq!(sieve::almost_primes(0, 20u32).next(), None);
+/// ```
+pub fn almost_primes<I>(k: u32, upper: I) -> AlmostPrimes<I>
+where
+    I: Copy + Ord + FromSmall,
+    usize: TryFrom<I>,
+{
+    AlmostPrimes::new(k, upper)
+}
+
+/// Construct an iterator over the semiprimes up to and including `upper` in
+/// increasing order.
+///
+/// A semiprime has exactly two prime factors counted with multiplicity, so
+/// squares of primes like 4 and 9 are included. Instead of factorizing each
+/// candidate, the number of prime factors of every number up to `upper` is
+/// computed ahead of time using a linear sieve, which takes a byte of memory
+/// per number.
+///
+/// This is the same as [`almost_primes()`] with `k = 2`.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `usize`.
+///
+/// # Examples
+///
+/// ```
+/// let semiprimes = sieve::semiprimes(30u32);
+/// assert!(semiprimes.eq([4, 6, 9, 10, 14, 15, 21, 22, 25, 26]));
+///
+/// assert_eq!(sieve::semiprimes(1_000_000u32).count(), 210_035);
+/// ```
+pub fn semiprimes<I>(upper: I) -> AlmostPrimes<I>
+where
+    I: Copy + Ord + FromSmall,
+    usize: TryFrom<I>,
+{
+    AlmostPrimes::new(2, upper)
+}
+
+impl<I> Iterator for AlmostPrimes<I>
+where
+    I: TryFrom<usize>,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&omega) = self.omega.get(self.n) {
+            let n = self.n;
+            self.n += 1;
+
+            if u32::from(omega) == self.k {
+                return I::try_from(n).ok();
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{almost_primes, semiprimes};
+
+    #[test]
+    fn first_terms() {
+        let first = semiprimes(100u32).take(20).collect::<Vec<_>>();
+
+        assert_eq!(
+            first,
+            [4, 6, 9, 10, 14, 15, 21, 22, 25, 26, 33, 34, 35, 38, 39, 46, 49, 51, 55, 57]
+        );
+    }
+
+    #[test]
+    fn matches_factorize() {
+        let expected = (0..=10_000u64)
+            .filter(|&n| crate::factorize(n).iter().map(|&(_, e)| e).sum::<u32>() == 2);
+
+        assert!(semiprimes(10_000u64).eq(expected));
+    }
+
+    #[test]
+    fn small_bounds() {
+        assert_eq!(semiprimes(0u32).next(), None);
+        assert_eq!(semiprimes(3u32).next(), None);
+        assert!(semiprimes(4u32).eq([4]));
+        assert!(semiprimes(-5i64).eq([]));
+        assert_eq!(semiprimes(1_000_000u32).count(), 210_035);
+    }
+
+    #[test]
+    fn almost_primes_match_factorize() {
+        let omega = |n: u64| crate::factorize(n).iter().map(|&(_, e)| e).sum::<u32>();
+
+        for k in 0..16 {
+            let expected = (2..=10_000u64).filter(|&n| omega(n) == k);
+            assert!(almost_primes(k, 10_000u64).eq(expected), "{}", k);
+        }
+    }
+
+    #[test]
+    fn almost_primes_degenerate() {
+        assert!(almost_primes(1, 100_000u32).eq(crate::bounded(100_000u32)));
+        assert!(almost_primes(2, 100_000u32).eq(semiprimes(100_000u32)));
+        assert_eq!(almost_primes(0, 100_000u32).next(), None);
+        assert!(almost_primes(20, 1 << 20).eq([1u32 << 20]));
+        assert_eq!(almost_primes(20, (1 << 20) - 1).next(), None::<u32>);
+    }
+
+    #[test]
+    fn almost_primes_counts() {
+        let first = almost_primes(3, 100u32).collect::<Vec<_>>();
+        assert_eq!(
+            first,
+            [
+                8, 12, 18, 20, 27, 28, 30, 42, 44, 45, 50, 52, 63, 66, 68, 70, 75, 76, 78, 92, 98,
+                99
+            ]
+        );
+
+        assert_eq!(almost_primes(3, 100_000u32).count(), 25_556);
+        assert_eq!(almost_primes(4, 100_000u32).count(), 18_744);
+    }
+}
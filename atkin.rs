@@ -0,0 +1,201 @@
+//! The sieve of Atkin.
+
+use alloc::vec;
+use core::convert::TryFrom;
+use core::marker;
+
+use crate::flat::{self, clear_multiples, Flat};
+use crate::FromSmall;
+
+/// An iterator over the primes found by the sieve of Atkin.
+///
+/// See [`atkin()`].
+pub struct Atkin<I> {
+    iter: flat::IntoIter,
+    _marker: marker::PhantomData<I>,
+}
+
+/// Construct a bounded sieve which yields the same primes as
+/// [`bounded()`][crate::bounded()], but sieves them up front with the sieve of
+/// Atkin.
+///
+/// Instead of crossing off multiples of primes, this toggles candidates on by
+/// counting their representations as one of three quadratic forms, and then
+/// crosses off multiples of the squares of the primes it found. The sieve
+/// takes `upper / 16` bytes.
+///
+/// The sieve of Atkin does asymptotically less work than the sieve of
+/// Eratosthenes, but its marking jumps around memory a lot more. In practice
+/// it's somewhat slower than the bit sieve behind
+/// [`prime_count()`][crate::prime_count()], and unlike
+/// [`segmented()`][crate::segmented()] it needs the whole bit array at once.
+/// It's mostly worth choosing for comparison, or where a bit array is wanted
+/// anyway and the bound is small enough for it to fit in cache.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `usize`.
+///
+/// # Examples
+///
+/// ```
+/// assert!(sieve::atkin(30u32).eq([2, 3, 5, 7, 11, 13, 17, 19, 23, 29]));
+/// assert_eq!(sieve::atkin(1_000_000u64).count(), 78_498);
+/// ```
+pub fn atkin<I>(upper: I) -> Atkin<I>
+where
+    I: Copy + Ord + FromSmall,
+    usize: TryFrom<I>,
+{
+    let upper = if upper < I::from_small(2) {
+        0
+    } else {
+        match usize::try_from(upper) {
+            Ok(upper) => upper,
+            Err(..) => panic!("bound doesn't fit in a usize"),
+        }
+    };
+
+    Atkin {
+        iter: sieve(upper).into_iter(),
+        _marker: marker::PhantomData,
+    }
+}
+
+/// Sieve the primes up to and including `upper`.
+fn sieve(upper: usize) -> Flat {
+    // Bit `i` is set if `2 * i + 1` is prime, same as in `Flat`.
+    let len = upper / 2 + upper % 2;
+    let mut bits = vec![0u64; (len + 63) / 64];
+
+    let mut toggle = |n: usize| {
+        let i = n / 2;
+        bits[i / 64] ^= 1 << (i % 64);
+    };
+
+    // `4x² + y² = n` for `n % 12` in 1 or 5, where `y` has to be odd for `n`
+    // to be.
+    let mut x = 1;
+
+    while 4 * x * x < upper {
+        let base = 4 * x * x;
+        let mut y = 1;
+
+        while base + y * y <= upper {
+            let n = base + y * y;
+
+            if n % 12 == 1 || n % 12 == 5 {
+                toggle(n);
+            }
+
+            y += 2;
+        }
+
+        x += 1;
+    }
+
+    // `3x² + y² = n` for `n % 12 == 7`, where `x` and `y` have different
+    // parity.
+    let mut x = 1;
+
+    while 3 * x * x < upper {
+        let base = 3 * x * x;
+        let mut y = 1 + x % 2;
+
+        while base + y * y <= upper {
+            let n = base + y * y;
+
+            if n % 12 == 7 {
+                toggle(n);
+            }
+
+            y += 2;
+        }
+
+        x += 1;
+    }
+
+    // `3x² - y² = n` for `n % 12 == 11` and `x > y`, where `n` grows as `y`
+    // shrinks.
+    let mut x = 2;
+
+    while 2 * x * x + 2 * x - 1 <= upper {
+        let base = 3 * x * x;
+        let mut y = x - 1;
+
+        loop {
+            let n = base - y * y;
+
+            if n > upper {
+                break;
+            }
+
+            if n % 12 == 11 {
+                toggle(n);
+            }
+
+            if y <= 2 {
+                break;
+            }
+
+            y -= 2;
+        }
+
+        x += 1;
+    }
+
+    // The forms flag squarefree numbers with an odd number of
+    // representations, so multiples of the squares of primes remain.
+    let mut r = 5;
+
+    while r * r <= upper {
+        let i = r / 2;
+
+        if bits[i / 64] & (1 << (i % 64)) != 0 {
+            // Only odd multiples of `r²` are in the sieve.
+            clear_multiples(&mut bits, r * r / 2, r * r);
+        }
+
+        r += 2;
+    }
+
+    if upper >= 3 {
+        bits[0] |= 1 << 1;
+    }
+
+    Flat::from_bits(bits, upper)
+}
+
+impl<I> Iterator for Atkin<I>
+where
+    I: TryFrom<u64>,
+{
+    type Item = I;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        I::try_from(self.iter.next()?).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::atkin;
+
+    #[test]
+    fn matches_bounded() {
+        for upper in 0..=2000u32 {
+            assert!(atkin(upper).eq(crate::bounded(upper)), "{}", upper);
+        }
+
+        assert!(atkin(1_000_000u64).eq(crate::bounded(1_000_000u64)));
+    }
+
+    #[test]
+    fn prime_counts() {
+        assert_eq!(atkin(10_000_000u64).count(), 664_579);
+        assert_eq!(atkin(100_000_000u64).count(), 5_761_455);
+        assert_eq!(atkin(99_999_989u64).last(), Some(99_999_989));
+        assert_eq!(atkin(99_999_988u64).last(), Some(99_999_971));
+    }
+}
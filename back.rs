@@ -0,0 +1,103 @@
+//! Sieving primes from the top of a bounded sieve downwards, which lets it be
+//! iterated from both ends.
+
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::flat::{isqrt, Segment};
+use crate::segmented::SEGMENT_LEN;
+use crate::small;
+
+/// The back end of a bounded sieve, which sieves fixed-size segments from the
+/// bound downwards and yields their primes in decreasing order.
+#[derive(Clone)]
+pub(crate) struct Back {
+    /// Odd base primes up to the square root of the bound.
+    primes: Vec<u64>,
+    segment: Segment,
+    /// The top of the next segment to sieve, or `None` once every segment down
+    /// to 2 has been sieved.
+    next: Option<u64>,
+    /// The number of primes popped.
+    popped: usize,
+    /// The smallest prime popped.
+    lowest: Option<u64>,
+}
+
+impl Back {
+    /// Construct the back end of a sieve whose primes go up to `upper`.
+    pub(crate) fn new(upper: u64) -> Self {
+        let primes = small::odd_primes(isqrt(upper));
+
+        Self {
+            primes,
+            segment: Segment::new(),
+            next: Some(upper),
+            popped: 0,
+            lowest: None,
+        }
+    }
+
+    /// Get the number of primes popped.
+    #[inline]
+    pub(crate) fn popped(&self) -> usize {
+        self.popped
+    }
+
+    /// Get the smallest prime popped, below which the front of the sieve is
+    /// confined.
+    #[inline]
+    pub(crate) fn lowest(&self) -> Option<u64> {
+        self.lowest
+    }
+
+    /// Get the number of bytes allocated for the back end.
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.primes.capacity() * mem::size_of::<u64>() + self.segment.memory_usage()
+    }
+
+    /// Remove and return the largest remaining prime which is at least
+    /// `floor`, which marks where the front of the sieve has reached.
+    pub(crate) fn pop(&mut self, floor: u64) -> Option<u64> {
+        loop {
+            if let Some(p) = self.segment.pop_last() {
+                // Everything else in the segment is smaller still.
+                if p < floor {
+                    return None;
+                }
+
+                self.popped += 1;
+                self.lowest = Some(p);
+                return Some(p);
+            }
+
+            let hi = match self.next {
+                Some(hi) if hi >= floor => hi,
+                _ => return None,
+            };
+
+            // The segments only cover odd numbers, so 2 is handled last.
+            if hi < 3 {
+                self.next = None;
+
+                if hi < 2 {
+                    return None;
+                }
+
+                self.popped += 1;
+                self.lowest = Some(2);
+                return Some(2);
+            }
+
+            let lo = hi.saturating_sub(2 * SEGMENT_LEN - 1).max(floor).max(3) | 1;
+
+            if lo > hi {
+                self.next = None;
+                return None;
+            }
+
+            self.segment.sieve(lo, hi, &self.primes);
+            self.next = Some(lo - 1);
+        }
+    }
+}
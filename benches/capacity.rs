@@ -0,0 +1,29 @@
+//! Compare finding the millionth prime with a composite map which has been
+//! sized up front against one which grows as it goes.
+//!
+//! Run with `cargo bench --bench capacity`.
+
+use std::time::{Duration, Instant};
+
+const PRIMES: usize = 1_000_000;
+const ROUNDS: usize = 5;
+
+fn bench(name: &str, sieve: impl Fn() -> sieve::Sieve<u64, std::ops::RangeFrom<u64>>) {
+    let mut best = Duration::MAX;
+    let mut last = None;
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        last = sieve().nth(PRIMES - 1);
+        best = best.min(start.elapsed());
+    }
+
+    println!("{:>14}: {:?} (prime #{} = {:?})", name, best, PRIMES, last);
+}
+
+fn main() {
+    bench("infinite", sieve::infinite::<u64>);
+    bench("with_capacity", || {
+        sieve::infinite_with_capacity::<u64>(PRIMES)
+    });
+}
@@ -0,0 +1,34 @@
+//! Compare collecting the primes up to a bound from the incremental sieve,
+//! the segmented sieve and `collect_primes_below`.
+//!
+//! Run with `cargo bench --bench collect`.
+
+use std::time::{Duration, Instant};
+
+const UPPER: u64 = 100_000_000;
+const ROUNDS: usize = 3;
+
+fn bench(name: &str, collect: impl Fn(u64) -> Vec<u64>) {
+    let mut best = Duration::MAX;
+    let mut primes = Vec::new();
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        primes = collect(UPPER);
+        best = best.min(start.elapsed());
+    }
+
+    println!(
+        "{:>14}: {:?} ({} primes, capacity {})",
+        name,
+        best,
+        primes.len(),
+        primes.capacity()
+    );
+}
+
+fn main() {
+    bench("bounded", |upper| sieve::bounded(upper).collect());
+    bench("segmented", |upper| sieve::segmented(upper).collect());
+    bench("collect_below", sieve::collect_primes_below);
+}
@@ -0,0 +1,34 @@
+//! Compare factorizing consecutive integers through a sieve which keeps its
+//! primes around against standalone factorization.
+//!
+//! Run with `cargo bench --bench factorize`.
+
+use std::time::{Duration, Instant};
+
+const START: u64 = 100_000_000;
+const COUNT: u64 = 10_000;
+const ROUNDS: usize = 5;
+
+fn bench(name: &str, mut factorize: impl FnMut(u64) -> Vec<(u64, u32)>) {
+    let mut best = Duration::MAX;
+    let mut factors = 0;
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        factors = (START..START + COUNT)
+            .map(|n| factorize(n).len())
+            .sum::<usize>();
+        best = best.min(start.elapsed());
+    }
+
+    println!("{:>14}: {:?} ({} distinct factors)", name, best, factors);
+}
+
+fn main() {
+    bench("factorize", sieve::factorize);
+
+    bench("factorize_with", {
+        let mut sieve = sieve::infinite::<u64>();
+        move |n| sieve.factorize_with(n)
+    });
+}
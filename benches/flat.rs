@@ -0,0 +1,70 @@
+//! Compare the flat bit sieve behind `prime_count`, which clears the
+//! multiples of small primes a word at a time, against clearing them one bit
+//! at a time and against the sieve of Atkin.
+//!
+//! Run with `cargo bench --bench flat`.
+
+use std::time::{Duration, Instant};
+
+const UPPER: usize = 100_000_000;
+const ROUNDS: usize = 5;
+
+/// Count the primes up to `upper` with a flat sieve over the odd numbers,
+/// clearing one bit at a time.
+fn scalar(upper: usize) -> usize {
+    let len = upper / 2 + upper % 2;
+    let mut bits = vec![!0u64; (len + 63) / 64];
+
+    if len % 64 != 0 {
+        if let Some(last) = bits.last_mut() {
+            *last = (1u64 << (len % 64)) - 1;
+        }
+    }
+
+    bits[0] &= !1;
+
+    let mut i = 1;
+
+    loop {
+        let p = 2 * i + 1;
+
+        if p > upper / p {
+            break;
+        }
+
+        if bits[i / 64] & (1 << (i % 64)) != 0 {
+            let mut j = p * p / 2;
+
+            while j < len {
+                bits[j / 64] &= !(1 << (j % 64));
+                j += p;
+            }
+        }
+
+        i += 1;
+    }
+
+    bits.iter()
+        .map(|word| word.count_ones() as usize)
+        .sum::<usize>()
+        + 1
+}
+
+fn bench(name: &str, count: impl Fn(usize) -> usize) {
+    let mut best = Duration::MAX;
+    let mut primes = 0;
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        primes = count(UPPER);
+        best = best.min(start.elapsed());
+    }
+
+    println!("{:>8}: {:?} ({} primes)", name, best, primes);
+}
+
+fn main() {
+    bench("scalar", scalar);
+    bench("strided", |upper| sieve::prime_count(upper as u64));
+    bench("atkin", |upper| sieve::atkin(upper as u64).count());
+}
@@ -0,0 +1,34 @@
+//! Compare the hashers available for the composite map by finding the
+//! millionth prime.
+//!
+//! Run with `cargo bench --bench hashers`.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, BuildHasherDefault};
+use std::time::{Duration, Instant};
+
+use sieve::FxHasher;
+
+const PRIMES: usize = 1_000_000;
+const ROUNDS: usize = 5;
+
+fn bench<S>(name: &str, hasher: impl Fn() -> S)
+where
+    S: BuildHasher,
+{
+    let mut best = Duration::MAX;
+    let mut last = None;
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        last = sieve::infinite_with_hasher::<u64, _>(hasher()).nth(PRIMES - 1);
+        best = best.min(start.elapsed());
+    }
+
+    println!("{:>12}: {:?} (prime #{} = {:?})", name, best, PRIMES, last);
+}
+
+fn main() {
+    bench("RandomState", RandomState::new);
+    bench("FxHasher", BuildHasherDefault::<FxHasher>::default);
+}
@@ -0,0 +1,32 @@
+//! Time feeding the first million primes through the incremental sieve one at
+//! a time, which is dominated by traffic to the composite map.
+//!
+//! Unlike `Iterator::nth`, taking the primes one by one never skips ahead in
+//! bulk. Run with `cargo bench --bench incremental`.
+
+use std::time::{Duration, Instant};
+
+const PRIMES: usize = 1_000_000;
+const ROUNDS: usize = 5;
+
+fn bench<I>(name: &str, sieve: impl Fn() -> I)
+where
+    I: Iterator,
+    I::Item: std::fmt::Debug,
+{
+    let mut best = Duration::MAX;
+    let mut last = None;
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        last = sieve().take(PRIMES).last();
+        best = best.min(start.elapsed());
+    }
+
+    println!("{:>8}: {:?} (prime #{} = {:?})", name, best, PRIMES, last);
+}
+
+fn main() {
+    bench("u32", sieve::infinite::<u32>);
+    bench("u64", sieve::infinite::<u64>);
+}
@@ -0,0 +1,32 @@
+//! Compare collecting every prime below a large bound on a single thread
+//! against doing it on an increasing number of threads.
+//!
+//! Run with `cargo bench --bench parallel`.
+
+use std::time::{Duration, Instant};
+
+const UPPER: u32 = 1_000_000_000;
+const ROUNDS: usize = 3;
+
+fn bench(name: &str, collect: impl Fn() -> Vec<u32>) {
+    let mut best = Duration::MAX;
+    let mut count = 0;
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        count = collect().len();
+        best = best.min(start.elapsed());
+    }
+
+    println!("{:>12}: {:?} ({} primes)", name, best, count);
+}
+
+fn main() {
+    bench("segmented", || sieve::segmented(UPPER).collect());
+
+    for &threads in &[1, 2, 4, 8] {
+        bench(&format!("{} threads", threads), || {
+            sieve::par_collect_bounded(UPPER, threads)
+        });
+    }
+}
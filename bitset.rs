@@ -0,0 +1,228 @@
+//! A bounded sieve backed by a flat bit array.
+
+use core::convert::TryFrom;
+use core::marker;
+
+use crate::flat::{self, Flat};
+use crate::FromSmall;
+
+/// An iterator over the primes in a bit array.
+///
+/// See [`bounded_bitset()`].
+pub struct Bitset<I> {
+    iter: flat::IntoIter,
+    _marker: marker::PhantomData<I>,
+}
+
+/// Construct a bounded sieve which yields the same primes as
+/// [`bounded()`][crate::bounded()], but sieves them up front into a bit array
+/// with one bit per odd number.
+///
+/// This takes `upper / 16` bytes and is an order of magnitude faster than the
+/// incremental sieve, at the cost of doing all of the sieving before the first
+/// prime is produced. Iterating then scans the array a word at a time.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `usize`.
+///
+/// # Examples
+///
+/// ```
+/// assert!(sieve::bounded_bitset(30u32).eq([2, 3, 5, 7, 11, 13, 17, 19, 23, 29]));
+/// assert_eq!(sieve::bounded_bitset(1_000_000u64).count(), 78_498);
+/// ```
+pub fn bounded_bitset<I>(upper: I) -> Bitset<I>
+where
+    I: Copy + Ord + FromSmall,
+    usize: TryFrom<I>,
+{
+    let upper = if upper < I::from_small(2) {
+        0
+    } else {
+        match usize::try_from(upper) {
+            Ok(upper) => upper,
+            Err(..) => panic!("bound doesn't fit in a usize"),
+        }
+    };
+
+    Bitset {
+        iter: Flat::new(upper).into_iter(),
+        _marker: marker::PhantomData,
+    }
+}
+
+impl<I> Iterator for Bitset<I>
+where
+    I: TryFrom<u64>,
+{
+    type Item = I;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        I::try_from(self.iter.next()?).ok()
+    }
+}
+
+/// A table of which numbers up to some bound are prime, stored as one bit per
+/// odd number.
+///
+/// See [`composite_flags()`].
+#[derive(Clone)]
+pub struct PrimalityMap {
+    flat: Flat,
+}
+
+/// Sieve a table of which numbers in `0..=upper` are prime, for algorithms
+/// which test primality by index many times over.
+///
+/// This uses the same flat bit array as [`bounded_bitset()`], taking
+/// `upper / 16` bytes, but keeps it around so that each lookup is a single bit
+/// test rather than a membership test against a vector or a hash map.
+///
+/// # Examples
+///
+/// ```
+/// let map = sieve::composite_flags(100);
+///
+/// assert_eq!(map.len(), 101);
+/// assert!(map.is_prime(97));
+/// assert!(!map.is_prime(91));
+/// assert!(!map.is_prime(0));
+/// assert!(!map.is_prime(1));
+/// assert!(map.is_prime(2));
+///
+/// assert_eq!(map.iter_primes().count(), 25);
+/// ```
+pub fn composite_flags(upper: usize) -> PrimalityMap {
+    PrimalityMap {
+        flat: Flat::new(upper),
+    }
+}
+
+impl PrimalityMap {
+    /// Test if `n` is prime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is beyond the bound of the table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let map = sieve::composite_flags(30);
+    ///
+    /// let primes = (0..map.len()).filter(|&n| map.is_prime(n));
+    /// assert!(primes.eq([2, 3, 5, 7, 11, 13, 17, 19, 23, 29]));
+    /// ```
+    #[inline]
+    pub fn is_prime(&self, n: usize) -> bool {
+        let upper = self.flat.upper();
+
+        if n > upper {
+            panic!("{} is beyond the bound {} of the primality map", n, upper);
+        }
+
+        self.flat.contains(n)
+    }
+
+    /// Get the number of numbers in the table, which is one more than its
+    /// bound since it starts at 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(sieve::composite_flags(0).len(), 1);
+    /// assert_eq!(sieve::composite_flags(1000).len(), 1001);
+    /// ```
+    // The table always holds 0, so it's never empty.
+    #[allow(clippy::len_without_is_empty)]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.flat.upper() + 1
+    }
+
+    /// Iterate over the primes in the table in increasing order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let map = sieve::composite_flags(1_000_000);
+    ///
+    /// assert!(map.iter_primes().take(5).eq([2, 3, 5, 7, 11]));
+    /// assert_eq!(map.iter_primes().last(), Some(999_983));
+    /// ```
+    #[inline]
+    pub fn iter_primes(&self) -> PrimalityMapIter<'_> {
+        PrimalityMapIter {
+            iter: self.flat.iter(),
+        }
+    }
+}
+
+/// An iterator over the primes in a [`PrimalityMap`].
+///
+/// See [`PrimalityMap::iter_primes`].
+pub struct PrimalityMapIter<'a> {
+    iter: flat::IntoIter<&'a [u64]>,
+}
+
+impl Iterator for PrimalityMapIter<'_> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.iter.next()? as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{bounded_bitset, composite_flags};
+
+    #[test]
+    fn matches_bounded() {
+        for upper in 0..=2000u32 {
+            assert!(bounded_bitset(upper).eq(crate::bounded(upper)), "{}", upper);
+        }
+
+        for shift in 1..22 {
+            let upper = 1u64 << shift;
+
+            for upper in upper - 1..=upper + 1 {
+                assert!(bounded_bitset(upper).eq(crate::bounded(upper)), "{}", upper);
+            }
+        }
+
+        assert!(bounded_bitset(-5i64).eq(crate::bounded(-5i64)));
+    }
+
+    #[test]
+    fn composite_flags_matches_bounded() {
+        let map = composite_flags(100_000);
+        let primes = crate::bounded(100_000usize).collect::<Vec<_>>();
+
+        for n in 0..=100_000 {
+            assert_eq!(map.is_prime(n), primes.binary_search(&n).is_ok(), "{}", n);
+        }
+
+        assert!(map.iter_primes().eq(primes.iter().copied()));
+        assert_eq!(map.len(), 100_001);
+
+        for upper in 0..=200 {
+            let map = composite_flags(upper);
+            let expected = (0..=upper).filter(|&n| crate::bounded(upper).any(|p| p == n));
+
+            assert!((0..map.len()).filter(|&n| map.is_prime(n)).eq(expected));
+            assert!(map.iter_primes().eq(crate::bounded(upper)), "{}", upper);
+        }
+    }
+
+    #[test]
+    #[should_panic = "4 is beyond the bound 3 of the primality map"]
+    fn composite_flags_out_of_bounds() {
+        composite_flags(3).is_prime(4);
+    }
+}
@@ -0,0 +1,82 @@
+//! Collecting primes straight into vectors.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::flat::Flat;
+use crate::FromSmall;
+
+/// Collect every prime up to and including `upper` into a vector in
+/// increasing order.
+///
+/// The result is the same as collecting [`bounded()`][crate::bounded()], but
+/// the primes are sieved into a flat bit array like
+/// [`prime_count()`][crate::prime_count()] and pushed straight into the
+/// vector. Since the bits can be counted before any primes are produced, the
+/// vector is allocated with exactly the capacity it needs.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `usize`.
+///
+/// # Examples
+///
+/// ```
+/// let primes = sieve::collect_primes_below(100u32);
+/// assert_eq!(primes.len(), 25);
+/// assert_eq!(primes.capacity(), 25);
+/// assert_eq!(primes[..5], [2, 3, 5, 7, 11]);
+/// assert_eq!(primes.last(), Some(&97));
+///
+/// // The bound is included, like with `bounded()`.
+/// assert_eq!(sieve::collect_primes_below(97u32).last(), Some(&97));
+/// ```
+pub fn collect_primes_below<I>(upper: I) -> Vec<I>
+where
+    I: Copy + Ord + FromSmall + TryFrom<u64>,
+    usize: TryFrom<I>,
+{
+    if upper < I::from_small(2) {
+        return Vec::new();
+    }
+
+    let upper = match usize::try_from(upper) {
+        Ok(upper) => upper,
+        Err(..) => panic!("bound doesn't fit in a usize"),
+    };
+
+    let flat = Flat::new(upper);
+    let mut primes = Vec::with_capacity(flat.count());
+    primes.extend(flat.into_iter().filter_map(|p| I::try_from(p).ok()));
+    primes
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::collect_primes_below;
+
+    #[test]
+    fn matches_bounded() {
+        for upper in 0..=2000u32 {
+            let expected = crate::bounded(upper).collect::<Vec<_>>();
+            let actual = collect_primes_below(upper);
+            assert_eq!(actual, expected, "{}", upper);
+            assert_eq!(actual.capacity(), actual.len(), "{}", upper);
+        }
+
+        let expected = crate::segmented(10_000_000u64).collect::<Vec<_>>();
+        let actual = collect_primes_below(10_000_000u64);
+        assert_eq!(actual, expected);
+        assert_eq!(actual.capacity(), actual.len());
+    }
+
+    #[test]
+    fn small_bounds() {
+        assert!(collect_primes_below(-5i32).is_empty());
+        assert!(collect_primes_below(1u32).is_empty());
+        assert_eq!(collect_primes_below(2u32), [2]);
+        assert_eq!(collect_primes_below(255u8).len(), 54);
+    }
+}
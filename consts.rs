@@ -0,0 +1,86 @@
+//! Primes computed in const contexts.
+
+/// Get the first `K` primes in increasing order, in a way which can be
+/// evaluated at compile time.
+///
+/// This uses trial division by the primes found so far, since const
+/// evaluation can't use the sieves, so it's only suitable for modest tables
+/// of up to a few thousand primes. It produces the same primes as
+/// [`infinite()`][crate::infinite()].
+///
+/// # Panics
+///
+/// Panics if a prime doesn't fit in a `u32`, which happens once `K` is larger
+/// than 203,280,221. When evaluated in a const context this is a compile
+/// error, as is running out of steps for a large `K`.
+///
+/// # Examples
+///
+/// ```
+/// const PRIMES: [u32; 100] = sieve::const_first::<100>();
+///
+/// assert_eq!(PRIMES[..5], [2, 3, 5, 7, 11]);
+/// assert_eq!(PRIMES[99], 541);
+///
+/// const EMPTY: [u32; 0] = sieve::const_first::<0>();
+/// assert_eq!(EMPTY, []);
+/// ```
+pub const fn const_first<const K: usize>() -> [u32; K] {
+    let mut primes = [0u32; K];
+
+    if K == 0 {
+        return primes;
+    }
+
+    primes[0] = 2;
+
+    let mut len = 1;
+    let mut n = 3u32;
+
+    while len < K {
+        let mut i = 1;
+        let mut prime = true;
+
+        while i < len {
+            let p = primes[i];
+
+            if p > n / p {
+                break;
+            }
+
+            if n % p == 0 {
+                prime = false;
+                break;
+            }
+
+            i += 1;
+        }
+
+        if prime {
+            primes[len] = n;
+            len += 1;
+        }
+
+        n += 2;
+    }
+
+    primes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::const_first;
+
+    const PRIMES: [u32; 2000] = const_first::<2000>();
+
+    #[test]
+    fn matches_sieve() {
+        assert!(PRIMES
+            .iter()
+            .copied()
+            .eq(crate::infinite::<u32>().take(2000)));
+        assert_eq!(const_first::<1>(), [2]);
+        assert_eq!(const_first::<2>(), [2, 3]);
+        assert_eq!(const_first::<3>(), [2, 3, 5]);
+    }
+}
@@ -0,0 +1,598 @@
+//! Functions for counting and indexing primes.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::flat::{isqrt, Flat};
+use crate::math::ln;
+use crate::FromSmall;
+
+/// Get the `n`th prime, counting from one.
+///
+/// Instead of driving the incremental sieve, this sieves a flat bit array up
+/// to [`nth_prime_upper_bound()`]. This makes it fast even for large `n`.
+///
+/// Returns `None` if `n` is zero, or if the prime doesn't fit in `I`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::nth_prime::<u32>(0), None);
+/// assert_eq!(sieve::nth_prime::<u32>(1), Some(2));
+/// assert_eq!(sieve::nth_prime::<u32>(25), Some(97));
+/// assert_eq!(sieve::nth_prime::<u64>(10_000), Some(104_729));
+///
+/// // The 55th prime is 257, which doesn't fit in a `u8`.
+/// assert_eq!(sieve::nth_prime::<u8>(54), Some(251));
+/// assert_eq!(sieve::nth_prime::<u8>(55), None);
+/// ```
+pub fn nth_prime<I>(n: usize) -> Option<I>
+where
+    I: TryFrom<u64>,
+{
+    if n == 0 {
+        return None;
+    }
+
+    if let Some(&prime) = crate::SMALL_PRIMES.get(n - 1) {
+        return I::try_from(u64::from(prime)).ok();
+    }
+
+    let n = n as u64;
+
+    // Cheaply rule out types which can't fit the result before allocating a
+    // sieve.
+    I::try_from(nth_prime_lower_bound(n)).ok()?;
+
+    let upper = usize::try_from(nth_prime_upper_bound(n)).ok()?;
+    let prime = Flat::new(upper).nth(n as usize - 1)?;
+    I::try_from(prime as u64).ok()
+}
+
+/// Get a lower bound on the `n`th prime, counting from one.
+///
+/// Up to the 6542nd prime this is the prime itself, taken from
+/// [`SMALL_PRIMES`][crate::SMALL_PRIMES]. Past that it's Dusart's bound
+/// `n (ln n + ln ln n - 1)`, which holds for all `n >= 2`. The zeroth prime
+/// doesn't exist, so its bound is 0.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::nth_prime_lower_bound(0), 0);
+/// assert_eq!(sieve::nth_prime_lower_bound(1), 2);
+/// assert_eq!(sieve::nth_prime_lower_bound(25), 97);
+///
+/// let lower = sieve::nth_prime_lower_bound(1_000_000);
+/// assert!(lower <= 15_485_863 && lower > 15_000_000);
+/// ```
+pub fn nth_prime_lower_bound(n: u64) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+
+    if let Some(&prime) = small_nth(n) {
+        return u64::from(prime);
+    }
+
+    let x = n as f64;
+    let l = ln(x);
+    (x * (l + ln(l) - 1.0)) as u64
+}
+
+/// Get an upper bound on the `n`th prime, counting from one.
+///
+/// Up to the 6542nd prime this is the prime itself, taken from
+/// [`SMALL_PRIMES`][crate::SMALL_PRIMES]. Past that it's Rosser's bound
+/// `n (ln n + ln ln n)`, which holds for all `n >= 6`. Bounds which don't fit
+/// in a `u64` saturate to `u64::MAX`, and the zeroth prime doesn't exist, so
+/// its bound is 0.
+///
+/// This is the bound [`nth_prime()`] sizes its sieve with.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::nth_prime_upper_bound(0), 0);
+/// assert_eq!(sieve::nth_prime_upper_bound(1), 2);
+/// assert_eq!(sieve::nth_prime_upper_bound(25), 97);
+///
+/// let upper = sieve::nth_prime_upper_bound(1_000_000);
+/// assert!(upper >= 15_485_863 && upper < 16_500_000);
+/// ```
+pub fn nth_prime_upper_bound(n: u64) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+
+    if let Some(&prime) = small_nth(n) {
+        return u64::from(prime);
+    }
+
+    let x = n as f64;
+    let l = ln(x);
+    let upper = x * (l + ln(l));
+    let ceil = upper as u64;
+
+    if (ceil as f64) < upper {
+        ceil.saturating_add(1)
+    } else {
+        ceil
+    }
+}
+
+/// Look up the `n`th prime, counting from one, in the table of small primes.
+fn small_nth(n: u64) -> Option<&'static u16> {
+    crate::SMALL_PRIMES.get(usize::try_from(n - 1).ok()?)
+}
+
+/// Count the number of primes less than or equal to `upper`, also known as
+/// `π(upper)`.
+///
+/// This is equivalent to `sieve::bounded(upper).count()`, but uses a flat bit
+/// sieve instead of the incremental one, so it runs in a fraction of the time
+/// and memory.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `usize`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::prime_count(0u32), 0);
+/// assert_eq!(sieve::prime_count(2u32), 1);
+/// assert_eq!(sieve::prime_count(100u32), 25);
+/// assert_eq!(sieve::prime_count(1_000_000u64), 78_498);
+/// ```
+pub fn prime_count<I>(upper: I) -> usize
+where
+    I: Copy + Ord + FromSmall,
+    usize: TryFrom<I>,
+{
+    if upper < I::from_small(2) {
+        return 0;
+    }
+
+    let upper = match usize::try_from(upper) {
+        Ok(upper) => upper,
+        Err(..) => panic!("bound doesn't fit in a usize"),
+    };
+
+    Flat::new(upper).count()
+}
+
+/// Count the number of primes less than or equal to `x` exactly, without
+/// sieving all the way up to it.
+///
+/// This uses the Lucy_Hedgehog method, which only keeps track of `π(v)` for
+/// the `2 * sqrt(x)` distinct values `v = x / i`. Starting out with every
+/// number in `2..=v` counted, the multiples of each prime up to `sqrt(x)` are
+/// then struck from all of them at once. That takes `O(x^(3/4))` time and
+/// `O(sqrt(x))` memory, which puts counts up to `10^12` and beyond within
+/// reach where [`prime_count()`] would need terabytes.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::prime_pi(0), 0);
+/// assert_eq!(sieve::prime_pi(100), 25);
+/// assert_eq!(sieve::prime_pi(1_000_000), 78_498);
+/// assert_eq!(sieve::prime_pi(10_000_000_000), 455_052_511);
+/// ```
+pub fn prime_pi(x: u64) -> u64 {
+    if x < 2 {
+        return 0;
+    }
+
+    let r = isqrt(x) as usize;
+
+    // `small[v]` counts the survivors in `2..=v`, and `large[i]` those in
+    // `2..=x / i`, both for `v, i <= sqrt(x)`.
+    let mut small = (0..=r as u64)
+        .map(|v| v.saturating_sub(1))
+        .collect::<Vec<_>>();
+    let mut large = (0..=r as u64)
+        .map(|i| x.checked_div(i).map_or(0, |v| v - 1))
+        .collect::<Vec<_>>();
+
+    for p in 2..=r {
+        // Nothing was struck from `p` by a smaller prime, so it's prime.
+        if small[p] == small[p - 1] {
+            continue;
+        }
+
+        let below = small[p - 1];
+        let square = (p * p) as u64;
+        let end = r.min((x / square) as usize);
+
+        // Strike the multiples `p * m` in `p^2..=v`, where `m` survived all
+        // primes below `p`, which are counted by `π(v / p) - π(p - 1)`.
+        for i in 1..=end {
+            let d = i * p;
+
+            let count = if d <= r {
+                large[d]
+            } else {
+                small[(x / d as u64) as usize]
+            };
+
+            large[i] -= count - below;
+        }
+
+        for v in (p * p..=r).rev() {
+            small[v] -= small[v / p] - below;
+        }
+    }
+
+    large[1]
+}
+
+/// Get the sum of the primes less than or equal to `n`.
+///
+/// The primes are drawn from a [`segmented()`][crate::segmented()] sieve and
+/// summed into a `u128`, which can't overflow since the sum of every prime
+/// which fits in a `u64` is less than `2^123`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::sum_of_primes_below(1), 0);
+/// assert_eq!(sieve::sum_of_primes_below(10), 17);
+/// assert_eq!(sieve::sum_of_primes_below(2_000_000), 142_913_828_922);
+/// ```
+pub fn sum_of_primes_below(n: u64) -> u128 {
+    crate::segmented(n).map(u128::from).sum()
+}
+
+/// Get the sum of the primes less than or equal to `n`, modulo `m`.
+///
+/// This is the same as taking [`sum_of_primes_below()`] modulo `m`.
+///
+/// # Panics
+///
+/// Panics if `m` is zero.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::sum_of_primes_below_mod(10, 7), 3);
+/// assert_eq!(sieve::sum_of_primes_below_mod(2_000_000, 1_000_000_007), 913_827_928);
+/// ```
+pub fn sum_of_primes_below_mod(n: u64, m: u64) -> u64 {
+    assert!(m != 0, "modulus is zero");
+    let m = u128::from(m);
+
+    // Reducing every prime keeps the sum within a `u128` regardless of how
+    // many there are.
+    let sum = crate::segmented(n).fold(0, |sum, p| (sum + u128::from(p)) % m);
+    sum as u64
+}
+
+/// Estimate the number of primes less than or equal to `x` using the
+/// logarithmic integral `li(x)`.
+///
+/// This is the approximation from the prime number theorem, which is far
+/// more accurate than `x / ln x`: its relative error is well below a
+/// thousandth once `x` is in the millions. For `x` less than 2 there are no
+/// primes and the estimate is 0.
+///
+/// # Examples
+///
+/// ```
+/// let estimate = sieve::prime_count_estimate(1e6);
+/// assert!((estimate - 78_498.0).abs() < 150.0);
+///
+/// let estimate = sieve::prime_count_estimate(1e18);
+/// assert!((estimate / 24_739_954_287_740_860.0 - 1.0).abs() < 1e-8);
+/// ```
+pub fn prime_count_estimate(x: f64) -> f64 {
+    if x.is_nan() || x < 2.0 {
+        return 0.0;
+    }
+
+    if x.is_infinite() {
+        return x;
+    }
+
+    // The series `li(x) = γ + ln ln x + Σ (ln x)^n / (n * n!)`, whose terms
+    // are all positive so it doesn't suffer from cancellation.
+    const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+    let l = ln(x);
+    let mut term = 1.0;
+    let mut sum = 0.0;
+    let mut n = 1.0;
+
+    loop {
+        term *= l / n;
+        let next = sum + term / n;
+
+        if next == sum {
+            break;
+        }
+
+        sum = next;
+        n += 1.0;
+    }
+
+    EULER_GAMMA + ln(l) + sum
+}
+
+/// Get a lower bound on the number of primes less than or equal to `x`.
+///
+/// This uses the explicit bounds proven by Dusart, which are the same the
+/// crate uses to estimate the length of bounded sieves.
+///
+/// # Examples
+///
+/// ```
+/// let count = sieve::prime_count(1_000_000u64) as u64;
+///
+/// assert!(sieve::prime_count_lower_bound(1_000_000) <= count);
+/// assert!(sieve::prime_count_upper_bound(1_000_000) >= count);
+/// ```
+pub fn prime_count_lower_bound(x: u64) -> u64 {
+    let (lower, _) = prime_count_bounds(x as f64);
+    lower as u64
+}
+
+/// Get an upper bound on the number of primes less than or equal to `x`.
+///
+/// Like [`prime_count_lower_bound()`], this uses the bounds proven by Dusart.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::prime_count_upper_bound(1), 0);
+/// assert!(sieve::prime_count_upper_bound(100) >= 25);
+/// ```
+pub fn prime_count_upper_bound(x: u64) -> u64 {
+    let (_, upper) = prime_count_bounds(x as f64);
+    let floor = upper as u64;
+
+    if (floor as f64) < upper {
+        floor.saturating_add(1)
+    } else {
+        floor
+    }
+}
+
+/// Get lower and upper bounds on `π(x)`.
+///
+/// These are the explicit bounds proven by Dusart, which are within a percent
+/// of the real count once `x` is in the millions. Below the range where those
+/// hold, the cruder `x / ln x < π(x) < 1.25506 x / ln x` is used instead.
+pub(crate) fn prime_count_bounds(x: f64) -> (f64, f64) {
+    if x < 2.0 {
+        return (0.0, 0.0);
+    }
+
+    let l = ln(x);
+    let base = x / l;
+
+    let lower = if x >= 599.0 {
+        base * (1.0 + 1.0 / l)
+    } else if x >= 17.0 {
+        base
+    } else {
+        0.0
+    };
+
+    let upper = if x >= 355_991.0 {
+        base * (1.0 + 1.0 / l + 2.51 / (l * l))
+    } else {
+        1.25506 * base
+    };
+
+    (lower, upper)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        nth_prime, nth_prime_lower_bound, nth_prime_upper_bound, prime_count, prime_count_bounds,
+        prime_count_estimate, prime_count_lower_bound, prime_count_upper_bound, prime_pi,
+        sum_of_primes_below, sum_of_primes_below_mod,
+    };
+    use crate::flat::Flat;
+
+    #[test]
+    fn known_values() {
+        assert_eq!(nth_prime::<u64>(10_000), Some(104_729));
+        assert_eq!(nth_prime::<u64>(100_000), Some(1_299_709));
+        assert_eq!(nth_prime::<u64>(1_000_000), Some(15_485_863));
+        assert_eq!(nth_prime::<u16>(1_000_000), None);
+    }
+
+    #[test]
+    fn nth_prime_bounds() {
+        for (n, prime) in crate::infinite::<u64>().take(20_000).enumerate() {
+            let n = n as u64 + 1;
+            assert!(nth_prime_lower_bound(n) <= prime, "{}", n);
+            assert!(nth_prime_upper_bound(n) >= prime, "{}", n);
+        }
+
+        // The 10,000,000th prime is 179,424,673.
+        let flat = Flat::new(179_424_673);
+        let mut n = 10;
+
+        while n <= 10_000_000 {
+            for &n in &[n, n * 2, n * 5] {
+                if let Some(prime) = flat.nth(n - 1) {
+                    let (prime, n) = (prime as u64, n as u64);
+                    assert!(nth_prime_lower_bound(n) <= prime, "{}", n);
+                    assert!(nth_prime_upper_bound(n) >= prime, "{}", n);
+                }
+            }
+
+            n *= 10;
+        }
+
+        assert_eq!(flat.nth(9_999_999), Some(179_424_673));
+        assert_eq!(nth_prime_upper_bound(u64::MAX), u64::MAX);
+        assert!(nth_prime_lower_bound(u64::MAX) > 0);
+
+        for n in 0..=3 {
+            assert!(nth_prime_lower_bound(n) <= nth_prime_upper_bound(n));
+        }
+    }
+
+    #[test]
+    fn matches_infinite() {
+        for (n, prime) in crate::infinite::<u64>().take(2000).enumerate() {
+            assert_eq!(nth_prime::<u64>(n + 1), Some(prime), "{}", n + 1);
+        }
+    }
+
+    #[test]
+    fn prime_count_small() {
+        assert_eq!(prime_count(0u32), 0);
+        assert_eq!(prime_count(1u32), 0);
+        assert_eq!(prime_count(2u32), 1);
+        assert_eq!(prime_count(3u32), 2);
+        assert_eq!(prime_count(-10i64), 0);
+    }
+
+    #[test]
+    fn prime_count_matches_bounded() {
+        for state in crate::xorshift(0x2545_f491_4f6c_dd1d).take(50) {
+            let upper = state % 20_000;
+            assert_eq!(
+                prime_count(upper),
+                crate::bounded(upper).count(),
+                "{}",
+                upper
+            );
+        }
+    }
+
+    #[test]
+    fn prime_pi_matches_prime_count() {
+        for x in 0..=5000u64 {
+            assert_eq!(prime_pi(x) as usize, prime_count(x), "{}", x);
+        }
+
+        let mut x = 10_000u64;
+
+        while x <= 100_000_000 {
+            for &x in &[x - 1, x, x + 1, x * 3 + 7] {
+                assert_eq!(prime_pi(x) as usize, prime_count(x), "{}", x);
+            }
+
+            x *= 10;
+        }
+
+        // Squares of primes are where strikes start.
+        for &p in &[97u64, 101, 9973, 10_007] {
+            for x in p * p - 1..=p * p + 1 {
+                assert_eq!(prime_pi(x) as usize, prime_count(x), "{}", x);
+            }
+        }
+    }
+
+    #[test]
+    fn prime_pi_known_values() {
+        assert_eq!(prime_pi(1_000_000_000), 50_847_534);
+        assert_eq!(prime_pi((1 << 32) - 1), 203_280_221);
+    }
+
+    #[test]
+    #[ignore = "takes a long time, run with `cargo test -- --ignored`"]
+    fn prime_pi_large_known_values() {
+        assert_eq!(prime_pi(10_000_000_000), 455_052_511);
+        assert_eq!(prime_pi(100_000_000_000), 4_118_054_813);
+    }
+
+    #[test]
+    fn sums() {
+        for n in 0..=2000u64 {
+            let expected = crate::bounded(n).map(u128::from).sum::<u128>();
+            assert_eq!(sum_of_primes_below(n), expected, "{}", n);
+
+            for &m in &[1, 2, 7, 1_000_000_007, u64::MAX] {
+                let expected = (expected % u128::from(m)) as u64;
+                assert_eq!(sum_of_primes_below_mod(n, m), expected, "{} {}", n, m);
+            }
+        }
+
+        assert_eq!(sum_of_primes_below(2_000_000), 142_913_828_922);
+        assert_eq!(sum_of_primes_below(100_000_000), 279_209_790_387_276);
+    }
+
+    #[test]
+    fn bounds_hold() {
+        let mut count = 0.0;
+        let mut primes = Flat::new(2_000_000).odd_primes().into_iter().peekable();
+
+        for n in 0..=2_000_000u64 {
+            if n == 2 || primes.next_if_eq(&n).is_some() {
+                count += 1.0;
+            }
+
+            let (lower, upper) = prime_count_bounds(n as f64);
+            assert!(lower.floor() <= count && count <= upper.ceil(), "{}", n);
+        }
+
+        // Known values of `π(10^k)`.
+        for &(x, count) in &[
+            (1e9, 50_847_534.0),
+            (1e12, 37_607_912_018.0),
+            (1e18, 24_739_954_287_740_860.0),
+            (1e20, 2_220_819_602_560_918_840.0),
+        ] {
+            let (lower, upper) = prime_count_bounds(x);
+            assert!(lower <= count && count <= upper, "{}", x);
+            assert!(upper - lower < count * 0.01, "{}", x);
+        }
+    }
+
+    #[test]
+    fn public_bounds() {
+        let mut x = 1;
+
+        for _ in 0..=8 {
+            let count = prime_count(x) as u64;
+            assert!(prime_count_lower_bound(x) <= count, "{}", x);
+            assert!(prime_count_upper_bound(x) >= count, "{}", x);
+
+            if x >= 100_000 {
+                let estimate = prime_count_estimate(x as f64);
+                assert!(
+                    (estimate - count as f64).abs() < count as f64 * 0.01,
+                    "{}",
+                    x
+                );
+            }
+
+            x *= 10;
+        }
+
+        for x in 0..=10_000 {
+            let count = prime_count(x) as u64;
+            assert!(prime_count_lower_bound(x) <= count, "{}", x);
+            assert!(prime_count_upper_bound(x) >= count, "{}", x);
+        }
+
+        assert!(prime_count_upper_bound(u64::MAX) > prime_count_lower_bound(u64::MAX));
+    }
+
+    #[test]
+    fn estimate() {
+        assert_eq!(prime_count_estimate(0.0), 0.0);
+        assert_eq!(prime_count_estimate(-1.0), 0.0);
+        assert_eq!(prime_count_estimate(f64::NAN), 0.0);
+        assert_eq!(prime_count_estimate(f64::INFINITY), f64::INFINITY);
+
+        // Known values of `li(x)`.
+        for &(x, li) in &[
+            (2.0, 1.045_163_780_117_492),
+            (10.0, 6.165_599_504_787_297),
+            (1e6, 78_627.549_159_462_18),
+            (1e9, 50_849_234.957_123_38),
+        ] {
+            let estimate = prime_count_estimate(x);
+            assert!((estimate - li).abs() < li * 1e-10, "{} {}", x, estimate);
+        }
+    }
+}
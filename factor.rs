@@ -0,0 +1,653 @@
+//! Prime factorization.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::ops;
+
+use crate::flat::isqrt;
+use crate::primality::mul_mod;
+use crate::progression::gcd;
+use crate::{small, FromSmall, SieveInt};
+
+/// The number of steps of Pollard's rho whose differences are multiplied
+/// together before taking a single gcd.
+const RHO_BATCH: u64 = 128;
+
+/// Get the prime factorization of `n`, as pairs of primes and their exponents
+/// in increasing order.
+///
+/// This performs trial division by the primes up to `sqrt(n)`, which are taken
+/// from [`SMALL_PRIMES`][crate::SMALL_PRIMES] and beyond that produced lazily
+/// by an [`infinite()`][crate::infinite()] sieve, only as far as the remaining
+/// cofactor requires. Whatever is left once the primes
+/// exceed its square root is itself a prime factor.
+///
+/// Neither 0 nor 1 have any prime factors, so their factorization is empty,
+/// as is that of any negative number.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::factorize(360u32), [(2, 3), (3, 2), (5, 1)]);
+/// assert_eq!(sieve::factorize(1_000_000_007u64), [(1_000_000_007, 1)]);
+/// assert_eq!(sieve::factorize(1u32), []);
+/// assert_eq!(sieve::factorize(0u32), []);
+/// ```
+pub fn factorize<I>(n: I) -> Vec<(I, u32)>
+where
+    ops::RangeFrom<I>: Iterator<Item = I>,
+    I: SieveInt,
+{
+    // The table covers every divisor needed for numbers below 2^32, after
+    // which the sieve picks up where it leaves off.
+    let primes =
+        crate::small::primes::<I>().chain(crate::infinite::<I>().skip(crate::SMALL_PRIMES.len()));
+
+    divide(n, primes)
+}
+
+/// Get the radical of `n`, which is the product of its distinct prime
+/// factors.
+///
+/// This is built on [`factorize()`], multiplying each prime once regardless
+/// of its exponent. The radical of 1 is 1 since it has no prime factors, and
+/// that of 0 is taken to be 0. Like with [`factorize()`], negative numbers
+/// are treated as having no prime factors.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::radical(360u32), 30);
+/// assert_eq!(sieve::radical(1024u32), 2);
+/// assert_eq!(sieve::radical(30u32), 30);
+/// assert_eq!(sieve::radical(1u32), 1);
+/// assert_eq!(sieve::radical(0u32), 0);
+/// ```
+pub fn radical<I>(n: I) -> I
+where
+    ops::RangeFrom<I>: Iterator<Item = I>,
+    I: SieveInt,
+{
+    if n == I::from_small(0) {
+        return n;
+    }
+
+    factorize(n)
+        .into_iter()
+        .fold(I::from_small(1), |product, (p, _)| product * p)
+}
+
+/// Compute the Carmichael function `λ(n)`, which is the smallest `m` such that
+/// `a^m = 1 (mod n)` for every `a` coprime to `n`.
+///
+/// This is the least common multiple of `λ(p^k)` over the prime powers in the
+/// factorization of `n` from [`factorize()`]. For odd primes `λ(p^k)` is
+/// `p^(k - 1) (p - 1)` like Euler's totient, but the powers of two are special
+/// since `λ(2^k)` is only `2^(k - 2)` once `k >= 3`. It divides the totient,
+/// and bounds the multiplicative order of every unit modulo `n`.
+///
+/// `λ(1)` is 1, and that of 0 is taken to be 0. Like with [`factorize()`],
+/// negative numbers are treated as having no prime factors.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::carmichael_lambda(8u32), 2);
+/// assert_eq!(sieve::carmichael_lambda(15u32), 4);
+/// assert_eq!(sieve::carmichael_lambda(561u32), 80);
+/// assert_eq!(sieve::carmichael_lambda(1_000_000_007u64), 1_000_000_006);
+/// assert_eq!(sieve::carmichael_lambda(1u32), 1);
+/// assert_eq!(sieve::carmichael_lambda(2u32), 1);
+/// ```
+pub fn carmichael_lambda<I>(n: I) -> I
+where
+    ops::RangeFrom<I>: Iterator<Item = I>,
+    I: SieveInt,
+{
+    let zero = I::from_small(0);
+    let one = I::from_small(1);
+    let two = I::from_small(2);
+
+    if n == zero {
+        return n;
+    }
+
+    factorize(n)
+        .into_iter()
+        .map(|(p, k)| {
+            let (mut lambda, k) = match (p == two, k) {
+                (true, 1..=2) => (one, k),
+                (true, _) => (one, k - 1),
+                (false, _) => (p - one, k),
+            };
+
+            for _ in 1..k {
+                lambda = lambda * p;
+            }
+
+            lambda
+        })
+        .fold(one, lcm)
+}
+
+/// Compute the least common multiple of `a` and `b`, which must be positive.
+///
+/// # Panics
+///
+/// Panics if the result doesn't fit in `I`, which can't happen for divisors of
+/// a value of `I` like those in [`carmichael_lambda()`].
+fn lcm<I>(a: I, b: I) -> I
+where
+    I: SieveInt,
+{
+    let zero = I::from_small(0);
+    let (mut x, mut y) = (a, b);
+
+    while y != zero {
+        let t = x % y;
+        x = y;
+        y = t;
+    }
+
+    match (a / x).checked_mul(b) {
+        Some(lcm) => lcm,
+        None => panic!("least common multiple overflowed"),
+    }
+}
+
+/// Get the prime factorization of `n` like [`factorize()`], using Pollard's
+/// rho algorithm for large factors.
+///
+/// Factors below `2^16` are divided out using
+/// [`SMALL_PRIMES`][crate::SMALL_PRIMES]. Every factor of what's left is
+/// larger than that, so it's either certified as a prime with
+/// [`is_prime_u64()`][crate::is_prime_u64()], or split in two using Brent's
+/// variant of Pollard's rho, which finds a factor `p` in roughly `sqrt(p)`
+/// steps. Unlike trial division, this stays fast when the two largest prime
+/// factors are both large.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::factorize_u64(360), [(2, 3), (3, 2), (5, 1)]);
+/// assert_eq!(sieve::factorize_u64(0), []);
+/// assert_eq!(sieve::factorize_u64(1), []);
+///
+/// let n = 1_000_000_007 * 998_244_353;
+/// assert_eq!(sieve::factorize_u64(n), [(998_244_353, 1), (1_000_000_007, 1)]);
+/// ```
+pub fn factorize_u64(n: u64) -> Vec<(u64, u32)> {
+    let mut factors = Vec::new();
+
+    if n <= 1 {
+        return factors;
+    }
+
+    let mut n = n;
+
+    for &p in crate::SMALL_PRIMES.iter() {
+        let p = u64::from(p);
+
+        if p > n / p {
+            break;
+        }
+
+        let mut exponent = 0;
+
+        while n % p == 0 {
+            n /= p;
+            exponent += 1;
+        }
+
+        if exponent > 0 {
+            factors.push((p, exponent));
+        }
+    }
+
+    if n == 1 {
+        return factors;
+    }
+
+    let mut large = Vec::new();
+    split(n, &mut large);
+    large.sort_unstable();
+
+    for p in large {
+        match factors.last_mut() {
+            Some((last, exponent)) if *last == p => *exponent += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+
+    factors
+}
+
+/// Get the prime factorization of every number in `lo..=hi`, in the same form
+/// as [`factorize()`], indexed by the number's offset from `lo`.
+///
+/// Rather than factorizing each number on its own, the window is sieved: the
+/// multiples of each prime up to `sqrt(hi)` are walked within the window and
+/// the prime is divided out of them, after which whatever is left of a number
+/// is either 1 or a single prime factor larger than `sqrt(hi)`. Apart from the
+/// primes, memory is proportional to the size of the window rather than to
+/// `hi`, so windows far from the origin are as cheap as those near it.
+///
+/// If `lo > hi` the result is empty.
+///
+/// # Examples
+///
+/// ```
+/// let factors = sieve::factorize_range(10, 15);
+///
+/// assert_eq!(factors, [
+///     vec![(2, 1), (5, 1)],
+///     vec![(11, 1)],
+///     vec![(2, 2), (3, 1)],
+///     vec![(13, 1)],
+///     vec![(2, 1), (7, 1)],
+///     vec![(3, 1), (5, 1)],
+/// ]);
+///
+/// let lo = 1_000_000_000;
+/// let factors = sieve::factorize_range(lo, lo + 1000);
+/// assert_eq!(factors[7], [(1_000_000_007, 1)]);
+/// assert_eq!(factors[1000], sieve::factorize(lo + 1000));
+/// ```
+pub fn factorize_range(lo: u64, hi: u64) -> Vec<Vec<(u64, u32)>> {
+    if lo > hi {
+        return Vec::new();
+    }
+
+    let len = match usize::try_from(hi - lo).ok().and_then(|n| n.checked_add(1)) {
+        Some(len) => len,
+        None => panic!("range is too large to factorize"),
+    };
+
+    let mut rest = (lo..=hi).collect::<Vec<_>>();
+    let mut factors = vec![Vec::new(); len];
+
+    let mut primes = Vec::new();
+    let root = isqrt(hi);
+
+    if root >= 2 {
+        primes.push(2);
+        primes.extend(small::odd_primes(root));
+    }
+
+    for p in primes {
+        let mut m = match lo % p {
+            0 => lo,
+            r => match lo.checked_add(p - r) {
+                Some(m) => m,
+                None => continue,
+            },
+        };
+
+        while m <= hi {
+            let i = (m - lo) as usize;
+            let n = &mut rest[i];
+
+            // 0 is a multiple of everything, but has no prime factors.
+            if *n != 0 {
+                let mut exponent = 0;
+
+                while *n % p == 0 {
+                    *n /= p;
+                    exponent += 1;
+                }
+
+                factors[i].push((p, exponent));
+            }
+
+            m = match m.checked_add(p) {
+                Some(m) => m,
+                None => break,
+            };
+        }
+    }
+
+    for (factors, n) in factors.iter_mut().zip(rest) {
+        if n > 1 {
+            factors.push((n, 1));
+        }
+    }
+
+    factors
+}
+
+/// Split `n`, which has no factors below `2^16`, into its prime factors.
+fn split(n: u64, out: &mut Vec<u64>) {
+    if crate::is_prime_u64(n) {
+        out.push(n);
+        return;
+    }
+
+    // Each choice of the constant gives a different pseudorandom sequence,
+    // and for a composite `n` one of them soon finds a proper factor.
+    let mut c = 1;
+
+    let d = loop {
+        if let Some(d) = brent(n, c) {
+            break d;
+        }
+
+        c += 1;
+    };
+
+    split(d, out);
+    split(n / d, out);
+}
+
+/// Find a proper factor of the composite `n` with Brent's variant of Pollard's
+/// rho, iterating `x <- x^2 + c mod n`.
+///
+/// Returns `None` if the sequence cycles modulo `n` before a factor is found,
+/// in which case another `c` has to be tried.
+fn brent(n: u64, c: u64) -> Option<u64> {
+    let f = |x: u64| ((u128::from(mul_mod(x, x, n)) + u128::from(c)) % u128::from(n)) as u64;
+    let diff = |a: u64, b: u64| if a > b { a - b } else { b - a };
+
+    let (mut x, mut y, mut ys) = (2, 2, 2);
+    let (mut q, mut g, mut r) = (1, 1, 1);
+
+    while g == 1 {
+        x = y;
+
+        for _ in 0..r {
+            y = f(y);
+        }
+
+        let mut k = 0;
+
+        while k < r && g == 1 {
+            ys = y;
+
+            for _ in 0..RHO_BATCH.min(r - k) {
+                y = f(y);
+                q = mul_mod(q, diff(x, y), n);
+            }
+
+            g = gcd(q, n);
+            k += RHO_BATCH;
+        }
+
+        r *= 2;
+    }
+
+    // The batch overshot, so step through it again one gcd at a time.
+    if g == n {
+        loop {
+            ys = f(ys);
+            g = gcd(diff(x, ys), n);
+
+            if g > 1 {
+                break;
+            }
+        }
+    }
+
+    if g == n {
+        None
+    } else {
+        Some(g)
+    }
+}
+
+/// Factorize `n` through trial division by `primes`, which must be the primes
+/// in increasing order up to at least `sqrt(n)`.
+pub(crate) fn divide<I, P>(n: I, primes: P) -> Vec<(I, u32)>
+where
+    I: Copy + Ord + FromSmall + ops::Div<Output = I> + ops::Rem<Output = I>,
+    P: IntoIterator<Item = I>,
+{
+    let zero = I::from_small(0);
+    let one = I::from_small(1);
+
+    let mut factors = Vec::new();
+
+    if n <= one {
+        return factors;
+    }
+
+    let mut n = n;
+
+    for p in primes {
+        if p > n / p {
+            break;
+        }
+
+        let mut exponent = 0;
+
+        while n % p == zero {
+            n = n / p;
+            exponent += 1;
+        }
+
+        if exponent > 0 {
+            factors.push((p, exponent));
+        }
+    }
+
+    if n > one {
+        factors.push((n, 1));
+    }
+
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{carmichael_lambda, factorize, factorize_range, factorize_u64, radical};
+
+    fn product(factors: &[(u64, u32)]) -> u64 {
+        factors.iter().map(|&(p, e)| p.pow(e)).product()
+    }
+
+    #[test]
+    fn reproduces_input() {
+        let ns = (2..20_000u64)
+            .chain(crate::bounded(1000u64).map(|p| p.pow(3)))
+            .chain([
+                720_720,
+                1_441_440,
+                963_761_198_400,
+                18_446_744_073_709_551_615,
+            ]);
+
+        for n in ns {
+            let factors = factorize(n);
+            assert_eq!(product(&factors), n, "{}", n);
+
+            for w in factors.windows(2) {
+                assert!(w[0].0 < w[1].0, "{}", n);
+            }
+
+            for &(p, _) in &factors {
+                assert!(crate::trial_division(p), "{} in {}", p, n);
+            }
+        }
+    }
+
+    #[test]
+    fn known_values() {
+        assert!(factorize(0i64).is_empty());
+        assert!(factorize(1u32).is_empty());
+        assert!(factorize(-12i64).is_empty());
+        assert_eq!(factorize(2u32), [(2, 1)]);
+        assert_eq!(factorize(255u32), [(3, 1), (5, 1), (17, 1)]);
+        assert_eq!(factorize(1u64 << 40), [(2, 40)]);
+        assert_eq!(factorize(7_919u32 * 7_919), [(7_919, 2)]);
+        assert_eq!(
+            factorize(963_761_198_400u64),
+            [
+                (2, 6),
+                (3, 4),
+                (5, 2),
+                (7, 1),
+                (11, 1),
+                (13, 1),
+                (17, 1),
+                (19, 1),
+                (23, 1)
+            ]
+        );
+        // A large prime cofactor beyond the square root.
+        assert_eq!(
+            factorize(2u64 * 1_000_000_007),
+            [(2, 1), (1_000_000_007, 1)]
+        );
+    }
+
+    #[test]
+    fn radicals() {
+        for n in 1..10_000u64 {
+            let expected = (2..=n)
+                .filter(|&p| n % p == 0 && crate::trial_division(p))
+                .product::<u64>();
+
+            assert_eq!(radical(n), expected, "{}", n);
+        }
+
+        for p in crate::bounded(100u64) {
+            for k in 1..8 {
+                assert_eq!(radical(p.pow(k)), p);
+            }
+        }
+
+        assert_eq!(radical(720_720u64), 2 * 3 * 5 * 7 * 11 * 13);
+        assert_eq!(radical(u64::MAX), u64::MAX);
+        assert_eq!(radical(1u64 << 63), 2);
+        assert_eq!(radical(0i64), 0);
+        assert_eq!(radical(-12i64), 1);
+    }
+
+    #[test]
+    fn pollard_rho() {
+        assert!(factorize_u64(0).is_empty());
+        assert!(factorize_u64(1).is_empty());
+        assert_eq!(factorize_u64(2), [(2, 1)]);
+        assert_eq!(factorize_u64((1 << 61) - 1), [((1 << 61) - 1, 1)]);
+        assert_eq!(factorize_u64(1_000_000_000_000_000_000), [(2, 18), (5, 18)]);
+        assert_eq!(
+            factorize_u64(4_294_967_291 * 4_294_967_291),
+            [(4_294_967_291, 2)]
+        );
+        assert_eq!(
+            factorize_u64(1_000_003 * 1_000_003 * 1_000_003),
+            [(1_000_003, 3)]
+        );
+        assert_eq!(factorize_u64(65_537 * 65_537), [(65_537, 2)]);
+
+        assert_eq!(
+            factorize_u64(u64::MAX),
+            [
+                (3, 1),
+                (5, 1),
+                (17, 1),
+                (257, 1),
+                (641, 1),
+                (65_537, 1),
+                (6_700_417, 1)
+            ]
+        );
+
+        let p = crate::next_prime(1u64 << 30).unwrap();
+        let q = crate::next_prime(p).unwrap();
+        assert_eq!(factorize_u64(p * q), [(p, 1), (q, 1)]);
+        assert_eq!(factorize_u64(p * p * 7), [(7, 1), (p, 2)]);
+    }
+
+    #[test]
+    fn pollard_rho_reproduces_input() {
+        for n in 2..20_000u64 {
+            assert_eq!(factorize_u64(n), factorize(n), "{}", n);
+        }
+
+        for state in crate::xorshift(0x2545_f491_4f6c_dd1d).take(300) {
+            // Products of two ~32-bit primes are the hardest case, so every
+            // other number is one.
+            let n = if state % 2 == 0 {
+                let p = crate::next_prime(state >> 33).unwrap();
+                let q = crate::next_prime(state as u32 as u64 >> 1).unwrap();
+                p * q
+            } else {
+                state
+            };
+
+            let factors = factorize_u64(n);
+            assert_eq!(product(&factors), n, "{}", n);
+
+            for w in factors.windows(2) {
+                assert!(w[0].0 < w[1].0, "{}", n);
+            }
+
+            for &(p, _) in &factors {
+                assert!(crate::is_prime_u64(p), "{} in {}", p, n);
+            }
+        }
+    }
+
+    #[test]
+    fn carmichael() {
+        /// Get the largest multiplicative order of the units modulo `n`.
+        fn largest_order(n: u64) -> u64 {
+            (1..n.max(2))
+                .filter(|&a| crate::progression::gcd(a, n) == 1)
+                .map(|a| {
+                    let mut x = a % n;
+                    let mut order = 1;
+
+                    while x != 1 % n {
+                        x = x * a % n;
+                        order += 1;
+                    }
+
+                    order
+                })
+                .max()
+                .unwrap_or(1)
+        }
+
+        for n in 1..1000u64 {
+            assert_eq!(carmichael_lambda(n), largest_order(n), "{}", n);
+        }
+
+        assert_eq!(carmichael_lambda(0u32), 0);
+        assert_eq!(carmichael_lambda(-15i32), 1);
+        assert_eq!(carmichael_lambda(1u64 << 63), 1 << 61);
+        assert_eq!(carmichael_lambda(u64::MAX), 17_153_064_960);
+        assert_eq!(carmichael_lambda(u8::MAX), 16);
+        assert_eq!(carmichael_lambda(i8::MAX), 126);
+    }
+
+    #[test]
+    fn factorize_range_matches() {
+        for state in crate::xorshift(0x2545_f491_4f6c_dd1d).take(20) {
+            let lo = state >> (state % 40 + 24);
+            let hi = lo + state % 2000;
+            let factors = factorize_range(lo, hi);
+            assert_eq!(factors.len() as u64, hi - lo + 1);
+
+            for (n, factors) in (lo..=hi).zip(factors) {
+                assert_eq!(factors, factorize_u64(n), "{}", n);
+            }
+        }
+
+        let factors = factorize_range(0, 10_000);
+
+        for (n, factors) in (0..=10_000u64).zip(factors) {
+            assert_eq!(factors, factorize(n), "{}", n);
+        }
+
+        let lo = 999_999_000_000;
+        let factors = factorize_range(lo, lo + 2_000);
+
+        for (n, factors) in (lo..=lo + 2_000).zip(factors) {
+            assert_eq!(factors, factorize_u64(n), "{}", n);
+        }
+
+        assert!(factorize_range(10, 9).is_empty());
+        assert_eq!(factorize_range(7, 7), [[(7, 1)]]);
+    }
+}
@@ -0,0 +1,578 @@
+//! A flat bit sieve over the odd numbers, used when the upper bound is known
+//! ahead of time.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+
+/// The primes up to and including some bound, stored as one bit per odd
+/// number.
+#[derive(Clone)]
+pub(crate) struct Flat {
+    /// Bit `i` is set if `2 * i + 1` is prime.
+    bits: Vec<u64>,
+    upper: usize,
+}
+
+impl Flat {
+    /// Sieve all primes up to and including `upper`.
+    pub(crate) fn new(upper: usize) -> Self {
+        // Number of odd numbers in `1..=upper`.
+        let len = upper / 2 + upper % 2;
+        let mut bits = vec![!0u64; (len + 63) / 64];
+
+        if len % 64 != 0 {
+            if let Some(last) = bits.last_mut() {
+                *last = (1u64 << (len % 64)) - 1;
+            }
+        }
+
+        if let Some(first) = bits.first_mut() {
+            // 1 is not prime.
+            *first &= !1;
+        }
+
+        let mut i = 1;
+
+        loop {
+            let p = 2 * i + 1;
+
+            if p > upper / p {
+                break;
+            }
+
+            if bits[i / 64] & (1 << (i % 64)) != 0 {
+                clear_multiples(&mut bits, p * p / 2, p);
+            }
+
+            i += 1;
+        }
+
+        Self { bits, upper }
+    }
+
+    /// Construct a sieve from bits where bit `i` is set if `2 * i + 1` is a
+    /// prime up to and including `upper`.
+    pub(crate) fn from_bits(bits: Vec<u64>, upper: usize) -> Self {
+        debug_assert_eq!(bits.len(), (upper / 2 + upper % 2 + 63) / 64);
+        Self { bits, upper }
+    }
+
+    /// Test if `n` is a prime in the sieve.
+    #[inline]
+    pub(crate) fn contains(&self, n: usize) -> bool {
+        if n % 2 == 0 {
+            return n == 2;
+        }
+
+        let i = n / 2;
+        n <= self.upper && self.bits[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// Count the number of primes in the sieve.
+    pub(crate) fn count(&self) -> usize {
+        let odd = self
+            .bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum::<usize>();
+
+        odd + usize::from(self.upper >= 2)
+    }
+
+    /// Get the bound of the sieve.
+    #[inline]
+    pub(crate) fn upper(&self) -> usize {
+        self.upper
+    }
+
+    /// Iterate over the primes in the sieve in increasing order.
+    #[inline]
+    pub(crate) fn iter(&self) -> IntoIter<&[u64]> {
+        IntoIter {
+            two: self.upper >= 2,
+            bits: &self.bits,
+            index: 0,
+            word: 0,
+        }
+    }
+
+    /// Collect the odd primes in the sieve.
+    pub(crate) fn odd_primes(&self) -> Vec<u64> {
+        let mut primes = Vec::with_capacity(self.count());
+
+        for (index, &word) in self.bits.iter().enumerate() {
+            let mut word = word;
+
+            while word != 0 {
+                let i = index * 64 + word.trailing_zeros() as usize;
+                primes.push(2 * i as u64 + 1);
+                word &= word - 1;
+            }
+        }
+
+        primes
+    }
+
+    /// Get the `n`th prime in the sieve, counting from zero.
+    pub(crate) fn nth(&self, mut n: usize) -> Option<usize> {
+        if self.upper >= 2 {
+            if n == 0 {
+                return Some(2);
+            }
+
+            n -= 1;
+        }
+
+        for (index, &word) in self.bits.iter().enumerate() {
+            let ones = word.count_ones() as usize;
+
+            if n >= ones {
+                n -= ones;
+                continue;
+            }
+
+            let mut word = word;
+
+            for _ in 0..n {
+                word &= word - 1;
+            }
+
+            let i = index * 64 + word.trailing_zeros() as usize;
+            return Some(2 * i + 1);
+        }
+
+        None
+    }
+}
+
+impl IntoIterator for Flat {
+    type Item = u64;
+    type IntoIter = IntoIter;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            two: self.upper >= 2,
+            bits: self.bits,
+            index: 0,
+            word: 0,
+        }
+    }
+}
+
+/// An iterator over the primes in a [`Flat`] sieve in increasing order, which
+/// either owns or borrows its bits.
+pub(crate) struct IntoIter<B = Vec<u64>> {
+    bits: B,
+    two: bool,
+    /// The index of the next word to scan.
+    index: usize,
+    /// What remains of the word before `index`.
+    word: u64,
+}
+
+impl<B> Iterator for IntoIter<B>
+where
+    B: AsRef<[u64]>,
+{
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.two {
+            self.two = false;
+            return Some(2);
+        }
+
+        while self.word == 0 {
+            self.word = *self.bits.as_ref().get(self.index)?;
+            self.index += 1;
+        }
+
+        let i = (self.index - 1) * 64 + self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some(2 * i as u64 + 1)
+    }
+}
+
+/// A window of odd numbers sieved against a list of base primes, which can
+/// be drained in increasing order.
+#[derive(Clone)]
+pub(crate) struct Segment {
+    /// The first odd number in the segment.
+    lo: u64,
+    /// Bit `i` is set if `lo + 2 * i` is prime.
+    bits: Vec<u64>,
+    /// The word to drain primes from next.
+    index: usize,
+}
+
+impl Segment {
+    /// Construct a new empty segment.
+    pub(crate) const fn new() -> Self {
+        Self {
+            lo: 1,
+            bits: Vec::new(),
+            index: 0,
+        }
+    }
+
+    /// Get the number of bytes allocated for the segment.
+    #[inline]
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.bits.capacity() * mem::size_of::<u64>()
+    }
+
+    /// Sieve the odd numbers in `lo..=hi`, where `lo` is odd and `primes`
+    /// holds all odd primes up to `sqrt(hi)` in increasing order.
+    pub(crate) fn sieve(&mut self, lo: u64, hi: u64, primes: &[u64]) {
+        debug_assert!(lo % 2 == 1 && lo <= hi);
+
+        let len = ((hi - lo) / 2 + 1) as usize;
+
+        self.lo = lo;
+        self.index = 0;
+        self.bits.clear();
+        self.bits.resize((len + 63) / 64, !0);
+
+        if len % 64 != 0 {
+            if let Some(last) = self.bits.last_mut() {
+                *last = (1u64 << (len % 64)) - 1;
+            }
+        }
+
+        if lo == 1 {
+            // 1 is not prime.
+            self.bits[0] &= !1;
+        }
+
+        for &p in primes {
+            if p > hi / p {
+                break;
+            }
+
+            let start = if p * p >= lo {
+                p * p
+            } else {
+                // Smallest odd multiple of `p` which is at least `lo`, which
+                // can be past the end of `u64` when `hi` is close to it.
+                let m = match ((lo - 1) / p + 1).checked_mul(p) {
+                    Some(m) if m % 2 == 0 => m.checked_add(p),
+                    m => m,
+                };
+
+                match m {
+                    Some(m) if m <= hi => m,
+                    _ => continue,
+                }
+            };
+
+            clear_multiples(&mut self.bits, ((start - lo) / 2) as usize, p as usize);
+        }
+    }
+
+    /// Remove and return the smallest prime remaining in the segment.
+    pub(crate) fn pop(&mut self) -> Option<u64> {
+        loop {
+            let word = self.bits.get_mut(self.index)?;
+
+            if *word != 0 {
+                let i = self.index * 64 + word.trailing_zeros() as usize;
+                *word &= *word - 1;
+                return Some(self.lo + 2 * i as u64);
+            }
+
+            self.index += 1;
+        }
+    }
+
+    /// Count the primes remaining in the segment.
+    pub(crate) fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .skip(self.index)
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Remove and return the largest prime remaining in the segment.
+    pub(crate) fn pop_last(&mut self) -> Option<u64> {
+        while self.bits.len() > self.index {
+            let last = self.bits.len() - 1;
+            let word = &mut self.bits[last];
+
+            if *word != 0 {
+                let bit = 63 - word.leading_zeros() as usize;
+                *word &= !(1 << bit);
+                return Some(self.lo + 2 * (last * 64 + bit) as u64);
+            }
+
+            self.bits.pop();
+        }
+
+        None
+    }
+}
+
+/// A window of consecutive numbers with a bit for each, which starts out set
+/// and can be drained in increasing order.
+///
+/// The numbers are offsets into whatever is being sieved, such as the indexes
+/// of the members of an arithmetic progression.
+#[derive(Clone)]
+pub(crate) struct Window {
+    /// The first number in the window.
+    lo: u64,
+    /// Bit `i` is set if `lo + i` is still in the window.
+    bits: Vec<u64>,
+    /// The word to drain numbers from next.
+    index: usize,
+}
+
+impl Window {
+    /// Construct a new empty window.
+    pub(crate) const fn new() -> Self {
+        Self {
+            lo: 0,
+            bits: Vec::new(),
+            index: 0,
+        }
+    }
+
+    /// Reset the window to hold every number in `lo..=hi`.
+    pub(crate) fn reset(&mut self, lo: u64, hi: u64) {
+        debug_assert!(lo <= hi);
+
+        let len = (hi - lo + 1) as usize;
+
+        self.lo = lo;
+        self.index = 0;
+        self.bits.clear();
+        self.bits.resize((len + 63) / 64, !0);
+
+        if len % 64 != 0 {
+            if let Some(last) = self.bits.last_mut() {
+                *last = (1u64 << (len % 64)) - 1;
+            }
+        }
+    }
+
+    /// Remove `lo + i` from the window.
+    #[inline]
+    pub(crate) fn clear(&mut self, i: usize) {
+        self.bits[i / 64] &= !(1 << (i % 64));
+    }
+
+    /// Remove every `step`th number from the window, starting from `lo +
+    /// start`.
+    #[inline]
+    pub(crate) fn clear_multiples(&mut self, start: usize, step: usize) {
+        clear_multiples(&mut self.bits, start, step);
+    }
+
+    /// Remove and return the smallest number remaining in the window.
+    pub(crate) fn pop(&mut self) -> Option<u64> {
+        loop {
+            let word = self.bits.get_mut(self.index)?;
+
+            if *word != 0 {
+                let i = self.index * 64 + word.trailing_zeros() as usize;
+                *word &= *word - 1;
+                return Some(self.lo + i as u64);
+            }
+
+            self.index += 1;
+        }
+    }
+}
+
+/// Strides below this clear more than one bit per word, so they're cleared a
+/// whole word at a time.
+const WORD_STRIDE: usize = 64;
+
+/// Clear every `step`th bit of `bits` starting from bit `start`.
+///
+/// Bits past the end of the sieve are always clear, so this runs to the end
+/// of the last word.
+#[inline]
+pub(crate) fn clear_multiples(bits: &mut [u64], start: usize, step: usize) {
+    if step < WORD_STRIDE {
+        clear_words(bits, start, step);
+    } else {
+        clear_bits(bits, start, step);
+    }
+}
+
+/// Clear bits one at a time.
+fn clear_bits(bits: &mut [u64], start: usize, step: usize) {
+    let len = bits.len() * 64;
+    let mut j = start;
+
+    while j < len {
+        bits[j / 64] &= !(1 << (j % 64));
+        j += step;
+    }
+}
+
+/// Clear bits a word at a time, by building the masks for `step` words after
+/// which the pattern of cleared bits repeats.
+fn clear_words(bits: &mut [u64], start: usize, step: usize) {
+    let words = match bits.get_mut(start / 64..) {
+        Some(words) => words,
+        None => return,
+    };
+
+    let offset = start % 64;
+    let mut masks = [0u64; WORD_STRIDE];
+    let mut j = offset % step;
+
+    while j < step * 64 {
+        masks[j / 64] |= 1 << (j % 64);
+        j += step;
+    }
+
+    let masks = &masks[..step];
+
+    // Bits before `start` in the first word mustn't be cleared.
+    if let Some(first) = words.first_mut() {
+        *first &= !(masks[0] & (!0 << offset));
+    }
+
+    for (word, mask) in words.iter_mut().zip(masks.iter().cycle()).skip(1) {
+        *word &= !mask;
+    }
+}
+
+/// Compute the integer square root of `n`.
+pub(crate) fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+
+    // Start from a power of two which is at least `sqrt(n)`, from which
+    // Newton's method converges downwards.
+    let mut x = 1u64 << ((64 - n.leading_zeros() + 1) / 2);
+
+    loop {
+        let y = (x + n / x) / 2;
+
+        if y >= x {
+            return x;
+        }
+
+        x = y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::{clear_bits, clear_words, isqrt, Flat, Segment, Window};
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0..10_000u64 {
+            let r = isqrt(n);
+            assert!(r * r <= n && (r + 1) * (r + 1) > n, "{}", n);
+        }
+
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+        assert_eq!(isqrt(u64::MAX - 1), u32::MAX as u64);
+    }
+
+    #[test]
+    fn matches_bounded() {
+        for upper in 0..=1000u32 {
+            let flat = Flat::new(upper as usize);
+            let mut n = 0;
+
+            let actual = core::iter::from_fn(|| {
+                n += 1;
+                flat.nth(n - 1)
+            });
+
+            let expected = crate::bounded(upper).map(|p| p as usize);
+            assert!(actual.eq(expected), "{}", upper);
+            assert_eq!(flat.count(), crate::bounded(upper).count(), "{}", upper);
+        }
+    }
+
+    #[test]
+    fn clear_words_matches_bits() {
+        for words in 0..8 {
+            for start in 0..(words * 64 + 70) {
+                for step in 1..64 {
+                    let mut expected = vec![!0u64; words];
+                    let mut actual = vec![!0u64; words];
+                    clear_bits(&mut expected, start, step);
+                    clear_words(&mut actual, start, step);
+                    assert_eq!(actual, expected, "{} {} {}", words, start, step);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn word_boundaries() {
+        // Odd numbers below `upper` fill a whole number of words when `upper`
+        // is a multiple of 128.
+        for &base in &[128, 1024, 8192, 1 << 20] {
+            for upper in base - 3..=base + 3 {
+                let flat = Flat::new(upper);
+                let expected = crate::bounded(upper as u64).skip(1).collect::<Vec<_>>();
+                assert_eq!(flat.odd_primes(), expected, "{}", upper);
+            }
+        }
+
+        let primes = Flat::new(1000).odd_primes();
+
+        for &(lo, hi) in &[
+            (1, 127),
+            (1, 129),
+            (129, 255),
+            (129, 257),
+            (1001, 1001 + 2 * 64 * 7),
+        ] {
+            let mut segment = Segment::new();
+            segment.sieve(lo, hi, &primes);
+            let actual = core::iter::from_fn(|| segment.pop()).collect::<Vec<_>>();
+            let expected = crate::range(lo, hi)
+                .filter(|&p| p != 2)
+                .collect::<Vec<u64>>();
+            assert_eq!(actual, expected, "{}..={}", lo, hi);
+        }
+
+        // The first multiple of a prime can be past the end of `u64`. Only
+        // the primes below `2^16` are sifted out, which is cheap to check.
+        let primes = Flat::new(1 << 16).odd_primes();
+        let lo = u64::MAX - 4000;
+
+        let mut segment = Segment::new();
+        segment.sieve(lo, u64::MAX, &primes);
+        let actual = core::iter::from_fn(|| segment.pop()).collect::<Vec<_>>();
+        let expected = (lo..=u64::MAX)
+            .step_by(2)
+            .filter(|&n| primes.iter().all(|&p| n % p != 0))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn window() {
+        let mut window = Window::new();
+        assert_eq!(window.pop(), None);
+
+        for &(lo, hi) in &[(0, 0), (5, 68), (5, 69), (100, 100 + 64 * 3)] {
+            window.reset(lo, hi);
+            window.clear_multiples(1, 3);
+            window.clear(0);
+            let actual = core::iter::from_fn(|| window.pop()).collect::<Vec<_>>();
+            let expected = (lo..=hi)
+                .filter(|&n| n != lo && (n - lo) % 3 != 1)
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected, "{}..={}", lo, hi);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Goldbach partitions of even numbers into two primes.
+
+use core::convert::TryFrom;
+use core::marker;
+
+use crate::flat::Flat;
+use crate::FromSmall;
+
+/// Find a pair of primes `(p, q)` with `p <= q` which sum to `n`, choosing the
+/// one with the smallest `p`.
+///
+/// The primes up to `n` are sieved into a flat bit array, so checking if
+/// `n - p` is prime takes constant time.
+///
+/// Returns `None` if `n` is odd or less than 4, since such numbers can't be
+/// written as a sum of two primes in this way.
+///
+/// # Panics
+///
+/// Panics if `n` doesn't fit in a `usize`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::goldbach_pair(4u32), Some((2, 2)));
+/// assert_eq!(sieve::goldbach_pair(28u32), Some((5, 23)));
+/// assert_eq!(sieve::goldbach_pair(27u32), None);
+/// assert_eq!(sieve::goldbach_pair(2u32), None);
+/// ```
+pub fn goldbach_pair<I>(n: I) -> Option<(I, I)>
+where
+    I: Copy + Ord + FromSmall + TryFrom<usize>,
+    usize: TryFrom<I>,
+{
+    goldbach_pairs(n).next()
+}
+
+/// Construct an iterator over all pairs of primes `(p, q)` with `p <= q` which
+/// sum to `n`, in increasing order of `p`.
+///
+/// Like [`goldbach_pair()`], this is empty if `n` is odd or less than 4.
+///
+/// # Panics
+///
+/// Panics if `n` doesn't fit in a `usize`.
+///
+/// # Examples
+///
+/// ```
+/// assert!(sieve::goldbach_pairs(100u32).eq([(3, 97), (11, 89), (17, 83), (29, 71), (41, 59), (47, 53)]));
+/// assert_eq!(sieve::goldbach_pairs(27u32).next(), None);
+/// ```
+pub fn goldbach_pairs<I>(n: I) -> GoldbachPairs<I>
+where
+    I: Copy + Ord + FromSmall,
+    usize: TryFrom<I>,
+{
+    if n < I::from_small(4) {
+        return GoldbachPairs::new(0);
+    }
+
+    let n = match usize::try_from(n) {
+        Ok(n) => n,
+        Err(..) => panic!("bound doesn't fit in a usize"),
+    };
+
+    if n % 2 == 1 {
+        return GoldbachPairs::new(0);
+    }
+
+    GoldbachPairs::new(n)
+}
+
+/// An iterator over the Goldbach partitions of a number.
+///
+/// See [`goldbach_pairs()`].
+pub struct GoldbachPairs<I> {
+    flat: Flat,
+    n: usize,
+    /// The next smaller member to test.
+    p: usize,
+    _marker: marker::PhantomData<I>,
+}
+
+impl<I> GoldbachPairs<I> {
+    fn new(n: usize) -> Self {
+        Self {
+            flat: Flat::new(n),
+            n,
+            p: 2,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<I> Iterator for GoldbachPairs<I>
+where
+    I: TryFrom<usize>,
+{
+    type Item = (I, I);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.p <= self.n / 2 {
+            let p = self.p;
+            // Past 2, only odd numbers can be prime.
+            self.p += if p == 2 { 1 } else { 2 };
+
+            if self.flat.contains(p) && self.flat.contains(self.n - p) {
+                return Some((I::try_from(p).ok()?, I::try_from(self.n - p).ok()?));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{goldbach_pair, goldbach_pairs};
+
+    #[test]
+    fn always_found() {
+        let flat = crate::flat::Flat::new(100_000);
+
+        for n in (4..100_000u32).step_by(2) {
+            let (p, q) = goldbach_pair(n).unwrap();
+            assert_eq!(p + q, n);
+            assert!(p <= q, "{}", n);
+            assert!(
+                flat.contains(p as usize) && flat.contains(q as usize),
+                "{}",
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        for n in 0..4u32 {
+            assert_eq!(goldbach_pair(n), None);
+        }
+
+        assert_eq!(goldbach_pair(-4i64), None);
+        assert_eq!(goldbach_pair(6u32), Some((3, 3)));
+        assert_eq!(goldbach_pair(1001u32), None);
+        assert!(goldbach_pairs(10u32).eq([(3, 7), (5, 5)]));
+        assert_eq!(goldbach_pairs(1_000_000u32).count(), 5402);
+    }
+}
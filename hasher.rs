@@ -0,0 +1,88 @@
+//! Hashing support for the composite map of [`Sieve`][crate::Sieve].
+
+use core::hash::Hasher;
+
+/// The hash builder used by default for the composite map.
+///
+/// With the `std` feature enabled this is the randomly seeded `RandomState`
+/// from `std`, unless the `fast-hash` feature is enabled. Otherwise it's
+/// [`FxHasher`].
+#[cfg(all(feature = "std", not(feature = "fast-hash")))]
+pub type DefaultHashBuilder = std::collections::hash_map::RandomState;
+
+/// The hash builder used by default for the composite map.
+///
+/// With the `std` feature enabled this is the randomly seeded `RandomState`
+/// from `std`, unless the `fast-hash` feature is enabled. Otherwise it's
+/// [`FxHasher`].
+#[cfg(any(not(feature = "std"), feature = "fast-hash"))]
+pub type DefaultHashBuilder = core::hash::BuildHasherDefault<FxHasher>;
+
+/// Multiplier used by [`FxHasher`].
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// A fast, non-cryptographic hasher in the style of the one used inside of
+/// rustc, which is well suited to the small integer keys of the sieve.
+///
+/// # Examples
+///
+/// ```
+/// use std::hash::BuildHasherDefault;
+///
+/// use sieve::FxHasher;
+///
+/// let sieve = sieve::bounded_with_hasher(100u32, BuildHasherDefault::<FxHasher>::default());
+/// assert_eq!(sieve.count(), 25);
+/// ```
+#[derive(Default, Clone, Copy)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    #[inline]
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, n: u8) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, n: u16) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, n: u32) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, n: u64) {
+        self.add(n);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, n: usize) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
@@ -0,0 +1,162 @@
+//! Traits describing the integer types which can be sieved.
+
+use core::convert::TryFrom;
+use core::hash;
+use core::ops;
+
+/// Checked arithmetic and conversions used by the sieve, so that it can run
+/// right up to the end of its integer type without overflowing.
+///
+/// This is implemented for all primitive integer types, where it maps directly
+/// to the inherent checked methods. On types as wide as `u128` overflow is
+/// practically unreachable, but checking for it costs next to nothing.
+pub trait CheckedOps: Sized {
+    /// Checked integer addition, returning `None` on overflow.
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    /// Checked integer multiplication, returning `None` on overflow.
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+
+    /// Convert to a `u64`, returning `None` if the value doesn't fit.
+    ///
+    /// This lets the sieve hand work off to the bit sieves, which operate on
+    /// `u64`.
+    fn to_u64(self) -> Option<u64>;
+
+    /// Convert from a `u64`, returning `None` if the value doesn't fit.
+    fn from_u64(n: u64) -> Option<Self>;
+
+    /// Convert to the nearest `f64`, which is used to estimate how many primes
+    /// there are up to a value.
+    fn to_f64(self) -> f64;
+}
+
+/// Construction from small constants, which the sieve needs for seeding and
+/// for the wheel.
+///
+/// Unlike `From<u32>` this is implemented for every primitive integer type,
+/// including the signed ones and those narrower than 32 bits.
+pub trait FromSmall {
+    /// Convert a small constant to this type.
+    fn from_small(n: u8) -> Self;
+}
+
+/// An integer type which can be sieved.
+///
+/// This gathers everything the sieve needs from its integer type, so that
+/// generic code can be bound on `I: SieveInt` instead of repeating each
+/// operator it happens to use, and so that new requirements inside the sieve
+/// don't break those bounds.
+///
+/// It's implemented for every primitive integer type, and sealed so that it
+/// can't be implemented outside of this crate.
+///
+/// Ranges are iterators through the unstable `Step` trait, which this can't
+/// imply. So code which is generic over [`infinite()`][crate::infinite()] or
+/// [`bounded()`][crate::bounded()] still needs to bound the range type.
+///
+/// # Examples
+///
+/// ```
+/// use std::ops::RangeFrom;
+///
+/// use sieve::SieveInt;
+///
+/// fn sum_of_first<I>(n: usize) -> I
+/// where
+///     I: SieveInt,
+///     RangeFrom<I>: Iterator<Item = I>,
+/// {
+///     sieve::infinite::<I>().take(n).fold(I::from_small(0), |sum, p| sum + p)
+/// }
+///
+/// assert_eq!(sum_of_first::<u8>(4), 17);
+/// assert_eq!(sum_of_first::<u64>(1000), 3_682_913);
+/// ```
+pub trait SieveInt:
+    sealed::Sealed
+    + Copy
+    + Ord
+    + hash::Hash
+    + ops::Add<Output = Self>
+    + ops::Sub<Output = Self>
+    + ops::Mul<Output = Self>
+    + ops::Div<Output = Self>
+    + ops::Rem<Output = Self>
+    + CheckedOps
+    + FromSmall
+{
+}
+
+mod sealed {
+    /// The supertrait which seals [`SieveInt`][super::SieveInt].
+    ///
+    /// It's public so that it can be named in the supertrait, but it lives in
+    /// a private module so that it can't be named outside of the crate. Impls
+    /// for types from optional dependencies can still be added here behind
+    /// their features.
+    pub trait Sealed {}
+}
+
+macro_rules! checked_ops {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl CheckedOps for $ty {
+                #[inline]
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$ty>::checked_add(self, rhs)
+                }
+
+                #[inline]
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$ty>::checked_mul(self, rhs)
+                }
+
+                #[inline]
+                fn to_u64(self) -> Option<u64> {
+                    u64::try_from(self).ok()
+                }
+
+                #[inline]
+                fn from_u64(n: u64) -> Option<Self> {
+                    <$ty>::try_from(n).ok()
+                }
+
+                #[inline]
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+
+            impl FromSmall for $ty {
+                #[inline]
+                fn from_small(n: u8) -> Self {
+                    n as $ty
+                }
+            }
+
+            impl sealed::Sealed for $ty {}
+
+            impl SieveInt for $ty {}
+        )*
+    };
+}
+
+checked_ops!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Get the largest value of `I`.
+///
+/// This is found by doubling, since the traits don't expose it directly.
+pub(crate) fn max_value<I>() -> I
+where
+    I: SieveInt,
+{
+    let one = I::from_small(1);
+    let mut half = one;
+
+    while let Some(next) = half.checked_add(half) {
+        half = next;
+    }
+
+    half + (half - one)
+}
@@ -23,131 +23,3599 @@
 //!     println!("prime = {}", prime);
 //! }
 //! ```
+//!
+//! <br>
+//!
+//! ## `no_std` support
+//!
+//! This crate can be used without `std` by disabling the default `std`
+//! feature, in which case it only depends on `alloc`. The composite map then
+//! is a `BTreeMap` from `alloc` instead of a hash map, so the hasher isn't used.
+//!
+//! <br>
+//!
+//! ## Hashing
+//!
+//! By default the composite map is hashed with the randomly seeded hasher from
+//! `std`. Enabling the `fast-hash` feature switches it to the faster
+//! [`FxHasher`], which is also the default without `std`, even though nothing is
+//! hashed there. Neither option pulls in any dependencies.
 
 #![deny(missing_docs)]
+#![no_std]
+
+extern crate alloc;
+
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+mod adapters;
+pub use self::adapters::{
+    Constellations, Gaps, Indexed, InvalidPattern, SafePrimes, SophieGermain, Twins, TwoSquares,
+};
+
+mod almost;
+pub use self::almost::{almost_primes, semiprimes, AlmostPrimes};
+
+mod atkin;
+pub use self::atkin::{atkin, Atkin};
+
+mod back;
+
+mod bitset;
+pub use self::bitset::{bounded_bitset, composite_flags, Bitset, PrimalityMap, PrimalityMapIter};
+
+mod collect;
+pub use self::collect::collect_primes_below;
+
+mod consts;
+pub use self::consts::const_first;
+
+mod counting;
+pub use self::counting::{
+    nth_prime, nth_prime_lower_bound, nth_prime_upper_bound, prime_count, prime_count_estimate,
+    prime_count_lower_bound, prime_count_upper_bound, prime_pi, sum_of_primes_below,
+    sum_of_primes_below_mod,
+};
+
+mod factor;
+pub use self::factor::{carmichael_lambda, factorize, factorize_range, factorize_u64, radical};
+
+mod flat;
+
+mod goldbach;
+pub use self::goldbach::{goldbach_pair, goldbach_pairs, GoldbachPairs};
+
+mod hasher;
+pub use self::hasher::{DefaultHashBuilder, FxHasher};
+
+mod int;
+pub use self::int::{CheckedOps, FromSmall, SieveInt};
+
+mod math;
+
+#[cfg(feature = "std")]
+mod parallel;
+#[cfg(feature = "std")]
+pub use self::parallel::par_collect_bounded;
+
+#[cfg(feature = "std")]
+mod persist;
+
+mod powers;
+pub use self::powers::{prime_powers, PrimePowers, WithExponents};
+
+mod primorial;
+pub use self::primorial::{primorial, primorial_below};
+
+mod primality;
+pub use self::primality::{is_prime_u64, is_probable_prime, lucas_lehmer, next_prime, prev_prime};
+
+mod progression;
+pub use self::progression::{in_progression, InProgression, InvalidProgression};
+
+mod residue;
+pub use self::residue::{
+    is_quadratic_residue, jacobi, legendre_symbol, primitive_root, two_squares,
+};
+
+mod rough;
+pub use self::rough::{rough_numbers, RoughNumbers};
 
-use std::collections::HashMap;
-use std::hash;
-use std::ops;
+mod segmented;
+pub use self::segmented::{range, segmented, starting_at, Segmented, StartingAt};
+
+mod set;
+pub use self::set::PrimeSet;
+
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(feature = "std")]
+pub use self::shared::{global, SharedPrimes};
+
+mod small;
+pub use self::small::{small_primes, SMALL_PRIMES};
+
+mod smooth;
+pub use self::smooth::{is_smooth, is_smooth_with, smooth_numbers, SmoothNumbers};
+
+mod squarefree;
+pub use self::squarefree::{is_squarefree, squarefree, Squarefree};
+
+mod strides;
+
+mod tables;
+pub use self::tables::{
+    big_omega_table, divisor_count_table, divisor_sum_table, mobius_table, omega_table,
+    prime_pi_table, prime_sum_table, spf_table, totient_table,
+};
+
+#[cfg(feature = "std")]
+mod write;
+#[cfg(feature = "std")]
+pub use self::write::{write_primes, Format};
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash;
+use core::iter;
+use core::mem;
+use core::ops;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use self::strides::Composites;
+
+/// The residues modulo 30 which are coprime to 30, and the gaps to the next
+/// such residue. Candidates are only drawn from these, with 2, 3 and 5 handled
+/// specially.
+const WHEEL_RESIDUES: [u8; 8] = [1, 7, 11, 13, 17, 19, 23, 29];
+const WHEEL_GAPS: [usize; 8] = [6, 4, 2, 4, 2, 4, 6, 2];
+
+/// Skips of at least this many primes through [`Iterator::nth`] are done in
+/// bulk with a segmented sieve.
+const NTH_BULK: usize = 4096;
+
+/// The largest bound for which [`Iterator::size_hint`] counts the primes up to
+/// it exactly. Counting takes `O(n^(3/4))` time and `O(√n)` memory, which is
+/// too much for a hint above this, so larger bounds are estimated instead.
+const EXACT_SIZE_HINT: u64 = 1 << 32;
+
+/// How close the underlying iterator of a sieve is to the end of its integer
+/// type.
+///
+/// An [`ops::RangeFrom`] overflows when it produces the last number of its
+/// type, since it has to compute the number after it. So once the iterator is
+/// near the end, it's only advanced while that can't happen, and the last
+/// number is taken by hand.
+#[derive(Debug, Clone, Copy)]
+enum Tail<I> {
+    /// Far enough from the end that any gap on the wheel can be taken.
+    Far,
+    /// Near the end, with the number the iterator would produce next.
+    Near(I),
+    /// Nothing is left to take.
+    Done,
+}
 
 /// A simple prime sieve.
-pub struct Sieve<I, U> {
-    composite: HashMap<I, I>,
+///
+/// The composite map is hashed using `S`, which can be customized through
+/// constructors such as [`infinite_with_hasher()`].
+pub struct Sieve<I, U, S = DefaultHashBuilder> {
+    /// The composites being tracked, each with the primes which cross it
+    /// off.
+    composite: Composites<I, S>,
     iter: U,
+    pending: Option<I>,
+    /// Position on the wheel of the last candidate, or `None` if the sieve
+    /// hasn't yet reached the first candidate coprime to 30.
+    wheel: Option<usize>,
+    tail: Tail<I>,
+    /// The largest number the sieve will produce, if known. Composites beyond
+    /// it are never tracked.
+    limit: Option<I>,
+    /// The largest prime the sieve has passed, whether it was yielded or not.
+    last: Option<I>,
+    /// Every prime up to `last` in increasing order, which is only recorded
+    /// once [`Sieve::factorize_with`] has been used.
+    primes: Option<Vec<I>>,
+    /// The back end of a bounded sieve, once it's been iterated in reverse.
+    back: Option<back::Back>,
+    /// The number of primes passed from the front.
+    count: usize,
+    /// The number of primes up to `limit`, once it's been computed, or
+    /// `usize::MAX` if it hasn't. This is atomic since it's computed lazily
+    /// by [`Iterator::size_hint`] and [`ExactSizeIterator::len`].
+    total: AtomicUsize,
 }
 
-impl<I, U> Sieve<I, U> {
-    /// Get the size of number of stored composite numbers.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// let mut sieve = sieve::infinite::<u32>();
-    ///
-    /// assert!(sieve.by_ref().skip(10).take(10).eq([31, 37, 41, 43, 47, 53, 59, 61, 67, 71]));
-    /// assert_eq!(sieve.size(), 20);
-    /// ```
-    pub fn size(&self) -> usize {
-        self.composite.len()
+/// Cloning a sieve lets it be branched, with each copy continuing
+/// independently from where the original was.
+///
+/// This copies every composite being tracked, so it costs time and memory
+/// proportional to [`Sieve::size`].
+///
+/// # Examples
+///
+/// ```
+/// let mut sieve = sieve::infinite::<u32>();
+/// assert_eq!(sieve.nth(99), Some(541));
+///
+/// let twins = sieve.clone().twins().take(10).count();
+/// let gaps = sieve.gaps().take(10).count();
+/// assert_eq!((twins, gaps), (10, 10));
+/// ```
+impl<I, U, S> Clone for Sieve<I, U, S>
+where
+    I: Clone,
+    U: Clone,
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            composite: self.composite.clone(),
+            iter: self.iter.clone(),
+            pending: self.pending.clone(),
+            wheel: self.wheel,
+            tail: self.tail.clone(),
+            limit: self.limit.clone(),
+            last: self.last.clone(),
+            primes: self.primes.clone(),
+            back: self.back.clone(),
+            count: self.count,
+            total: AtomicUsize::new(self.total.load(Ordering::Relaxed)),
+        }
     }
 }
 
-/// Generate infinite primes.
+/// The default sieve is an infinite one, equivalent to [`infinite()`] or
+/// [`infinite_with_hasher()`] with the default hash builder.
 ///
 /// # Examples
 ///
 /// ```
-/// let iter = sieve::infinite::<u32>().skip(10).take(10);
+/// #[derive(Default)]
+/// struct Primes {
+///     sieve: sieve::Infinite<u32>,
+/// }
 ///
-/// assert!(
-///     iter.eq([31, 37, 41, 43, 47, 53, 59, 61, 67, 71])
-/// );
+/// let primes = Primes::default();
+/// assert!(primes.sieve.take(5).eq([2, 3, 5, 7, 11]));
 /// ```
-#[inline]
-pub fn infinite<I>() -> Sieve<I, ops::RangeFrom<I>>
+impl<I, S> Default for Sieve<I, ops::RangeFrom<I>, S>
 where
-    I: From<u32> + Eq + hash::Hash,
+    I: SieveInt,
+    S: hash::BuildHasher + Default,
 {
-    Sieve {
-        iter: 2.into()..,
-        composite: HashMap::new(),
+    #[inline]
+    fn default() -> Self {
+        infinite_with_hasher(S::default())
     }
 }
 
-/// Construct a bounded sieve, which stops returning values after it's reached
-/// the given numerical bound.
+/// The composite map isn't printed in full, since it can be very large. Only
+/// the number of primes it tracks is included as `composites`, unless the
+/// alternate `{:#?}` format is used, which also includes a few of them in no
+/// particular order.
 ///
 /// # Examples
 ///
 /// ```
-/// let iter = sieve::bounded::<u32>(100).skip(10);
+/// let mut sieve = sieve::bounded::<u32>(100);
+/// assert_eq!(sieve.nth(9), Some(29));
 ///
-/// assert!(
-///     iter.eq([31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97])
+/// assert_eq!(
+///     format!("{:?}", sieve),
+///     "Sieve { iter: 30..=100, pending: None, limit: Some(100), last: Some(29), composites: 1 }"
 /// );
 /// ```
-#[inline]
-pub fn bounded<I>(upper: I) -> Sieve<I, ops::RangeInclusive<I>>
+impl<I, U, S> fmt::Debug for Sieve<I, U, S>
 where
-    I: From<u32> + Eq + hash::Hash,
+    I: fmt::Debug,
+    U: fmt::Debug,
 {
-    Sieve {
-        iter: 2.into()..=upper,
-        composite: HashMap::new(),
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// The number of tracked primes included by the alternate format.
+        const SAMPLE: usize = 8;
+
+        struct Sample<'a, I, S>(&'a Composites<I, S>);
+
+        impl<I, S> fmt::Debug for Sample<'_, I, S>
+        where
+            I: fmt::Debug,
+        {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_map().entries(self.0.iter().take(SAMPLE)).finish()
+            }
+        }
+
+        let alternate = f.alternate();
+
+        let mut s = f.debug_struct("Sieve");
+        s.field("iter", &self.iter);
+        s.field("pending", &self.pending);
+        s.field("limit", &self.limit);
+        s.field("last", &self.last);
+        s.field("composites", &self.composite.len());
+
+        if alternate {
+            s.field("sample", &Sample(&self.composite));
+        }
+
+        s.finish()
     }
 }
 
-impl<I, U> Iterator for Sieve<I, U>
-where
-    U: Iterator<Item = I>,
-    I: Eq + hash::Hash + Copy + ops::Add<Output = I> + ops::Mul<Output = I>,
-{
-    type Item = I;
+impl<I, U, S> Sieve<I, U, S> {
+    /// Get the number of primes tracked by the sieve, which is the number of
+    /// pairs produced by [`composites()`][Self::composites()]. Several primes
+    /// can share the same composite, so there may be fewer distinct
+    /// composites.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    ///
+    /// assert!(sieve.by_ref().skip(10).take(10).eq([31, 37, 41, 43, 47, 53, 59, 61, 67, 71]));
+    /// assert_eq!(sieve.size(), 17);
+    /// ```
+    pub fn size(&self) -> usize {
+        self.composite.len()
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        for n in self.iter.by_ref() {
-            if let Some(value) = self.composite.remove(&n) {
-                let mut key = n + value;
+    /// Iterate over the composites currently tracked by the sieve, as pairs of
+    /// the next composite to cross off and the prime it's a multiple of, in
+    /// no particular order.
+    ///
+    /// Every prime from 7 and up which the sieve has passed is tracked by
+    /// exactly one odd multiple of it which hasn't been reached yet, starting
+    /// from its square. Multiples which are divisible by 3 or 5 are never
+    /// tracked, since they're not candidates. Bounded sieves stop tracking
+    /// primes once they have no multiples left within the bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    /// assert_eq!(sieve.nth(9), Some(29));
+    ///
+    /// let mut composites = sieve.composites().collect::<Vec<_>>();
+    /// composites.sort();
+    ///
+    /// assert_eq!(
+    ///     composites,
+    ///     [(49, 7), (121, 11), (169, 13), (289, 17), (361, 19), (529, 23), (841, 29)]
+    /// );
+    ///
+    /// for (composite, prime) in composites {
+    ///     assert!(composite >= prime * prime && composite % (2 * prime) == prime);
+    /// }
+    /// ```
+    pub fn composites(&self) -> impl Iterator<Item = (I, I)> + '_
+    where
+        I: Copy,
+    {
+        self.composite.iter().map(|(&key, &prime)| (key, prime))
+    }
 
-                while self.composite.contains_key(&key) {
-                    key = key + value;
-                }
+    /// Get the largest composite currently tracked by the sieve, which is
+    /// typically the square of the last prime it passed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    /// assert_eq!(sieve.max_tracked(), None);
+    ///
+    /// assert_eq!(sieve.nth(9), Some(29));
+    /// assert_eq!(sieve.max_tracked(), Some(841));
+    /// ```
+    pub fn max_tracked(&self) -> Option<I>
+    where
+        I: Copy + Ord,
+    {
+        self.composite.keys().copied().max()
+    }
 
-                self.composite.insert(key, value);
-            } else {
-                self.composite.insert(n * n, n);
-                return Some(n);
-            }
+    /// Estimate the number of bytes of memory held by the sieve.
+    ///
+    /// This is dominated by the composite map. With `std` it accounts for the
+    /// room the map has allocated rather than just the composites currently in
+    /// it, since the map doesn't shrink after growing. Primes recorded by
+    /// [`Sieve::factorize_with`] and the buffers used when iterating from the
+    /// back are also included. The short-lived lists for the occasional
+    /// composite which is crossed off by several primes at once aren't.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u64>();
+    /// let before = sieve.memory_usage();
+    ///
+    /// assert_eq!(sieve.nth(9_999), Some(104_729));
+    /// assert!(sieve.memory_usage() >= before + sieve.size() * 16);
+    /// ```
+    pub fn memory_usage(&self) -> usize {
+        let primes = self.primes.as_ref().map_or(0, |primes| primes.capacity());
+        let back = self.back.as_ref().map_or(0, back::Back::memory_usage);
+
+        self.composite.memory_usage() + primes * mem::size_of::<I>() + back + mem::size_of::<Self>()
+    }
+
+    /// Turn the sieve into an iterator over twin primes, which are pairs of
+    /// primes `(p, p + 2)`.
+    ///
+    /// Since both primes come from the sieve, a bounded sieve never produces a
+    /// pair whose upper member exceeds its bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let twins = sieve::bounded::<u32>(50).twins();
+    /// assert!(twins.eq([(3, 5), (5, 7), (11, 13), (17, 19), (29, 31), (41, 43)]));
+    /// ```
+    pub fn twins(self) -> Twins<Self>
+    where
+        Self: Iterator,
+    {
+        Twins::new(self)
+    }
+
+    /// Turn the sieve into an iterator over each prime and the gap to the
+    /// prime after it.
+    ///
+    /// The gap is only known once the next prime has been found, so the last
+    /// prime of a bounded sieve isn't produced at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let gaps = sieve::bounded::<u32>(20).gaps();
+    /// assert!(gaps.eq([(2, 1), (3, 2), (5, 2), (7, 4), (11, 2), (13, 4), (17, 2)]));
+    /// ```
+    pub fn gaps(self) -> Gaps<Self>
+    where
+        Self: Iterator,
+    {
+        Gaps::new(self)
+    }
+
+    /// Turn the sieve into an iterator over prime constellations, which are
+    /// groups of primes at the given `offsets` from the smallest one.
+    ///
+    /// The offsets must start at zero and be strictly increasing, otherwise an
+    /// [`InvalidPattern`] error is returned. Primes are kept in a sliding
+    /// window spanning the pattern, so nothing is tested for primality more
+    /// than once. A bounded sieve never produces a constellation with members
+    /// exceeding its bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let triplets = sieve::bounded::<u32>(50).constellations(&[0, 2, 6])?;
+    /// assert!(triplets.eq([[5, 7, 11], [11, 13, 17], [17, 19, 23], [41, 43, 47]]));
+    ///
+    /// assert!(sieve::bounded::<u32>(50).constellations(&[0, 6, 2]).is_err());
+    /// # Ok::<_, sieve::InvalidPattern>(())
+    /// ```
+    pub fn constellations(self, offsets: &[I]) -> Result<Constellations<Self>, InvalidPattern>
+    where
+        Self: Iterator<Item = I>,
+        I: Copy + Ord + FromSmall,
+    {
+        Constellations::new(self, offsets)
+    }
+
+    /// Turn the sieve into an iterator over Sophie Germain primes, which are
+    /// primes `p` such that `2p + 1` is also prime.
+    ///
+    /// The partner `2p + 1` is checked against a second infinite sieve which
+    /// runs ahead of this one, so nothing is tested for primality from
+    /// scratch. Since the partner is tested regardless of the bound, a bounded
+    /// sieve produces every Sophie Germain prime up to its bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let primes = sieve::bounded::<u32>(100).sophie_germain();
+    /// assert!(primes.eq([2, 3, 5, 11, 23, 29, 41, 53, 83, 89]));
+    /// ```
+    pub fn sophie_germain(self) -> SophieGermain<Self>
+    where
+        Self: Iterator<Item = I>,
+        ops::RangeFrom<I>: Iterator<Item = I>,
+        I: SieveInt,
+    {
+        SophieGermain::new(self)
+    }
+
+    /// Turn the sieve into an iterator over safe primes, which are primes `p`
+    /// such that `(p - 1) / 2` is also prime.
+    ///
+    /// These are the partners of the primes produced by
+    /// [`Sieve::sophie_germain`], and are checked against a second sieve in
+    /// the same way. 2 and 3 are not safe primes, since `(p - 1) / 2` is not a
+    /// prime for either of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let primes = sieve::bounded::<u32>(200).safe_primes();
+    /// assert!(primes.eq([5, 7, 11, 23, 47, 59, 83, 107, 167, 179]));
+    /// ```
+    pub fn safe_primes(self) -> SafePrimes<Self>
+    where
+        Self: Iterator<Item = I>,
+        ops::RangeFrom<I>: Iterator<Item = I>,
+        I: SieveInt,
+    {
+        SafePrimes::new(self)
+    }
+
+    /// Turn the sieve into an iterator over the primes which are sums of two
+    /// squares, as `(p, a, b)` with `a^2 + b^2 = p` and `a <= b`.
+    ///
+    /// These are 2 and the primes congruent to 1 modulo 4, which correspond
+    /// to the Gaussian primes `a + bi`. Each is decomposed like
+    /// [`two_squares()`], without testing its primality again. Since that's
+    /// done with `u64` arithmetic, the iterator ends at the first prime which
+    /// doesn't fit in one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let squares = sieve::bounded::<u32>(40).two_squares();
+    /// assert!(squares.eq([(2, 1, 1), (5, 1, 2), (13, 2, 3), (17, 1, 4), (29, 2, 5), (37, 1, 6)]));
+    /// ```
+    pub fn two_squares(self) -> TwoSquares<Self>
+    where
+        Self: Iterator<Item = I>,
+        I: SieveInt,
+    {
+        TwoSquares::new(self)
+    }
+
+    /// Turn the sieve into an iterator over each prime and its index among all
+    /// primes, counting from 1 for the prime 2.
+    ///
+    /// Unlike [`enumerate`][Iterator::enumerate], the index comes from the
+    /// sieve itself, so primes it has already produced or skipped past, like
+    /// with [`skip_to`][Sieve::skip_to], are counted before the first index is
+    /// produced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    /// assert_eq!(sieve.nth(9), Some(29));
+    ///
+    /// let mut indexed = sieve.indexed();
+    /// assert_eq!(indexed.next(), Some((11, 31)));
+    /// assert_eq!(indexed.nth(88), Some((100, 541)));
+    /// ```
+    pub fn indexed(self) -> Indexed<Self>
+    where
+        Self: Iterator<Item = I>,
+    {
+        Indexed::new(self)
+    }
+
+    /// Discard everything the sieve has done, except for the room allocated
+    /// for composites, so that it can be restarted with a fresh iterator.
+    fn rewind(&mut self, iter: U) {
+        self.composite.clear();
+        self.iter = iter;
+        self.pending = None;
+        self.wheel = None;
+        self.tail = Tail::Far;
+        self.last = None;
+        self.back = None;
+        self.count = 0;
+
+        if let Some(primes) = &mut self.primes {
+            primes.clear();
         }
+    }
 
-        None
+    /// Decompose the sieve into its raw state.
+    ///
+    /// The sieve can be reconstructed from the returned [`Parts`] using
+    /// [`Sieve::from_parts`], which continues producing primes exactly where
+    /// this one left off.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    /// assert!(sieve.by_ref().take(4).eq([2, 3, 5, 7]));
+    ///
+    /// let parts = sieve.into_parts();
+    /// assert_eq!(parts.composites, [(49, 7)]);
+    ///
+    /// let mut sieve = sieve::Sieve::from_parts(parts);
+    /// assert!(sieve.take(4).eq([11, 13, 17, 19]));
+    /// ```
+    pub fn into_parts(self) -> Parts<I, U>
+    where
+        I: Clone + CheckedOps,
+    {
+        // The primes produced from the back aren't part of the state, so the
+        // sieve is bounded below the smallest of them instead.
+        let limit = match self.back.as_ref().and_then(back::Back::lowest) {
+            Some(lowest) => I::from_u64(lowest - 1),
+            None => self.limit,
+        };
+
+        Parts {
+            iter: self.iter,
+            pending: self.pending,
+            last: self.last,
+            composites: self.composite.into_vec(),
+            limit,
+            count: self.count,
+        }
     }
+}
 
+impl<I, U> Sieve<I, U>
+where
+    I: Ord + hash::Hash,
+{
+    /// Reconstruct a sieve from its raw state.
+    ///
+    /// The parts must be consistent with each other, such as when they've been
+    /// produced by [`Sieve::into_parts`]. Inconsistent parts don't cause any
+    /// undefined behavior, but the sieve will produce the wrong numbers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sieve::{Parts, Sieve};
+    ///
+    /// let parts = Parts {
+    ///     iter: 12u32..,
+    ///     pending: Some(11),
+    ///     last: Some(7),
+    ///     composites: vec![(49, 7)],
+    ///     limit: None,
+    ///     count: 4,
+    /// };
+    ///
+    /// let sieve = Sieve::from_parts(parts);
+    /// assert!(sieve.take(4).eq([11, 13, 17, 19]));
+    /// ```
     #[inline]
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        self.iter.size_hint()
+    pub fn from_parts(parts: Parts<I, U>) -> Self {
+        Self::from_parts_with_hasher(parts, DefaultHashBuilder::default())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl<I, U, S> Sieve<I, U, S>
+where
+    I: Ord + hash::Hash,
+    S: hash::BuildHasher,
+{
+    /// Reconstruct a sieve from its raw state, using the given hash builder for
+    /// the composite map.
+    ///
+    /// See [`Sieve::from_parts`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::hash_map::RandomState;
+    /// use sieve::Sieve;
+    ///
+    /// let mut sieve = sieve::infinite::<u32>();
+    /// assert!(sieve.by_ref().take(4).eq([2, 3, 5, 7]));
+    ///
+    /// let sieve = Sieve::from_parts_with_hasher(sieve.into_parts(), RandomState::new());
+    /// assert!(sieve.take(4).eq([11, 13, 17, 19]));
+    /// ```
+    pub fn from_parts_with_hasher(parts: Parts<I, U>, hasher: S) -> Self {
+        let mut composite = Composites::with_hasher(hasher);
+        composite.reserve(parts.composites.len());
 
-    #[test]
-    fn it_works() {
-        let mut sieve = infinite::<u32>();
-        let primes = sieve.by_ref().take(100).collect::<Vec<u32>>();
+        for (key, prime) in parts.composites {
+            composite.insert(key, prime);
+        }
 
-        assert_eq!(
-            primes,
-            vec![
-                2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79,
-                83, 89, 97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167,
-                173, 179, 181, 191, 193, 197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257,
-                263, 269, 271, 277, 281, 283, 293, 307, 311, 313, 317, 331, 337, 347, 349, 353,
-                359, 367, 373, 379, 383, 389, 397, 401, 409, 419, 421, 431, 433, 439, 443, 449,
-                457, 461, 463, 467, 479, 487, 491, 499, 503, 509, 521, 523, 541,
-            ]
+        Sieve {
+            composite,
+            iter: parts.iter,
+            pending: parts.pending,
+            wheel: None,
+            tail: Tail::Far,
+            limit: parts.limit,
+            last: parts.last,
+            primes: None,
+            back: None,
+            count: parts.count,
+            total: AtomicUsize::new(usize::MAX),
+        }
+    }
+
+    /// Reserve room for at least `additional` more composites, so that the
+    /// composite map doesn't have to grow while they're being tracked.
+    ///
+    /// An infinite sieve tracks roughly one composite for every prime it has
+    /// produced, while a bounded one only tracks one for every prime up to the
+    /// square root of its bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u64>();
+    /// sieve.reserve(1000);
+    ///
+    /// assert_eq!(sieve.nth(999), Some(7919));
+    /// assert_eq!(sieve.size(), 997);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.composite.reserve(additional);
+    }
+}
+
+/// The raw state of a [`Sieve`].
+///
+/// This is produced by [`Sieve::into_parts`] and consumed by
+/// [`Sieve::from_parts`], which allows the state of a sieve to be inspected or
+/// persisted in any format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parts<I, U> {
+    /// The remaining numbers to sieve. These must be consecutive integers.
+    pub iter: U,
+    /// A number which has been taken from `iter` but not yet sieved. If
+    /// present, it comes immediately before the numbers in `iter`.
+    pub pending: Option<I>,
+    /// The largest prime which has been passed, if any. Every number up to it
+    /// must have been sieved.
+    pub last: Option<I>,
+    /// The composites being tracked, as pairs of the next multiple to be
+    /// crossed off and the prime it's a multiple of, in no particular order.
+    ///
+    /// Only primes from 7 and up are tracked, and the multiples are never
+    /// divisible by 2, 3 or 5. Several primes can share a multiple.
+    pub composites: Vec<(I, I)>,
+    /// The largest number the sieve will produce, if it's known, past which
+    /// composites are never tracked.
+    pub limit: Option<I>,
+    /// The number of primes which have been passed, which are all the primes
+    /// up to `last`.
+    pub count: usize,
+}
+
+/// An infinite sieve, as constructed by [`infinite()`].
+///
+/// This names the sieve without spelling out the iterator it draws candidates
+/// from, which is an implementation detail.
+///
+/// # Examples
+///
+/// ```
+/// fn my_primes() -> sieve::Infinite<u64> {
+///     sieve::infinite()
+/// }
+///
+/// assert!(my_primes().take(5).eq([2, 3, 5, 7, 11]));
+/// ```
+pub type Infinite<I, S = DefaultHashBuilder> = Sieve<I, ops::RangeFrom<I>, S>;
+
+/// A bounded sieve, as constructed by [`bounded()`].
+///
+/// Like [`Infinite`], this names the sieve without spelling out the iterator
+/// it draws candidates from.
+///
+/// # Examples
+///
+/// ```
+/// fn primes_below_100() -> sieve::Bounded<u32> {
+///     sieve::bounded(100)
+/// }
+///
+/// assert_eq!(primes_below_100().len(), 25);
+/// assert_eq!(primes_below_100().next_back(), Some(97));
+/// ```
+pub type Bounded<I, S = DefaultHashBuilder> = Sieve<I, ops::RangeInclusive<I>, S>;
+
+/// Generate infinite primes.
+///
+/// # Examples
+///
+/// ```
+/// let iter = sieve::infinite::<u32>().skip(10).take(10);
+///
+/// assert!(
+///     iter.eq([31, 37, 41, 43, 47, 53, 59, 61, 67, 71])
+/// );
+/// ```
+#[inline]
+pub fn infinite<I>() -> Infinite<I>
+where
+    I: SieveInt,
+{
+    infinite_with_hasher(DefaultHashBuilder::default())
+}
+
+/// Generate infinite primes, using the given hash builder for the composite
+/// map.
+///
+/// This mirrors `HashMap::with_hasher` from `std`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::hash_map::RandomState;
+///
+/// let iter = sieve::infinite_with_hasher::<u32, _>(RandomState::new()).skip(10).take(10);
+///
+/// assert!(
+///     iter.eq([31, 37, 41, 43, 47, 53, 59, 61, 67, 71])
+/// );
+/// ```
+#[inline]
+pub fn infinite_with_hasher<I, S>(hasher: S) -> Infinite<I, S>
+where
+    I: SieveInt,
+    S: hash::BuildHasher,
+{
+    Sieve {
+        iter: I::from_small(2)..,
+        composite: Composites::with_hasher(hasher),
+        pending: None,
+        wheel: None,
+        tail: Tail::Far,
+        limit: None,
+        last: None,
+        primes: None,
+        back: None,
+        count: 0,
+        total: AtomicUsize::new(usize::MAX),
+    }
+}
+
+/// Generate infinite primes, with room for `capacity` composites before the
+/// composite map has to grow.
+///
+/// Producing the first `n` primes tracks roughly `n` composites, so this
+/// avoids repeatedly rehashing the map when `n` is known up front. See
+/// [`Sieve::reserve`].
+///
+/// # Examples
+///
+/// ```
+/// let mut sieve = sieve::infinite_with_capacity::<u64>(10_000);
+///
+/// assert_eq!(sieve.nth(9_999), Some(104_729));
+/// ```
+#[inline]
+pub fn infinite_with_capacity<I>(capacity: usize) -> Infinite<I>
+where
+    I: SieveInt,
+{
+    let mut sieve = infinite();
+    sieve.reserve(capacity);
+    sieve
+}
+
+/// Construct a bounded sieve, which stops returning values after it's reached
+/// the given numerical bound.
+///
+/// # Examples
+///
+/// ```
+/// let iter = sieve::bounded::<u32>(100).skip(10);
+///
+/// assert!(
+///     iter.eq([31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97])
+/// );
+/// ```
+#[inline]
+pub fn bounded<I>(upper: I) -> Bounded<I>
+where
+    I: SieveInt,
+{
+    bounded_with_hasher(upper, DefaultHashBuilder::default())
+}
+
+/// Construct a bounded sieve, using the given hash builder for the composite
+/// map.
+///
+/// This mirrors `HashMap::with_hasher` from `std`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::hash_map::RandomState;
+///
+/// let iter = sieve::bounded_with_hasher::<u32, _>(100, RandomState::new()).skip(10);
+///
+/// assert!(
+///     iter.eq([31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97])
+/// );
+/// ```
+#[inline]
+pub fn bounded_with_hasher<I, S>(upper: I, hasher: S) -> Bounded<I, S>
+where
+    I: SieveInt,
+    S: hash::BuildHasher,
+{
+    Sieve {
+        iter: I::from_small(2)..=upper,
+        composite: Composites::with_hasher(hasher),
+        pending: None,
+        wheel: None,
+        tail: Tail::Far,
+        limit: Some(upper),
+        last: None,
+        primes: None,
+        back: None,
+        count: 0,
+        total: AtomicUsize::new(usize::MAX),
+    }
+}
+
+/// Construct a bounded sieve over the primes strictly less than `upper`.
+///
+/// This is like [`bounded()`], except the bound itself is excluded even if
+/// it's a prime. It's driven by an [`ops::Range`] instead of an
+/// [`ops::RangeInclusive`], so it's empty if `upper` is 2 or less.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::bounded(97u32).last(), Some(97));
+/// assert_eq!(sieve::bounded_exclusive(97u32).last(), Some(89));
+/// assert_eq!(sieve::bounded_exclusive(98u32).last(), Some(97));
+///
+/// assert_eq!(sieve::bounded(100u32).count(), 25);
+/// assert_eq!(sieve::bounded_exclusive(100u32).count(), 25);
+///
+/// assert!(sieve::bounded(2u32).eq([2]));
+/// assert_eq!(sieve::bounded_exclusive(2u32).next(), None);
+/// ```
+#[inline]
+pub fn bounded_exclusive<I>(upper: I) -> Sieve<I, ops::Range<I>>
+where
+    I: SieveInt,
+{
+    let one = I::from_small(1);
+
+    // The limit is the largest number the sieve can produce, which is
+    // clamped so that it can't underflow.
+    let limit = if upper > one { upper - one } else { one };
+
+    Sieve {
+        iter: I::from_small(2)..upper,
+        composite: Composites::with_hasher(DefaultHashBuilder::default()),
+        pending: None,
+        wheel: None,
+        tail: Tail::Far,
+        limit: Some(limit),
+        last: None,
+        primes: None,
+        back: None,
+        count: 0,
+        total: AtomicUsize::new(usize::MAX),
+    }
+}
+
+/// Construct a bounded sieve, with room for `capacity` composites before the
+/// composite map has to grow.
+///
+/// Only primes up to the square root of `upper` have composites tracked, so
+/// the map never needs to hold more than `π(√upper)` of them. See
+/// [`Sieve::reserve`].
+///
+/// # Examples
+///
+/// ```
+/// let sieve = sieve::bounded_with_capacity::<u64>(1_000_000, 168);
+///
+/// assert_eq!(sieve.count(), 78_498);
+/// ```
+#[inline]
+pub fn bounded_with_capacity<I>(upper: I, capacity: usize) -> Bounded<I>
+where
+    I: SieveInt,
+{
+    let mut sieve = bounded(upper);
+    sieve.reserve(capacity);
+    sieve
+}
+
+/// Generate prime constellations from an infinite sieve.
+///
+/// See [`Sieve::constellations`].
+///
+/// # Examples
+///
+/// ```
+/// let mut sexy = sieve::constellations::<u32>(&[0, 6])?;
+/// assert_eq!(sexy.next(), Some(vec![5, 11]));
+/// assert_eq!(sexy.next(), Some(vec![7, 13]));
+/// # Ok::<_, sieve::InvalidPattern>(())
+/// ```
+#[inline]
+pub fn constellations<I>(offsets: &[I]) -> Result<Constellations<Infinite<I>>, InvalidPattern>
+where
+    ops::RangeFrom<I>: Iterator<Item = I>,
+    I: SieveInt,
+{
+    infinite().constellations(offsets)
+}
+
+/// Generate Sophie Germain primes from an infinite sieve.
+///
+/// See [`Sieve::sophie_germain`].
+///
+/// # Examples
+///
+/// ```
+/// let mut primes = sieve::sophie_germain::<u64>();
+/// assert_eq!(primes.nth(999), Some(82_493));
+/// ```
+#[inline]
+pub fn sophie_germain<I>() -> SophieGermain<Infinite<I>>
+where
+    ops::RangeFrom<I>: Iterator<Item = I>,
+    I: SieveInt,
+{
+    infinite().sophie_germain()
+}
+
+/// Generate safe primes from an infinite sieve.
+///
+/// See [`Sieve::safe_primes`].
+///
+/// # Examples
+///
+/// ```
+/// let mut primes = sieve::safe_primes::<u64>();
+/// assert_eq!(primes.next(), Some(5));
+/// assert_eq!(primes.next(), Some(7));
+/// assert_eq!(primes.next(), Some(11));
+/// ```
+#[inline]
+pub fn safe_primes<I>() -> SafePrimes<Infinite<I>>
+where
+    ops::RangeFrom<I>: Iterator<Item = I>,
+    I: SieveInt,
+{
+    infinite().safe_primes()
+}
+
+impl<I, U, S> Sieve<I, U, S>
+where
+    U: Iterator<Item = I>,
+    I: SieveInt,
+    S: hash::BuildHasher,
+{
+    /// Get the next candidate, preferring one which has been put back.
+    ///
+    /// Candidates are 2, 3, 5 and every number coprime to 30 which is at least
+    /// 7, which are found by walking the wheel of gaps between them.
+    #[inline]
+    fn candidate(&mut self) -> Option<I> {
+        if let Some(n) = self.pending.take() {
+            return Some(n);
+        }
+
+        if let Some(index) = self.wheel {
+            let n = self.draw(WHEEL_GAPS[index])?;
+            self.wheel = Some((index + 1) % WHEEL_GAPS.len());
+            return Some(n);
+        }
+
+        loop {
+            let n = self.draw(1)?;
+
+            if n < I::from_small(7) {
+                if n == I::from_small(2) || n == I::from_small(3) || n == I::from_small(5) {
+                    return Some(n);
+                }
+
+                continue;
+            }
+
+            let residue = n % I::from_small(30);
+
+            if let Some(index) = WHEEL_RESIDUES
+                .iter()
+                .position(|&r| I::from_small(r) == residue)
+            {
+                self.wheel = Some(index);
+                return Some(n);
+            }
+        }
+    }
+
+    /// Take the `k`th next number from the underlying iterator, where `k` is
+    /// at most the largest gap on the wheel.
+    ///
+    /// See [`Tail`] for how the end of the integer type is handled.
+    fn draw(&mut self, k: usize) -> Option<I> {
+        let start = match self.tail {
+            Tail::Far => {
+                let n = self.iter.nth(k - 1)?;
+
+                // Any gap on the wheel can be taken from at least one past the
+                // largest gap before the end.
+                if n.checked_add(I::from_small(7)).is_none() {
+                    self.tail = match n.checked_add(I::from_small(1)) {
+                        Some(start) => Tail::Near(start),
+                        None => Tail::Done,
+                    };
+                }
+
+                return Some(n);
+            }
+            Tail::Near(start) => start,
+            Tail::Done => return None,
+        };
+
+        let n = match start.checked_add(I::from_small((k - 1) as u8)) {
+            Some(n) => n,
+            None => {
+                self.tail = Tail::Done;
+                return None;
+            }
+        };
+
+        if let Some(next) = n.checked_add(I::from_small(1)) {
+            let n = self.iter.nth(k - 1);
+
+            self.tail = match n {
+                Some(..) => Tail::Near(next),
+                None => Tail::Done,
+            };
+
+            return n;
+        }
+
+        // The last number of the type is taken by hand, unless the iterator
+        // ends before it.
+        self.tail = Tail::Done;
+
+        if k > 1 {
+            self.iter.nth(k - 2)?;
+        }
+
+        if self.iter.size_hint().1 == Some(0) {
+            return None;
+        }
+
+        Some(n)
+    }
+
+    /// Feed a single candidate through the sieve, returning `true` if it's
+    /// prime.
+    fn step(&mut self, n: I) -> bool {
+        // Multiples of 2, 3 and 5 are never candidates, so they don't need to
+        // be tracked.
+        if n >= I::from_small(7) {
+            if let Some(strides) = self.composite.remove(&n) {
+                for prime in strides {
+                    // Even multiples are skipped by stepping twice the prime.
+                    if let Some(step) = prime.checked_add(prime) {
+                        if let Some(key) = n.checked_add(step) {
+                            self.track(key, prime, step);
+                        }
+                    }
+                }
+
+                return false;
+            }
+
+            if let Some(square) = n.checked_mul(n) {
+                if self.within_limit(square) {
+                    self.composite.insert(square, n);
+                }
+            }
+        }
+
+        self.passed(n);
+        true
+    }
+
+    /// Note that the sieve has passed the prime `p`.
+    #[inline]
+    fn passed(&mut self, p: I) {
+        self.last = Some(p);
+        self.count += 1;
+
+        if let Some(primes) = &mut self.primes {
+            primes.push(p);
+        }
+    }
+
+    /// Test if `n` is within the limit of the sieve.
+    #[inline]
+    fn within_limit(&self, n: I) -> bool {
+        match self.limit {
+            Some(limit) => n <= limit,
+            None => true,
+        }
+    }
+
+    /// Get the exact number of primes remaining in a bounded sieve, counting
+    /// the primes up to the bound with [`prime_pi()`] the first time it's
+    /// needed.
+    ///
+    /// Returns `None` if the sieve isn't bounded, if the primes haven't been
+    /// counted yet and the bound is above `max`, or if the count doesn't fit
+    /// in a `usize`.
+    fn remaining(&self, max: u64) -> Option<usize> {
+        let mut total = self.total.load(Ordering::Relaxed);
+
+        if total == usize::MAX {
+            let limit = self.limit?.to_u64().filter(|&limit| limit <= max)?;
+            total = usize::try_from(counting::prime_pi(limit)).ok()?;
+            self.total.store(total, Ordering::Relaxed);
+        }
+
+        // Parts which have been put together by hand might claim to have
+        // passed more primes than there are.
+        Some(
+            total
+                .saturating_sub(self.count)
+                .saturating_sub(self.popped()),
+        )
+    }
+
+    /// Get the number of primes produced from the back.
+    #[inline]
+    fn popped(&self) -> usize {
+        self.back.as_ref().map_or(0, back::Back::popped)
+    }
+
+    /// Track the first multiple of `prime` from `key` onwards, walking by
+    /// `step`, which is a candidate. It's tracked alongside any other primes
+    /// which already cross off the same composite.
+    ///
+    /// Composites which overflow or exceed the limit can never be reached, so
+    /// they're simply dropped. Multiples of 3 and 5 are stepped past since
+    /// they're never candidates.
+    fn track(&mut self, mut key: I, prime: I, step: I) {
+        let zero = I::from_small(0);
+
+        while self.within_limit(key) {
+            if key % I::from_small(3) != zero && key % I::from_small(5) != zero {
+                self.composite.insert(key, prime);
+                return;
+            }
+
+            key = match key.checked_add(step) {
+                Some(key) => key,
+                None => return,
+            };
+        }
+    }
+
+    /// Track the first odd multiple of `prime` which is at least `n`, but no
+    /// smaller than its square.
+    fn track_from(&mut self, n: I, prime: I) {
+        let zero = I::from_small(0);
+
+        let square = match prime.checked_mul(prime) {
+            Some(square) => square,
+            None => return,
+        };
+
+        let mut key = if square >= n {
+            square
+        } else {
+            match (n / prime).checked_mul(prime) {
+                Some(key) if key >= n => key,
+                Some(key) => match key.checked_add(prime) {
+                    Some(key) => key,
+                    None => return,
+                },
+                None => return,
+            }
+        };
+
+        if key % I::from_small(2) == zero {
+            key = match key.checked_add(prime) {
+                Some(key) => key,
+                None => return,
+            };
+        }
+
+        if let Some(step) = prime.checked_add(prime) {
+            self.track(key, prime, step);
+        }
+    }
+
+    /// Track the first odd multiple of `prime` which is at least `n`, but no
+    /// smaller than its square, like [`Sieve::track_from`] but computed in
+    /// `u64`.
+    fn track_past(&mut self, n: u64, prime: I) {
+        let p = match prime.to_u64() {
+            Some(p) => p,
+            None => return,
+        };
+
+        let key = match (p.checked_mul(p), ((n - 1) / p + 1).checked_mul(p)) {
+            (Some(square), Some(key)) => square.max(key),
+            _ => return,
+        };
+
+        let key = if key % 2 == 0 {
+            key.checked_add(p)
+        } else {
+            Some(key)
+        };
+
+        if let (Some(key), Some(step)) = (key.and_then(I::from_u64), prime.checked_add(prime)) {
+            self.track(key, prime, step);
+        }
+    }
+
+    /// Skip past `n` primes in bulk, so that the next prime produced is the
+    /// one [`Iterator::nth`] would return.
+    ///
+    /// The primes in between are found with [`starting_at()`], after which
+    /// every tracked composite is moved past them and the underlying iterator
+    /// is moved forward without feeding anything through. Returns the number
+    /// of primes which are left to skip, which is `n` if the position of the
+    /// sieve doesn't fit in a `u64`.
+    fn skip_primes(&mut self, n: usize) -> usize {
+        let c = match self.candidate() {
+            Some(c) => c,
+            None => return n,
+        };
+
+        self.pending = Some(c);
+
+        let lo = match c.to_u64() {
+            Some(lo) => lo,
+            None => return n,
+        };
+
+        // Without a limit which fits in a `u64`, running out of primes below
+        // the end of `u64` doesn't mean the sieve is exhausted.
+        let limit = self.limit.and_then(CheckedOps::to_u64);
+
+        // `n` can be far more than the number of primes left, so the primes
+        // aren't allocated for up front.
+        let mut primes = Vec::new();
+        let mut target = None;
+
+        for p in starting_at(lo).take_while(|&p| p <= limit.unwrap_or(u64::MAX)) {
+            let prime = match I::from_u64(p) {
+                Some(prime) => prime,
+                None => break,
+            };
+
+            if primes.len() == n {
+                target = Some((p, prime));
+                break;
+            }
+
+            primes.push(prime);
+        }
+
+        let (to, target) = match target {
+            // The iterator can't be moved next to the end of the type in bulk,
+            // see `Tail`.
+            Some((_, target)) if target.checked_add(I::from_small(8)).is_none() => return n,
+            Some(target) => target,
+            None if limit.is_some() => {
+                // Fewer than `n` primes are left, so the sieve is exhausted.
+                for p in primes {
+                    self.passed(p);
+                }
+
+                self.pending = None;
+                self.composite.clear();
+                while self.iter.nth(usize::MAX).is_some() {}
+                return 0;
+            }
+            None => return n,
+        };
+
+        let stale = self
+            .composite
+            .keys()
+            .copied()
+            .filter(|&key| key < target)
+            .collect::<Vec<_>>();
+
+        for key in stale {
+            if let Some(strides) = self.composite.remove(&key) {
+                for prime in strides {
+                    self.track_past(to, prime);
+                }
+            }
+        }
+
+        for p in primes {
+            // Only primes from 7 and up are tracked.
+            if p >= I::from_small(7) {
+                self.track_past(to, p);
+            }
+
+            self.passed(p);
+        }
+
+        // The candidate was put back, so the iterator is just past it. The
+        // target is at least 7 since `n` is large, so it's on the wheel.
+        let mut skip = to - lo;
+
+        while skip > 0 {
+            let step = usize::try_from(skip).unwrap_or(usize::MAX);
+            self.iter.nth(step - 1);
+            skip -= step as u64;
+        }
+
+        let residue = target % I::from_small(30);
+        self.pending = Some(target);
+        self.wheel = WHEEL_RESIDUES
+            .iter()
+            .position(|&r| I::from_small(r) == residue);
+        0
+    }
+
+    /// Advance the sieve from `pos`, which is the next number it would feed
+    /// through, to `n` without yielding anything.
+    ///
+    /// Where the numbers fit in a `u64` this is done in bulk: the primes in
+    /// between are found with a segmented sieve, and every tracked composite
+    /// is moved straight past `n`, after which `reposition` is called to move
+    /// the underlying iterator to `n`. Otherwise candidates are fed through
+    /// one at a time.
+    fn advance(&mut self, pos: I, n: I, reposition: impl FnOnce(&mut U)) {
+        if n <= pos {
+            return;
+        }
+
+        let (lo, hi) = match (pos.to_u64(), n.to_u64()) {
+            (Some(lo), Some(hi)) => (lo, hi),
+            _ => {
+                while let Some(c) = self.candidate() {
+                    if c >= n {
+                        self.pending = Some(c);
+                        break;
+                    }
+
+                    self.step(c);
+                }
+
+                return;
+            }
+        };
+
+        let stale = self
+            .composite
+            .keys()
+            .copied()
+            .filter(|&key| key < n)
+            .collect::<Vec<_>>();
+
+        for key in stale {
+            if let Some(strides) = self.composite.remove(&key) {
+                for prime in strides {
+                    self.track_from(n, prime);
+                }
+            }
+        }
+
+        for p in range::<u64>(lo, hi - 1) {
+            let p = match I::from_u64(p) {
+                Some(p) => p,
+                None => break,
+            };
+
+            // Only primes from 7 and up are tracked.
+            if p >= I::from_small(7) {
+                self.track_from(n, p);
+            }
+
+            self.passed(p);
+        }
+
+        self.pending = None;
+        self.wheel = None;
+        self.tail = match n.checked_add(I::from_small(7)) {
+            Some(..) => Tail::Far,
+            None => Tail::Near(n),
+        };
+        reposition(&mut self.iter);
+    }
+
+    /// Get the next prime without consuming it, so that the following call to
+    /// [`next`][Iterator::next] returns the same prime.
+    ///
+    /// Composites up to the prime are fed through the sieve, but the prime
+    /// itself is put back as the next candidate without being passed. So it's
+    /// still counted among the remaining primes, and queries like
+    /// [`is_prime`][Sieve::is_prime] answer as if it hadn't been peeked at.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::bounded::<u32>(20);
+    /// assert_eq!(sieve.nth(3), Some(7));
+    ///
+    /// assert_eq!(sieve.peek(), Some(11));
+    /// assert_eq!(sieve.peek(), Some(11));
+    /// assert_eq!(sieve.len(), 4);
+    ///
+    /// assert_eq!(sieve.next(), Some(11));
+    /// assert!(sieve.eq([13, 17, 19]));
+    /// ```
+    pub fn peek(&mut self) -> Option<I> {
+        loop {
+            let c = self.candidate()?;
+
+            // Candidates below 7 are 2, 3 and 5, and other candidates are
+            // prime unless they're a tracked composite.
+            if c < I::from_small(7) || !self.composite.contains(&c) {
+                self.pending = Some(c);
+                return Some(c);
+            }
+
+            self.step(c);
+        }
+    }
+
+    /// Get the last prime the sieve produced from the front, or `None` if it
+    /// hasn't produced any yet.
+    ///
+    /// This includes primes consumed in bulk, like those skipped over by
+    /// [`nth`][Iterator::nth] and [`skip_to`][Sieve::skip_to] or passed while
+    /// answering [`is_prime`][Sieve::is_prime], but not a prime which has only
+    /// been [peeked][Sieve::peek] at. Once a bounded sieve is exhausted from
+    /// the front, this is the largest prime up to its bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::bounded::<u32>(100);
+    /// assert_eq!(sieve.last_yielded(), None);
+    ///
+    /// assert_eq!(sieve.by_ref().take_while(|&p| p < 10).count(), 4);
+    /// assert_eq!(sieve.last_yielded(), Some(11));
+    ///
+    /// sieve.skip_to(50);
+    /// assert_eq!(sieve.last_yielded(), Some(47));
+    ///
+    /// assert_eq!(sieve.by_ref().count(), 10);
+    /// assert_eq!(sieve.last_yielded(), Some(97));
+    /// ```
+    #[inline]
+    pub fn last_yielded(&self) -> Option<I> {
+        self.last
+    }
+
+    /// Test if the given number is prime.
+    ///
+    /// If `n` is ahead of the sieve, the sieve is advanced up to and including
+    /// `n`, so the next call to [`next`][Iterator::next] returns the first
+    /// prime *after* `n`. Any primes in between are skipped. This keeps
+    /// repeated queries with increasing arguments from redoing any work.
+    ///
+    /// Numbers which the sieve has already passed are answered through trial
+    /// division, which costs `O(sqrt(n))` and is dwarfed by the work already
+    /// spent sieving up to `n`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    ///
+    /// assert_eq!(sieve.next(), Some(2));
+    /// assert_eq!(sieve.next(), Some(3));
+    ///
+    /// // Advances the sieve past 9, skipping 5 and 7.
+    /// assert!(!sieve.is_prime(9));
+    /// assert_eq!(sieve.next(), Some(11));
+    ///
+    /// // Numbers which have already been passed are still answered.
+    /// assert!(sieve.is_prime(7));
+    /// assert!(!sieve.is_prime(1));
+    /// assert_eq!(sieve.next(), Some(13));
+    /// ```
+    pub fn is_prime(&mut self, n: I) -> bool {
+        while let Some(c) = self.candidate() {
+            if c > n {
+                self.pending = Some(c);
+                break;
+            }
+
+            let prime = self.step(c);
+
+            if c == n {
+                return prime;
+            }
+        }
+
+        trial_division(n)
+    }
+
+    /// Get the prime factorization of `n` like [`factorize()`], but using the
+    /// primes this sieve has produced.
+    ///
+    /// Once this has been called, the sieve records every prime it passes.
+    /// If the recorded primes don't reach `sqrt(n)`, the sieve is advanced
+    /// until they do, in which case the primes passed won't be yielded by
+    /// [`next`][Iterator::next]. Factorizing many numbers in increasing order
+    /// therefore only sieves as far as the square root of the largest one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u64>();
+    ///
+    /// assert_eq!(sieve.factorize_with(360), [(2, 3), (3, 2), (5, 1)]);
+    /// assert_eq!(sieve.factorize_with(1_000_000_007), [(1_000_000_007, 1)]);
+    ///
+    /// // The sieve has been advanced to the square root of the largest number.
+    /// assert_eq!(sieve.next(), Some(31_643));
+    /// ```
+    pub fn factorize_with(&mut self, n: I) -> Vec<(I, u32)>
+    where
+        ops::RangeInclusive<I>: Iterator<Item = I>,
+    {
+        let one = I::from_small(1);
+
+        if n <= one {
+            return Vec::new();
+        }
+
+        self.record_primes();
+
+        loop {
+            match self.last {
+                Some(last) if last > n / last => break,
+                _ => {}
+            }
+
+            if self.next().is_none() {
+                break;
+            }
+        }
+
+        let primes = self.primes.as_deref().unwrap_or_default();
+
+        // If the sieve is exhausted before reaching `sqrt(n)`, division
+        // carries on with every number past the last prime. These can't
+        // divide what's left unless they're prime.
+        let first = match primes.last() {
+            Some(&p) => p + one,
+            None => I::from_small(2),
+        };
+
+        let rest = core::iter::successors(Some(first), |&d| d.checked_add(one));
+
+        factor::divide(n, primes.iter().copied().chain(rest))
+    }
+
+    /// Get the 1-based index of `p` among all primes, or `None` if it isn't
+    /// prime.
+    ///
+    /// If `p` is ahead of the sieve, the sieve is advanced up to and including
+    /// `p` like with [`is_prime`][Sieve::is_prime], counting the primes it
+    /// passes along the way. If the sieve has already passed `p`, it's looked
+    /// up among the primes the sieve records once this or
+    /// [`factorize_with`][Sieve::factorize_with] has been used, which starts
+    /// out by collecting every prime passed so far.
+    ///
+    /// Primes which a bounded sieve doesn't reach from the front, because
+    /// they're beyond its bound or were produced by
+    /// [`next_back`][DoubleEndedIterator::next_back], are counted with a
+    /// [`range()`] sieve instead. These only have an index if they fit in a
+    /// `u64`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    ///
+    /// assert_eq!(sieve.index_of(541), Some(100));
+    /// assert_eq!(sieve.next(), Some(547));
+    ///
+    /// // Primes which have already been passed are still found.
+    /// assert_eq!(sieve.index_of(2), Some(1));
+    /// assert_eq!(sieve.index_of(100), None);
+    /// ```
+    pub fn index_of(&mut self, p: I) -> Option<u64>
+    where
+        ops::RangeInclusive<I>: Iterator<Item = I>,
+    {
+        if matches!(self.last, Some(last) if p <= last) {
+            let primes = self.record_primes();
+            let index = primes.binary_search(&p).ok()?;
+            return Some(index as u64 + 1);
+        }
+
+        while let Some(c) = self.candidate() {
+            if c > p {
+                self.pending = Some(c);
+                return None;
+            }
+
+            let prime = self.step(c);
+
+            if c == p {
+                return if prime { Some(self.count as u64) } else { None };
+            }
+        }
+
+        let n = p.to_u64()?;
+
+        if !is_prime_u64(n) {
+            return None;
+        }
+
+        let lo = match self.last.and_then(CheckedOps::to_u64) {
+            Some(last) => last + 1,
+            None => 0,
+        };
+
+        Some(self.count as u64 + range(lo, n).count() as u64)
+    }
+
+    /// Start recording the primes the sieve passes, if it isn't already,
+    /// returning the primes recorded so far.
+    fn record_primes(&mut self) -> &[I]
+    where
+        ops::RangeInclusive<I>: Iterator<Item = I>,
+    {
+        let last = self.last;
+
+        self.primes.get_or_insert_with(|| match last {
+            Some(last) => bounded(last).collect(),
+            None => Vec::new(),
+        })
+    }
+}
+
+impl<I, S> Sieve<I, ops::RangeFrom<I>, S>
+where
+    ops::RangeFrom<I>: Iterator<Item = I>,
+    I: SieveInt,
+    S: hash::BuildHasher,
+{
+    /// Get the first prime which is at least `n`.
+    ///
+    /// If `n` is ahead of the sieve, the sieve is fast-forwarded to `n` in
+    /// bulk, and subsequent calls to [`next`][Iterator::next] continue with the
+    /// primes after the returned one. Numbers which don't fit in a `u64` can't
+    /// be skipped in bulk, and are fed through the sieve one at a time.
+    ///
+    /// If `n` is behind the sieve, the primes between `n` and the current
+    /// position of the sieve are searched through trial division without
+    /// affecting the sieve. Only if there are none is the sieve advanced, in
+    /// which case this behaves like [`next`][Iterator::next].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    ///
+    /// assert_eq!(sieve.next_prime_after(1000), Some(1009));
+    /// assert_eq!(sieve.next(), Some(1013));
+    ///
+    /// assert_eq!(sieve.next_prime_after(1_000_000), Some(1_000_003));
+    /// assert_eq!(sieve.next(), Some(1_000_033));
+    ///
+    /// // Primes which have already been passed are still found.
+    /// assert_eq!(sieve.next_prime_after(1001), Some(1009));
+    /// assert_eq!(sieve.next(), Some(1_000_037));
+    /// ```
+    pub fn next_prime_after(&mut self, n: I) -> Option<I> {
+        let pos = self.pending.unwrap_or(self.iter.start);
+
+        if n < pos {
+            if let Some(p) = trial_search(n, pos) {
+                return Some(p);
+            }
+        }
+
+        self.advance(pos, n, |iter| *iter = n..);
+        self.next()
+    }
+
+    /// Advance the sieve without yielding anything, so that the next call to
+    /// [`next`][Iterator::next] returns the first prime which is at least `n`.
+    ///
+    /// This is done in bulk like in [`Sieve::next_prime_after`], by moving
+    /// every tracked composite straight past `n`. Skipping to a number the
+    /// sieve has already passed does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    ///
+    /// sieve.skip_to(1000);
+    /// assert_eq!(sieve.next(), Some(1009));
+    ///
+    /// // Skipping backwards is a no-op.
+    /// sieve.skip_to(10);
+    /// assert_eq!(sieve.next(), Some(1013));
+    /// ```
+    pub fn skip_to(&mut self, n: I) {
+        let pos = self.pending.unwrap_or(self.iter.start);
+        self.advance(pos, n, |iter| *iter = n..);
+    }
+
+    /// Rewind the sieve so that it starts over from 2.
+    ///
+    /// The composite map is cleared, but with `std` it keeps the room it has
+    /// allocated, so a sieve which is reset and iterated again as far as before
+    /// doesn't have to allocate again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    /// assert_eq!(sieve.nth(999), Some(7919));
+    /// let memory_usage = sieve.memory_usage();
+    ///
+    /// sieve.reset();
+    /// assert_eq!(sieve.size(), 0);
+    /// assert!(sieve.by_ref().take(4).eq([2, 3, 5, 7]));
+    ///
+    /// assert_eq!(sieve.nth(995), Some(7919));
+    /// assert_eq!(sieve.memory_usage(), memory_usage);
+    /// ```
+    pub fn reset(&mut self) {
+        self.rewind(I::from_small(2)..);
+    }
+}
+
+impl<I, S> Sieve<I, ops::RangeInclusive<I>, S>
+where
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+    I: SieveInt,
+    S: hash::BuildHasher,
+{
+    /// Get the first prime which is at least `n`, or `None` if there is no such
+    /// prime within the bound of the sieve.
+    ///
+    /// This behaves like [`Sieve::next_prime_after`] for infinite sieves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::bounded::<u32>(1000);
+    ///
+    /// assert_eq!(sieve.next_prime_after(900), Some(907));
+    /// assert_eq!(sieve.next(), Some(911));
+    /// assert_eq!(sieve.next_prime_after(2), Some(2));
+    /// assert_eq!(sieve.next_prime_after(998), None);
+    /// assert_eq!(sieve.next(), None);
+    /// ```
+    pub fn next_prime_after(&mut self, n: I) -> Option<I> {
+        let end = *self.iter.end();
+
+        let pos = match self.pending {
+            Some(pos) => pos,
+            None if self.iter.is_empty() => {
+                // Everything up to the bound has been passed.
+                return match trial_search(n, end) {
+                    Some(p) => Some(p),
+                    None if n <= end && trial_division(end) => Some(end),
+                    None => None,
+                };
+            }
+            None => *self.iter.start(),
+        };
+
+        if n < pos {
+            if let Some(p) = trial_search(n, pos) {
+                return Some(p);
+            }
+        }
+
+        // The bound itself is left to be fed through `next` below.
+        let to = n.min(end);
+        self.advance(pos, to, |iter| *iter = to..=end);
+        self.find(|&p| p >= n)
+    }
+
+    /// Advance the sieve without yielding anything, so that the next call to
+    /// [`next`][Iterator::next] returns the first prime which is at least `n`.
+    ///
+    /// This behaves like [`Sieve::skip_to`] for infinite sieves. Skipping past
+    /// the bound exhausts the sieve.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::bounded::<u32>(1000);
+    ///
+    /// sieve.skip_to(900);
+    /// assert_eq!(sieve.next(), Some(907));
+    /// assert_eq!(sieve.len(), 13);
+    ///
+    /// sieve.skip_to(1001);
+    /// assert_eq!(sieve.next(), None);
+    /// ```
+    pub fn skip_to(&mut self, n: I) {
+        let pos = match self.pending {
+            Some(pos) => pos,
+            None if self.iter.is_empty() => return,
+            None => *self.iter.start(),
+        };
+
+        let end = *self.iter.end();
+
+        if n <= end {
+            self.advance(pos, n, |iter| *iter = n..=end);
+        } else {
+            // The bound itself has to be fed through to be passed.
+            self.advance(pos, end, |iter| *iter = end..=end);
+            self.next();
+        }
+    }
+
+    /// Rewind the sieve so that it starts over from 2, with the same bound.
+    ///
+    /// This behaves like [`Sieve::reset`] for infinite sieves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::bounded::<u32>(100);
+    /// assert_eq!(sieve.by_ref().count(), 25);
+    ///
+    /// sieve.reset();
+    /// assert_eq!(sieve.len(), 25);
+    /// assert_eq!(sieve.next_back(), Some(97));
+    /// ```
+    pub fn reset(&mut self) {
+        // Iterating from the back lowers the end of the iterator, but not the
+        // limit.
+        let end = self.limit.unwrap_or(*self.iter.end());
+        self.rewind(I::from_small(2)..=end);
+    }
+
+    /// Rewind the sieve so that it starts over from 2, with a new bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::bounded::<u32>(100);
+    /// assert_eq!(sieve.by_ref().count(), 25);
+    ///
+    /// sieve.reset_with_bound(1000);
+    /// assert_eq!(sieve.len(), 168);
+    /// assert_eq!(sieve.next_back(), Some(997));
+    ///
+    /// sieve.reset_with_bound(1);
+    /// assert_eq!(sieve.next(), None);
+    /// ```
+    pub fn reset_with_bound(&mut self, upper: I) {
+        self.rewind(I::from_small(2)..=upper);
+        self.limit = Some(upper);
+        self.total.store(usize::MAX, Ordering::Relaxed);
+    }
+
+    /// Raise the bound of the sieve to `upper`, so that iterating it continues
+    /// past the old bound, even if it was already exhausted.
+    ///
+    /// Unlike [`Sieve::reset_with_bound`] this keeps the position of the
+    /// sieve, so nothing below it is sieved again. Composites past the old
+    /// bound were never tracked, so the primes the sieve has passed which are
+    /// needed for the new bound, those up to `sqrt(upper)`, are tracked again
+    /// from the current position.
+    ///
+    /// A bound which isn't larger than [`Sieve::bound`] is ignored. To lower
+    /// the bound, use [`Sieve::reset_with_bound`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `upper` doesn't fit in a `u64`, or if the sieve has been
+    /// iterated from the back, since the primes produced from there would
+    /// leave a hole in what's left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::bounded::<u32>(100);
+    /// assert_eq!(sieve.by_ref().count(), 25);
+    ///
+    /// sieve.extend_bound(200);
+    /// assert_eq!(sieve.bound(), 200);
+    /// assert_eq!(sieve.len(), 21);
+    /// assert!(sieve.eq(sieve::range(101u32, 200)));
+    /// ```
+    pub fn extend_bound(&mut self, upper: I) {
+        let old = self.bound();
+
+        if upper <= old {
+            return;
+        }
+
+        if self.back.is_some() {
+            panic!("can't extend the bound of a sieve which has been iterated from the back");
+        }
+
+        let root = match upper.to_u64() {
+            Some(upper) => flat::isqrt(upper),
+            None => panic!("bound doesn't fit in a u64"),
+        };
+
+        let exhausted =
+            self.pending.is_none() && (self.iter.is_empty() || matches!(self.tail, Tail::Done));
+
+        let pos = if exhausted {
+            // Every candidate up to the old bound has been fed through, so the
+            // wheel is picked up again from just past it.
+            let pos = (old + I::from_small(1)).max(I::from_small(2));
+            self.iter = pos..=upper;
+            self.wheel = None;
+            self.tail = match pos.checked_add(I::from_small(7)) {
+                Some(..) => Tail::Far,
+                None => Tail::Near(pos),
+            };
+            pos
+        } else {
+            let start = *self.iter.start();
+            self.iter = start..=upper;
+            self.pending.unwrap_or(start)
+        };
+
+        self.limit = Some(upper);
+        self.total.store(usize::MAX, Ordering::Relaxed);
+        self.composite.clear();
+
+        for p in small::odd_primes(root) {
+            let p = match I::from_u64(p) {
+                Some(p) if p < pos => p,
+                _ => break,
+            };
+
+            // Only primes from 7 and up are tracked.
+            if p >= I::from_small(7) {
+                self.track_from(pos, p);
+            }
+        }
+    }
+
+    /// Get the bound of the sieve, which is the largest number it can produce.
+    ///
+    /// This is the bound it was constructed with, and it doesn't change as
+    /// the sieve is iterated from either end, or once it's exhausted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::bounded::<u32>(100);
+    /// assert_eq!(sieve.bound(), 100);
+    ///
+    /// assert_eq!(sieve.next_back(), Some(97));
+    /// assert_eq!(sieve.by_ref().count(), 24);
+    /// assert_eq!(sieve.bound(), 100);
+    ///
+    /// sieve.reset_with_bound(1000);
+    /// assert_eq!(sieve.bound(), 1000);
+    /// ```
+    pub fn bound(&self) -> I {
+        self.limit.unwrap_or(*self.iter.end())
+    }
+
+    /// Get the range of numbers the sieve has yet to consider, as the next
+    /// number it will consider and the largest one, or `None` if the sieve is
+    /// exhausted.
+    ///
+    /// The next number is only a candidate, and isn't necessarily a prime.
+    /// Iterating from the back lowers the largest number below the last prime
+    /// produced from there, so unlike [`Sieve::bound`] it tracks both ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::bounded::<u32>(100);
+    /// assert_eq!(sieve.remaining_range(), Some((2, 100)));
+    ///
+    /// assert_eq!(sieve.nth(9), Some(29));
+    /// assert_eq!(sieve.remaining_range(), Some((30, 100)));
+    ///
+    /// assert_eq!(sieve.next_back(), Some(97));
+    /// assert_eq!(sieve.remaining_range(), Some((30, 96)));
+    ///
+    /// sieve.by_ref().count();
+    /// assert_eq!(sieve.remaining_range(), None);
+    /// ```
+    pub fn remaining_range(&self) -> Option<(I, I)> {
+        let pos = match self.pending {
+            Some(pos) => pos,
+            // Near the end of the type the iterator isn't always drained.
+            None if self.iter.is_empty() || matches!(self.tail, Tail::Done) => return None,
+            None => *self.iter.start(),
+        };
+
+        Some((pos, *self.iter.end()))
+    }
+}
+
+/// Find the first prime in `lo..hi` through trial division.
+fn trial_search<I>(lo: I, hi: I) -> Option<I>
+where
+    I: Copy + FromSmall + Ord + ops::Add<Output = I> + ops::Div<Output = I> + ops::Rem<Output = I>,
+{
+    let mut n = lo;
+
+    while n < hi {
+        if trial_division(n) {
+            return Some(n);
+        }
+
+        n = n + I::from_small(1);
+    }
+
+    None
+}
+
+/// Test if `n` is prime by trial division.
+fn trial_division<I>(n: I) -> bool
+where
+    I: Copy + FromSmall + Ord + ops::Add<Output = I> + ops::Div<Output = I> + ops::Rem<Output = I>,
+{
+    let zero = I::from_small(0);
+    let two = I::from_small(2);
+
+    if n < two {
+        return false;
+    }
+
+    if n % two == zero {
+        return n == two;
+    }
+
+    let mut d = I::from_small(3);
+
+    while d <= n / d {
+        if n % d == zero {
+            return false;
+        }
+
+        d = d + two;
+    }
+
+    true
+}
+
+/// Round a non-negative estimate down to a `usize`, saturating.
+fn floor_usize(n: f64) -> usize {
+    if n <= 0.0 {
+        0
+    } else {
+        n as usize
+    }
+}
+
+/// Round an estimate up to a `usize`, saturating.
+fn ceil_usize(n: f64) -> usize {
+    let floor = floor_usize(n);
+
+    if (floor as f64) < n {
+        floor.saturating_add(1)
+    } else {
+        floor
+    }
+}
+
+impl<I, U, S> Iterator for Sieve<I, U, S>
+where
+    U: Iterator<Item = I>,
+    I: SieveInt,
+    S: hash::BuildHasher,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(n) = self.candidate() {
+            if self.step(n) {
+                return Some(n);
+            }
+        }
+
+        None
+    }
+
+    /// Count the remaining primes.
+    ///
+    /// For a bounded sieve whose bound fits in a `u64`, this counts the primes
+    /// up to the bound with [`prime_pi()`] instead of producing every prime,
+    /// and subtracts the primes which have already been produced.
+    fn count(self) -> usize {
+        match self.remaining(u64::MAX) {
+            Some(remaining) => remaining,
+            None => self.fold(0, |count, _| count + 1),
+        }
+    }
+
+    /// Get the `n`th next prime, counting from zero.
+    ///
+    /// Large skips are done in bulk through a segmented sieve, where only
+    /// the composites being tracked need to be moved past the skipped primes,
+    /// instead of feeding every candidate through the sieve.
+    fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
+        if n >= NTH_BULK && self.back.is_none() {
+            n = self.skip_primes(n);
+        }
+
+        while let Some(c) = self.candidate() {
+            if self.step(c) {
+                if n == 0 {
+                    return Some(c);
+                }
+
+                n -= 1;
+            }
+        }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if let Some(remaining) = self.remaining(EXACT_SIZE_HINT) {
+            return (remaining, Some(remaining));
+        }
+
+        let (_, upper) = self.iter.size_hint();
+        let pending = usize::from(self.pending.is_some());
+
+        // No window of `n` candidates contains more than `2 n / ln n` primes
+        // (Montgomery and Vaughan).
+        let window = upper.and_then(|upper| upper.checked_add(pending)).map(|n| {
+            if n > 1 {
+                let x = n as f64;
+                ceil_usize(2.0 * x / math::ln(x)).min(n)
+            } else {
+                n
+            }
+        });
+
+        let limit = match self.limit {
+            Some(limit) => limit,
+            // An unbounded sieve still runs out of primes at the end of its
+            // type.
+            None if window.is_none() => int::max_value::<I>(),
+            None => return (0, window),
+        };
+
+        let (lo, hi) = counting::prime_count_bounds(limit.to_f64());
+        let upper = (hi < usize::MAX as f64).then(|| ceil_usize(hi).saturating_sub(self.count));
+
+        let upper = match (upper, window) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        let lower = floor_usize(lo)
+            .saturating_sub(self.count)
+            .saturating_sub(self.popped());
+        (upper.map_or(lower, |upper| lower.min(upper)), upper)
+    }
+}
+
+/// Once a sieve has run out of candidates it keeps returning `None`, without
+/// touching the composites it's tracking.
+///
+/// # Examples
+///
+/// ```
+/// let mut sieve = sieve::bounded::<u32>(10);
+///
+/// assert!(sieve.by_ref().eq([2, 3, 5, 7]));
+/// assert_eq!(sieve.next(), None);
+/// assert_eq!(sieve.next(), None);
+/// ```
+impl<I, U, S> iter::FusedIterator for Sieve<I, U, S>
+where
+    U: iter::FusedIterator<Item = I>,
+    I: SieveInt,
+    S: hash::BuildHasher,
+{
+}
+
+/// Bounded sieves can be iterated from the back, which sieves segments from the
+/// bound downwards independently of the front. The two ends never yield the
+/// same prime.
+///
+/// # Panics
+///
+/// Iterating from the back panics if the bound doesn't fit in a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// let mut sieve = sieve::bounded::<u32>(100);
+///
+/// assert!(sieve.by_ref().rev().take(3).eq([97, 89, 83]));
+/// assert_eq!(sieve.next(), Some(2));
+/// assert_eq!(sieve.nth_back(1), Some(73));
+/// assert_eq!(sieve.last(), Some(71));
+/// ```
+impl<I, S> DoubleEndedIterator for Sieve<I, ops::RangeInclusive<I>, S>
+where
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+    I: SieveInt,
+    S: hash::BuildHasher,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // The next number the front would feed through the sieve.
+        let floor = match self.pending {
+            Some(n) => n,
+            None if self.iter.is_empty() => return None,
+            None => *self.iter.start(),
+        };
+
+        let floor = floor.to_u64()?;
+
+        let back = match &mut self.back {
+            Some(back) => back,
+            None => {
+                let end = *self.iter.end();
+
+                // The bound is about to be lowered, so it's remembered in
+                // case the sieve didn't know it already.
+                self.limit.get_or_insert(end);
+
+                match end.to_u64() {
+                    Some(upper) => self.back.insert(back::Back::new(upper)),
+                    None => panic!("bound doesn't fit in a u64"),
+                }
+            }
+        };
+
+        let start = *self.iter.start();
+
+        let p = match back.pop(floor) {
+            Some(p) => p,
+            None => {
+                // Both ends have met, so there's nothing left for the front.
+                let end = *self.iter.end();
+                self.pending = None;
+                self.iter = end..=end;
+                self.iter.next();
+                return None;
+            }
+        };
+
+        // Keep the front from reaching the primes yielded from the back.
+        let prime = I::from_u64(p)?;
+        self.iter = start..=I::from_u64(p - 1)?;
+
+        if matches!(self.pending, Some(n) if n >= prime) {
+            self.pending = None;
+        }
+
+        Some(prime)
+    }
+}
+
+/// The number of primes in a bounded sieve is computed the first time it's
+/// needed with [`prime_pi()`], which takes `O(n^(3/4))` time and `O(√n)`
+/// memory rather than sieving every prime up to the bound. After that it's
+/// kept up to date as the sieve is iterated.
+///
+/// [`Iterator::size_hint`] only counts the primes for bounds up to 2^32, and
+/// estimates them above that, so [`len()`][ExactSizeIterator::len] has to be
+/// called to get the exact length of a sieve with a larger bound.
+///
+/// # Examples
+///
+/// ```
+/// let mut sieve = sieve::bounded::<u32>(10_000);
+///
+/// assert_eq!(sieve.len(), 1229);
+/// assert_eq!(sieve.by_ref().take(100).count(), 100);
+/// assert_eq!(sieve.len(), 1129);
+/// assert_eq!(sieve.size_hint(), (1129, Some(1129)));
+/// ```
+impl<I, S> ExactSizeIterator for Sieve<I, ops::RangeInclusive<I>, S>
+where
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+    I: SieveInt,
+    S: hash::BuildHasher,
+{
+    /// Get the exact number of primes left in the sieve.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bound doesn't fit in a `u64`, or if the number of primes
+    /// up to it doesn't fit in a `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// let sieve = sieve::bounded::<u128>(1 << 64);
+    /// sieve.len();
+    /// ```
+    fn len(&self) -> usize {
+        match self.remaining(u64::MAX) {
+            Some(remaining) => remaining,
+            None => panic!("the primes up to the bound of the sieve can't be counted"),
+        }
+    }
+}
+
+/// A deterministic stream of pseudo-random numbers from xorshift, for tests
+/// which sample large ranges.
+#[cfg(test)]
+fn xorshift(mut state: u64) -> impl Iterator<Item = u64> {
+    iter::from_fn(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        Some(state)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::format;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use std::collections::HashMap;
+
+    #[test]
+    fn it_works() {
+        let mut sieve = infinite::<u32>();
+        let primes = sieve.by_ref().take(100).collect::<Vec<u32>>();
+
+        assert_eq!(
+            primes,
+            vec![
+                2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79,
+                83, 89, 97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167,
+                173, 179, 181, 191, 193, 197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257,
+                263, 269, 271, 277, 281, 283, 293, 307, 311, 313, 317, 331, 337, 347, 349, 353,
+                359, 367, 373, 379, 383, 389, 397, 401, 409, 419, 421, 431, 433, 439, 443, 449,
+                457, 461, 463, 467, 479, 487, 491, 499, 503, 509, 521, 523, 541,
+            ]
+        );
+    }
+
+    #[test]
+    fn is_prime() {
+        let expected = bounded::<u32>(1000).collect::<Vec<_>>();
+
+        let mut sieve = infinite::<u32>();
+
+        for n in 0..=1000 {
+            assert_eq!(sieve.is_prime(n), expected.contains(&n), "{}", n);
+        }
+
+        for n in (0..=1000).rev() {
+            assert_eq!(sieve.is_prime(n), expected.contains(&n), "{}", n);
+        }
+
+        assert_eq!(sieve.next(), Some(1009));
+    }
+
+    #[test]
+    fn past_u16() {
+        assert_eq!(infinite::<u32>().take_while(|&p| p < 100_000).count(), 9592);
+        assert_eq!(bounded::<u32>(u16::MAX as u32).count(), 6542);
+        assert_eq!(bounded::<u32>(u16::MAX as u32 + 1).count(), 6542);
+    }
+
+    #[test]
+    fn signed() {
+        let expected = infinite::<u32>().take(10_000).collect::<Vec<_>>();
+
+        let primes = infinite::<i32>().take(10_000);
+        assert!(primes.map(|p| p as u32).eq(expected.iter().copied()));
+        let primes = infinite::<i64>().take(10_000);
+        assert!(primes.map(|p| p as u32).eq(expected.iter().copied()));
+
+        assert!(bounded(1_000_000i32)
+            .map(|p| p as u32)
+            .eq(bounded(1_000_000u32)));
+        assert!(bounded(1_000_000i64)
+            .map(|p| p as u64)
+            .eq(bounded(1_000_000u64)));
+        assert_eq!(bounded::<i32>(u16::MAX as i32 + 1).count(), 6542);
+
+        let mut sieve = infinite::<i32>();
+
+        for n in -10..=1000 {
+            let expected = n >= 0 && expected.contains(&(n as u32));
+            assert_eq!(sieve.is_prime(n), expected, "{}", n);
+        }
+
+        assert_eq!(bounded(-1i32).next(), None);
+        assert_eq!(bounded(i64::MIN).next(), None);
+        assert_eq!(bounded(1i32).next(), None);
+        assert!(bounded(2i32).eq([2]));
+    }
+
+    #[test]
+    fn bounded_exclusive() {
+        for upper in 0..=1000u32 {
+            let expected = bounded(upper).filter(|&p| p < upper);
+            assert!(super::bounded_exclusive(upper).eq(expected), "{}", upper);
+
+            let sieve = super::bounded_exclusive(upper);
+            let count = sieve.clone().count();
+            assert_eq!(sieve.size_hint(), (count, Some(count)), "{}", upper);
+        }
+
+        assert_eq!(super::bounded_exclusive(0u32).next(), None);
+        assert_eq!(super::bounded_exclusive(2u32).next(), None);
+        assert!(super::bounded_exclusive(3u32).eq([2]));
+        assert_eq!(super::bounded_exclusive(i64::MIN).next(), None);
+        assert_eq!(super::bounded_exclusive(-5i32).next(), None);
+        assert_eq!(super::bounded_exclusive(1_000_003u64).count(), 78_498);
+    }
+
+    #[test]
+    fn nth() {
+        /// Skip `n` primes one at a time, like the default `nth`.
+        fn slow<T: Iterator>(iter: &mut T, n: usize) -> Option<T::Item> {
+            for _ in 0..n {
+                iter.next()?;
+            }
+
+            iter.next()
+        }
+
+        for &skips in &[
+            &[0, 0, 1, 5][..],
+            &[NTH_BULK - 1, NTH_BULK, NTH_BULK + 1],
+            &[3, NTH_BULK, 0, 10_000, 1],
+            &[100_000, 200_000],
+        ] {
+            let mut expected = infinite::<u64>();
+            let mut actual = infinite::<u64>();
+
+            for &n in skips {
+                assert_eq!(actual.nth(n), slow(&mut expected, n), "{:?}", skips);
+            }
+
+            assert!(actual.by_ref().take(1000).eq(expected.by_ref().take(1000)));
+            assert_eq!(actual.count, expected.count);
+
+            let mut expected = infinite::<i32>();
+            let mut actual = infinite::<i32>();
+
+            for &n in skips {
+                assert_eq!(actual.nth(n), slow(&mut expected, n), "{:?}", skips);
+            }
+
+            assert!(actual.take(1000).eq(expected.take(1000)));
+        }
+
+        for &upper in &[0u32, 2, 100, 40_000, 40_009, 300_000] {
+            for &n in &[0, 1, NTH_BULK, 4202, 4203, 4204, 25_996, 25_997, 30_000] {
+                let mut expected = bounded(upper);
+                let mut actual = bounded(upper);
+                assert_eq!(actual.nth(n), slow(&mut expected, n), "{} {}", upper, n);
+                assert_eq!(actual.size_hint(), expected.size_hint(), "{} {}", upper, n);
+                assert!(actual.eq(expected), "{} {}", upper, n);
+
+                let mut expected = super::bounded_exclusive(upper);
+                let mut actual = super::bounded_exclusive(upper);
+                assert_eq!(actual.nth(n), slow(&mut expected, n), "{} {}", upper, n);
+                assert!(actual.eq(expected), "{} {}", upper, n);
+            }
+        }
+
+        for &n in &[1 << 40, usize::MAX] {
+            let mut sieve = bounded::<u32>(100);
+            assert_eq!(sieve.nth(n), None, "{}", n);
+            assert_eq!(sieve.next(), None, "{}", n);
+        }
+
+        let mut sieve = infinite::<u32>();
+        assert_eq!(sieve.nth(999_999), Some(15_485_863));
+        assert_eq!(sieve.next(), Some(15_485_867));
+
+        let mut sieve = bounded::<u128>(1_000_000);
+        assert_eq!(sieve.nth(10_000), Some(104_743));
+        assert_eq!(sieve.nth(100_000), None);
+        assert_eq!(sieve.next(), None);
+    }
+
+    #[test]
+    fn fast_count() {
+        assert_eq!(bounded(1_000_000u32).count(), 78_498);
+        assert_eq!(bounded(10_000_000u64).count(), 664_579);
+        assert_eq!(bounded(10_000_000u128).count(), 664_579);
+        assert_eq!(super::bounded_exclusive(8_999_999u32).count(), 602_489);
+
+        for &taken in &[0, 1, 5, 1000, 9591, 9592] {
+            let mut sieve = bounded(100_000u32);
+            assert_eq!(sieve.by_ref().take(taken).count(), taken);
+            assert_eq!(sieve.count(), 9592 - taken, "{}", taken);
+        }
+
+        let mut sieve = bounded(100_000u32);
+        sieve.by_ref().take(10).for_each(drop);
+        sieve.by_ref().rev().take(10).for_each(drop);
+        let expected = sieve.clone().fold(0, |count, _| count + 1);
+        assert_eq!(sieve.count(), expected);
+        assert_eq!(expected, 9572);
+    }
+
+    /// Construct a sieve over `lo..=hi` in the same state as if it had been
+    /// driven there from 2.
+    fn seeded(lo: u32, hi: u32) -> Sieve<u32, ops::RangeInclusive<u32>> {
+        let mut sieve = bounded(hi);
+
+        for p in bounded::<u64>(flat::isqrt(u64::from(hi))).skip(3) {
+            let mut key = (p * p).max((u64::from(lo) + p - 1) / p * p);
+
+            if key % 2 == 0 {
+                key += p;
+            }
+
+            while key <= u64::from(hi) && (key % 3 == 0 || key % 5 == 0) {
+                key += 2 * p;
+            }
+
+            if key <= u64::from(hi) {
+                sieve.composite.insert(key as u32, p as u32);
+            }
+        }
+
+        sieve.iter = lo..=hi;
+        sieve
+    }
+
+    #[test]
+    fn up_to_u32_max() {
+        let lo = u32::MAX - 10_000;
+        let expected = (lo..=u32::MAX).filter(|&n| trial_division(u64::from(n)));
+        assert!(seeded(lo, u32::MAX).eq(expected));
+        assert_eq!(seeded(lo, u32::MAX).next_back(), Some(4_294_967_291));
+    }
+
+    #[test]
+    fn shared_composites() {
+        // Every prime from 7 to 23 crosses off this number first.
+        let lo = 7 * 11 * 13 * 17 * 19 * 23;
+        let hi = lo + 100_000;
+
+        let sieve = seeded(lo, hi);
+        let shared = sieve.composites().filter(|&(key, _)| key == lo).count();
+        assert_eq!(shared, 6);
+
+        let size = sieve.size();
+        let expected = (lo..=hi).filter(|&n| trial_division(u64::from(n)));
+        let mut sieve = sieve;
+        assert!(sieve.by_ref().take(10).eq(expected.take(10)));
+
+        // Each of them moved on to a later composite, rather than being lost.
+        assert_eq!(sieve.size(), size);
+        assert!(sieve.composites().all(|(key, _)| key > lo));
+        assert!(sieve.eq((lo..=hi).filter(|&n| trial_division(u64::from(n))).skip(10)));
+    }
+
+    #[test]
+    fn u128_support() {
+        let expected = infinite::<u64>().take(1000).map(u128::from);
+        assert!(infinite::<u128>().take(1000).eq(expected));
+
+        for n in 0..=200u32 {
+            let expected = bounded::<u64>(n.into()).map(u128::from);
+            assert!(bounded::<u128>(n.into()).eq(expected), "{}", n);
+        }
+
+        // Composites past the range of a `u64` are tracked as usual.
+        let mut sieve = infinite::<u128>();
+        let p = 4_294_967_311u128;
+        sieve.composite.insert(p * p, p);
+        assert!(!sieve.step(p * p));
+        let key = sieve.composites().find(|&(_, v)| v == p).map(|(k, _)| k);
+        assert_eq!(key.map(|k| (k - p * p) % (2 * p)), Some(0));
+        assert!(key > Some(p * p));
+
+        let mut sieve = infinite::<u128>();
+        assert_eq!(sieve.next_prime_after(1_000_000), Some(1_000_003));
+        assert!(sieve.is_prime(1_000_033));
+        assert_eq!(sieve.next(), Some(1_000_037));
+    }
+
+    #[test]
+    fn small_types() {
+        assert_eq!(bounded::<u8>(255).count(), 54);
+        assert_eq!(bounded::<u8>(255).next_back(), Some(251));
+        assert_eq!(bounded::<u16>(u16::MAX).count(), 6542);
+        assert_eq!(bounded::<u16>(u16::MAX).next_back(), Some(65521));
+        assert_eq!(bounded::<u16>(u16::MAX).len(), 6542);
+
+        let expected = bounded::<u32>(255).map(|p| p as u8).collect::<Vec<_>>();
+        assert_eq!(bounded::<u8>(255).collect::<Vec<_>>(), expected);
+        assert!(bounded::<u8>(255).rev().eq(expected.into_iter().rev()));
+    }
+
+    #[test]
+    fn end_of_type() {
+        // Without care, the underlying iterator overflows when it produces the
+        // last number of the type, which panics with overflow checks and wraps
+        // around forever without them.
+        assert!(infinite::<u8>().eq(bounded::<u8>(u8::MAX)));
+        assert!(infinite::<u16>().eq(bounded::<u16>(u16::MAX)));
+        assert!(infinite::<i16>().eq(bounded::<i16>(i16::MAX)));
+
+        // The last number of the type is a prime.
+        assert!(infinite::<i8>().eq(bounded::<i8>(i8::MAX)));
+        assert_eq!(infinite::<i8>().last(), Some(127));
+
+        let mut sieve = infinite::<u8>();
+        assert_eq!(sieve.nth(53), Some(251));
+        assert_eq!(sieve.next(), None);
+        assert_eq!(sieve.next(), None);
+
+        for n in u16::MAX - 20..=u16::MAX {
+            let mut sieve = infinite::<u16>();
+            sieve.skip_to(n);
+            assert!(
+                sieve.eq(bounded::<u16>(u16::MAX).filter(|&p| p >= n)),
+                "{}",
+                n
+            );
+        }
+
+        let mut sieve = infinite::<i32>();
+        sieve.skip_to(i32::MAX - 10);
+        assert_eq!(sieve.last(), Some(i32::MAX));
+    }
+
+    #[test]
+    fn wheel_matches_trial_division() {
+        let expected = (0u64..).filter(|&n| trial_division(n));
+        assert!(infinite::<u64>().take(10_000).eq(expected.take(10_000)));
+    }
+
+    /// The incremental sieve without any wheel, which feeds every number
+    /// through the composite map and tracks even multiples too.
+    fn naive(limit: usize) -> Vec<u64> {
+        let mut composite = HashMap::<u64, u64, _>::with_hasher(DefaultHashBuilder::default());
+        let mut primes = Vec::with_capacity(limit);
+        let mut n = 2;
+
+        while primes.len() < limit {
+            match composite.remove(&n) {
+                Some(p) => {
+                    let mut key = n + p;
+
+                    while composite.contains_key(&key) {
+                        key += p;
+                    }
+
+                    composite.insert(key, p);
+                }
+                None => {
+                    composite.insert(n * n, n);
+                    primes.push(n);
+                }
+            }
+
+            n += 1;
+        }
+
+        primes
+    }
+
+    #[test]
+    fn wheel_matches_naive() {
+        let expected = naive(100_000);
+        assert!(infinite::<u64>().take(100_000).eq(expected.iter().copied()));
+
+        let upper = *expected.last().unwrap();
+        assert!(bounded::<u64>(upper).eq(expected.iter().copied()));
+    }
+
+    #[test]
+    fn wheel_skips_small_primes() {
+        let mut sieve = infinite::<u32>();
+        assert_eq!(sieve.by_ref().take(1000).last(), Some(7919));
+        // Only primes from 7 and up are tracked.
+        assert_eq!(sieve.size(), 997);
+    }
+
+    /// A trivial hasher which uses the integer itself as its hash.
+    #[derive(Default)]
+    struct Identity(u64);
+
+    impl hash::Hasher for Identity {
+        fn write(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.0 = self.0 << 8 | u64::from(b);
+            }
+        }
+
+        fn write_u32(&mut self, n: u32) {
+            self.0 = u64::from(n);
+        }
+
+        fn finish(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn custom_hasher() {
+        let hasher = hash::BuildHasherDefault::<Identity>::default();
+        let sieve = infinite_with_hasher::<u32, _>(hasher);
+        assert!(sieve.take(1000).eq(infinite::<u32>().take(1000)));
+
+        let hasher = hash::BuildHasherDefault::<Identity>::default();
+        let sieve = bounded_with_hasher::<u32, _>(10_000, hasher);
+        assert!(sieve.eq(bounded::<u32>(10_000)));
+    }
+
+    #[test]
+    fn next_prime_after() {
+        let expected = bounded::<u32>(200_000).collect::<Vec<_>>();
+        let first_at_least = |n: u32| expected.iter().copied().find(|&p| p >= n);
+
+        let mut sieve = infinite::<u32>();
+        let mut n = 0;
+
+        while n < 190_000 {
+            let p = sieve.next_prime_after(n);
+            assert_eq!(p, first_at_least(n), "{}", n);
+            let p = p.unwrap();
+            assert_eq!(sieve.next(), first_at_least(p + 1), "{}", n);
+            n = p + 1 + n % 7919;
+        }
+
+        // Going backwards finds primes through trial division, without
+        // affecting the sieve.
+        let pos = sieve.next().unwrap();
+        assert_eq!(sieve.next_prime_after(1000), Some(1009));
+        assert_eq!(sieve.next_prime_after(pos - 1), Some(pos));
+        assert_eq!(sieve.next(), first_at_least(pos + 1));
+
+        for n in 0..=2 {
+            assert_eq!(infinite::<u32>().next_prime_after(n), Some(2));
+        }
+
+        // The state after a jump is the same as if it had been driven there.
+        let mut sieve = infinite::<u64>();
+        assert_eq!(sieve.next_prime_after(1_000_000), Some(1_000_003));
+        assert!(sieve
+            .take(5000)
+            .eq(range(1_000_004u64, 2_000_000).take(5000)));
+    }
+
+    #[test]
+    fn next_prime_after_bounded() {
+        let mut sieve = bounded::<u32>(1000);
+        assert_eq!(sieve.next_prime_after(0), Some(2));
+        assert_eq!(sieve.next_prime_after(100), Some(101));
+        assert_eq!(sieve.next_prime_after(996), Some(997));
+        assert_eq!(sieve.next(), None);
+        assert_eq!(sieve.next_prime_after(10), Some(11));
+        assert_eq!(sieve.next_prime_after(998), None);
+
+        // The bound itself is included.
+        assert_eq!(bounded::<u32>(997).next_prime_after(997), Some(997));
+        assert_eq!(bounded::<u32>(997).next_prime_after(998), None);
+
+        let mut sieve = bounded::<u32>(1000);
+        assert_eq!(sieve.next_prime_after(5000), None);
+        assert_eq!(sieve.next(), None);
+    }
+
+    #[test]
+    fn factorize_with() {
+        let mut sieve = infinite::<u64>();
+
+        for n in 0..10_000 {
+            assert_eq!(sieve.factorize_with(n), factorize(n), "{}", n);
+        }
+
+        // Primes which were passed before factorizing are recovered.
+        let mut sieve = infinite::<u64>();
+        assert_eq!(sieve.next_prime_after(1000), Some(1009));
+        assert_eq!(sieve.factorize_with(1009 * 1013), [(1009, 1), (1013, 1)]);
+        assert_eq!(sieve.factorize_with(1_000_000_000_000), [(2, 12), (5, 12)]);
+
+        let parts = sieve.into_parts();
+        let mut sieve = Sieve::from_parts(parts);
+        assert_eq!(
+            sieve.factorize_with(2 * 1_000_003),
+            [(2, 1), (1_000_003, 1)]
+        );
+
+        // An exhausted sieve falls back to plain trial division.
+        let mut sieve = bounded::<u64>(10);
+        assert_eq!(sieve.factorize_with(143 * 143), [(11, 2), (13, 2)]);
+        let mut sieve = bounded::<u64>(1);
+        assert_eq!(sieve.factorize_with(12), [(2, 2), (3, 1)]);
+    }
+
+    #[test]
+    fn double_ended() {
+        let expected = bounded::<u32>(100_000).collect::<Vec<_>>();
+
+        let mut reversed = bounded::<u32>(100_000).rev().collect::<Vec<_>>();
+        reversed.reverse();
+        assert_eq!(reversed, expected);
+
+        for n in 0..200u32 {
+            let expected = bounded::<u32>(n).collect::<Vec<_>>();
+            let mut reversed = bounded::<u32>(n).rev().collect::<Vec<_>>();
+            reversed.reverse();
+            assert_eq!(reversed, expected, "{}", n);
+        }
+
+        // Alternate between the ends in different patterns, which must meet
+        // without skipping or repeating anything.
+        for &upper in &[0u32, 2, 3, 10, 97, 100, 1000, 10_007] {
+            let expected = bounded::<u32>(upper).collect::<Vec<_>>();
+
+            for pattern in 1..8u32 {
+                let mut sieve = bounded::<u32>(upper);
+                let mut front = Vec::new();
+                let mut back = Vec::new();
+
+                for i in 0.. {
+                    let p = if (i / pattern) % 2 == 0 {
+                        sieve.next().map(|p| front.push(p))
+                    } else {
+                        sieve.next_back().map(|p| back.push(p))
+                    };
+
+                    if p.is_none() && sieve.next().is_none() && sieve.next_back().is_none() {
+                        break;
+                    }
+                }
+
+                back.reverse();
+                front.extend(back);
+                assert_eq!(front, expected, "{} {}", upper, pattern);
+            }
+        }
+
+        // Querying the front doesn't let it pass the back.
+        let mut sieve = bounded::<u32>(1000);
+        assert_eq!(sieve.next_back(), Some(997));
+        assert!(sieve.is_prime(991));
+        assert_eq!(sieve.next_prime_after(992), None);
+        assert_eq!(sieve.next_back(), None);
+        assert_eq!(sieve.next(), None);
+    }
+
+    #[test]
+    fn exact_size() {
+        let mut sieve = bounded::<u32>(10_000);
+        assert_eq!(sieve.len(), 1229);
+
+        for expected in (0..1229).rev() {
+            assert!(sieve.next().is_some());
+            assert_eq!(sieve.len(), expected);
+        }
+
+        assert_eq!(sieve.next(), None);
+        assert_eq!(sieve.len(), 0);
+
+        // The length is kept up to date from both ends, and when primes are
+        // passed without being yielded.
+        let mut sieve = bounded::<u32>(10_000);
+        sieve.next();
+        assert_eq!(sieve.len(), 1228);
+        sieve.next_back();
+        assert_eq!(sieve.len(), 1227);
+        assert!(!sieve.is_prime(100));
+        assert_eq!(sieve.len(), 1229 - 25 - 1);
+        assert_eq!(sieve.next_prime_after(5000), Some(5003));
+        assert_eq!(sieve.len(), 1229 - 670 - 1);
+        assert_eq!(sieve.len(), sieve.by_ref().count());
+
+        for n in 0..100u32 {
+            assert_eq!(bounded::<u32>(n).len(), bounded::<u32>(n).count(), "{}", n);
+        }
+
+        // Parts which claim to have passed more primes than there are don't
+        // underflow.
+        let mut parts = bounded::<u32>(100).into_parts();
+        parts.count = 1000;
+        let sieve = Sieve::from_parts(parts);
+        assert_eq!(sieve.size_hint(), (0, Some(0)));
+        assert_eq!(sieve.len(), 0);
+    }
+
+    #[test]
+    #[should_panic = "the primes up to the bound of the sieve can't be counted"]
+    fn exact_size_too_large() {
+        bounded::<u128>(100_000_000_000_000_000_000).len();
+    }
+
+    #[test]
+    fn analytic_size_hint() {
+        // Exact while the bound is cheap to count up to.
+        let mut sieve = bounded::<u64>(1_000_000);
+        assert_eq!(sieve.size_hint(), (78_498, Some(78_498)));
+        assert_eq!(sieve.by_ref().collect::<Vec<_>>().len(), 78_498);
+        assert_eq!(sieve.size_hint(), (0, Some(0)));
+
+        let sieve = bounded::<u64>(EXACT_SIZE_HINT);
+        assert_eq!(sieve.size_hint(), (203_280_221, Some(203_280_221)));
+
+        // Larger bounds are estimated without counting or sieving up to them,
+        // until the length is asked for.
+        let mut sieve = bounded::<u64>(10_000_000_000);
+        let memory_usage = sieve.memory_usage();
+        let (lower, upper) = sieve.size_hint();
+        assert!(lower <= 455_052_511 && 455_052_511 <= upper.unwrap());
+        assert_eq!(sieve.total.load(Ordering::Relaxed), usize::MAX);
+        assert_eq!(sieve.memory_usage(), memory_usage);
+
+        assert_eq!(sieve.len(), 455_052_511);
+        assert_eq!(sieve.size_hint(), (455_052_511, Some(455_052_511)));
+        assert_eq!(
+            sieve.by_ref().take(10).collect::<Vec<_>>(),
+            [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+        assert_eq!(sieve.size_hint(), (455_052_501, Some(455_052_501)));
+
+        for &bound in &[EXACT_SIZE_HINT + 1, 1 << 40, u64::MAX] {
+            let sieve = bounded::<u64>(bound);
+            let memory_usage = sieve.memory_usage();
+            let (lower, upper) = sieve.size_hint();
+            let (lo, hi) = counting::prime_count_bounds(bound as f64);
+            assert!(
+                lo - 1.0 <= lower as f64 && upper.unwrap() as f64 <= hi + 1.0,
+                "{}",
+                bound
+            );
+            assert_eq!(sieve.total.load(Ordering::Relaxed), usize::MAX, "{}", bound);
+            assert_eq!(sieve.memory_usage(), memory_usage, "{}", bound);
+            assert_eq!(
+                sieve.take(5).collect::<Vec<_>>(),
+                [2, 3, 5, 7, 11],
+                "{}",
+                bound
+            );
+        }
+
+        // Otherwise estimated, and adjusted as primes are passed.
+        let mut sieve = bounded::<u128>(100_000_000_000_000_000_000);
+        let (lower, upper) = sieve.size_hint();
+        let expected = 2_220_819_602_560_918_840;
+        assert!(lower <= expected && expected <= upper.unwrap());
+        assert!(upper.unwrap() - lower < expected / 100);
+        assert_eq!(sieve.by_ref().take(1000).count(), 1000);
+        assert_eq!(sieve.size_hint(), (lower - 1000, upper.map(|n| n - 1000)));
+
+        // Restored sieves keep their bound.
+        let mut sieve = bounded::<u32>(1_000_000);
+        sieve.by_ref().take(10).count();
+        let sieve = Sieve::<u32, _>::from_parts(sieve.into_parts());
+        assert_eq!(sieve.size_hint(), (78_488, Some(78_488)));
+        assert_eq!(sieve.count(), 78_488);
+
+        // Unbounded sieves end with their type.
+        let (lower, upper) = infinite::<u64>().size_hint();
+        let expected = 425_656_284_035_217_743;
+        assert!(lower <= expected && expected <= upper.unwrap());
+        assert_eq!(infinite::<u128>().size_hint().1, None);
+
+        assert_eq!(infinite::<u8>().collect::<Vec<_>>().len(), 54);
+        assert_eq!(infinite::<u16>().collect::<Vec<_>>().len(), 6542);
+        assert_eq!(infinite::<i16>().collect::<Vec<_>>().len(), 3512);
+
+        let mut sieve = infinite::<u16>();
+        let mut count = 6542;
+
+        while count > 0 {
+            let (lower, upper) = sieve.size_hint();
+            assert!(lower <= count && count <= upper.unwrap(), "{}", count);
+            sieve.nth(99.min(count - 1));
+            count -= 100.min(count);
+        }
+
+        assert_eq!(sieve.next(), None);
+    }
+
+    #[test]
+    fn fused() {
+        fn assert_fused<T: iter::FusedIterator>(_: &T) {}
+
+        let mut sieve = bounded::<u32>(1000);
+        assert_fused(&sieve);
+        assert_eq!(sieve.by_ref().count(), 168);
+
+        let size = sieve.size();
+
+        for _ in 0..10 {
+            assert_eq!(sieve.next(), None);
+            assert_eq!(sieve.size(), size);
+        }
+
+        // Also when the last candidate isn't prime.
+        let mut sieve = bounded::<u32>(1001);
+        assert_eq!(sieve.by_ref().count(), 168);
+        let size = sieve.size();
+
+        for _ in 0..10 {
+            assert_eq!(sieve.next(), None);
+            assert_eq!(sieve.next_back(), None);
+            assert_eq!(sieve.size(), size);
+        }
+    }
+
+    #[test]
+    fn clone() {
+        let mut sieve = bounded::<u32>(100_000);
+        assert_eq!(sieve.nth(999), Some(7919));
+
+        let mut branch = sieve.clone();
+        assert_eq!(branch.size(), sieve.size());
+
+        // Advancing the clone leaves the original where it was.
+        assert_eq!(branch.next_prime_after(50_000), Some(50_021));
+        assert_eq!(branch.next_back(), Some(99_991));
+        assert_eq!(sieve.next(), Some(7927));
+
+        let branch = sieve.clone();
+        assert_eq!(branch.len(), sieve.len());
+        assert!(branch.eq(sieve));
+    }
+
+    #[test]
+    fn default() {
+        #[derive(Default)]
+        struct Wrapper {
+            sieve: Infinite<u32>,
+            hashed: Infinite<u64, hash::BuildHasherDefault<FxHasher>>,
+        }
+
+        assert!(Infinite::<u32>::default().take(5).eq([2, 3, 5, 7, 11]));
+
+        let wrapper = Wrapper::default();
+        assert!(wrapper.sieve.take(1000).eq(infinite::<u32>().take(1000)));
+        assert!(wrapper.hashed.take(1000).eq(infinite::<u64>().take(1000)));
+    }
+
+    #[test]
+    fn bound() {
+        let mut sieve = bounded::<u32>(1000);
+        assert_eq!(sieve.bound(), 1000);
+        assert_eq!(sieve.remaining_range(), Some((2, 1000)));
+
+        assert_eq!(sieve.nth(99), Some(541));
+        assert_eq!(sieve.bound(), 1000);
+        assert_eq!(sieve.remaining_range(), Some((542, 1000)));
+
+        sieve.skip_to(900);
+        assert_eq!(sieve.remaining_range(), Some((900, 1000)));
+        assert_eq!(sieve.next(), Some(907));
+
+        assert_eq!(sieve.next_back(), Some(997));
+        assert_eq!(sieve.bound(), 1000);
+        assert_eq!(sieve.remaining_range(), Some((908, 996)));
+
+        assert_eq!(sieve.by_ref().count(), 12);
+        assert_eq!(sieve.bound(), 1000);
+        assert_eq!(sieve.remaining_range(), None);
+
+        sieve.reset();
+        assert_eq!(sieve.remaining_range(), Some((2, 1000)));
+
+        // Bounds at the end of the type, and bounds with no primes.
+        let mut sieve = bounded(u8::MAX);
+        assert_eq!(sieve.by_ref().count(), 54);
+        assert_eq!(sieve.bound(), u8::MAX);
+        assert_eq!(sieve.remaining_range(), None);
+
+        let sieve = bounded(1u32);
+        assert_eq!(sieve.bound(), 1);
+        assert_eq!(sieve.remaining_range(), None);
+
+        let sieve = bounded(-5i32);
+        assert_eq!(sieve.bound(), -5);
+        assert_eq!(sieve.remaining_range(), None);
+    }
+
+    #[test]
+    fn extend_bound() {
+        for old in 0..150u32 {
+            for &taken in &[0, 1, 5, 10, 30] {
+                for &upper in &[old, old + 1, old + 50, 1000] {
+                    let mut sieve = bounded(old);
+                    let passed = sieve.by_ref().take(taken).collect::<Vec<_>>();
+                    sieve.extend_bound(upper);
+
+                    let expected = bounded(upper.max(old)).skip(passed.len());
+                    assert_eq!(sieve.len(), expected.len(), "{} {} {}", old, taken, upper);
+                    assert!(sieve.eq(expected), "{} {} {}", old, taken, upper);
+                }
+            }
+        }
+
+        // Extending repeatedly.
+        let mut sieve = bounded::<u64>(10);
+        let mut primes = Vec::new();
+
+        for upper in (10..100_000).step_by(997) {
+            sieve.extend_bound(upper);
+            primes.extend(sieve.by_ref().take(50));
+        }
+
+        sieve.extend_bound(100_000);
+        primes.extend(sieve.by_ref());
+        assert_eq!(primes, bounded::<u64>(100_000).collect::<Vec<_>>());
+
+        // Lower bounds are ignored.
+        let mut sieve = bounded::<u32>(100);
+        sieve.extend_bound(50);
+        assert_eq!(sieve.bound(), 100);
+        assert_eq!(sieve.count(), 25);
+
+        // Up to the end of the type.
+        let mut sieve = bounded(200u8);
+        assert_eq!(sieve.by_ref().count(), 46);
+        sieve.extend_bound(u8::MAX);
+        assert!(sieve.eq([211, 223, 227, 229, 233, 239, 241, 251]));
+
+        let mut sieve = bounded(i32::MAX - 1);
+        sieve.skip_to(i32::MAX - 10);
+        assert_eq!(sieve.next(), None);
+        sieve.extend_bound(i32::MAX);
+        assert!(sieve.eq([i32::MAX]));
+
+        let mut sieve = bounded(-10i32);
+        sieve.extend_bound(20);
+        assert!(sieve.eq([2, 3, 5, 7, 11, 13, 17, 19]));
+    }
+
+    #[test]
+    fn peek() {
+        let mut sieve = bounded::<u32>(100_000);
+        let mut expected = bounded::<u32>(100_000);
+
+        while let Some(p) = sieve.peek() {
+            assert_eq!(sieve.peek(), Some(p));
+            assert_eq!(sieve.len(), expected.len());
+            assert_eq!(sieve.next(), expected.next());
+        }
+
+        assert_eq!(expected.next(), None);
+        assert_eq!(sieve.next(), None);
+        assert_eq!(sieve.peek(), None);
+
+        // Peeking doesn't change the answers of other calls.
+        let mut sieve = infinite::<u64>();
+        assert_eq!(sieve.peek(), Some(2));
+        assert!(sieve.is_prime(2));
+        assert_eq!(sieve.peek(), Some(3));
+        assert_eq!(sieve.next_prime_after(4), Some(5));
+        assert_eq!(sieve.peek(), Some(7));
+        sieve.skip_to(100);
+        assert_eq!(sieve.peek(), Some(101));
+        assert_eq!(sieve.nth(1), Some(103));
+        assert!(!sieve.is_prime(105));
+        assert_eq!(sieve.peek(), Some(107));
+
+        let mut sieve = bounded::<u32>(100);
+        assert_eq!(sieve.peek(), Some(2));
+        assert_eq!(sieve.next_back(), Some(97));
+        assert_eq!(sieve.peek(), Some(2));
+        assert_eq!(sieve.by_ref().count(), 24);
+        assert_eq!(sieve.peek(), None);
+
+        // Up to the end of the type.
+        let mut sieve = infinite::<u8>();
+        let mut primes = Vec::new();
+
+        while let Some(p) = sieve.peek() {
+            assert_eq!(sieve.next(), Some(p));
+            primes.push(p);
+        }
+
+        assert_eq!(primes, bounded(u8::MAX).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn last_yielded() {
+        let mut sieve = infinite::<u64>();
+        assert_eq!(sieve.last_yielded(), None);
+        assert_eq!(sieve.peek(), Some(2));
+        assert_eq!(sieve.last_yielded(), None);
+
+        for _ in 0..50 {
+            let p = sieve.by_ref().filter(|&p| p % 4 == 1).nth(3);
+            assert_eq!(sieve.last_yielded(), p);
+        }
+
+        assert_eq!(sieve.last_yielded(), Some(2797));
+        assert_eq!(sieve.by_ref().nth(10_000), Some(109_471));
+        assert_eq!(sieve.last_yielded(), Some(109_471));
+        assert!(!sieve.is_prime(109_482));
+        assert_eq!(sieve.last_yielded(), Some(109_481));
+        assert!(sieve.is_prime(5));
+        assert_eq!(sieve.last_yielded(), Some(109_481));
+        sieve.skip_to(1_000_000);
+        assert_eq!(sieve.last_yielded(), Some(999_983));
+        assert_eq!(sieve.next_prime_after(2_000_000), Some(2_000_003));
+        assert_eq!(sieve.last_yielded(), Some(2_000_003));
+        sieve.reset();
+        assert_eq!(sieve.last_yielded(), None);
+
+        let mut sieve = bounded::<u32>(1000);
+        assert_eq!(sieve.next_back(), Some(997));
+        assert_eq!(sieve.last_yielded(), None);
+        let primes = sieve.by_ref().take_while(|&p| p < 500).count();
+        assert_eq!(primes, 95);
+        assert_eq!(sieve.last_yielded(), Some(503));
+        assert_eq!(sieve.by_ref().last(), Some(991));
+        assert_eq!(sieve.last_yielded(), Some(991));
+
+        let mut sieve = bounded::<u8>(u8::MAX);
+        assert_eq!(sieve.by_ref().count(), 54);
+        assert_eq!(sieve.last_yielded(), Some(251));
+        assert_eq!(sieve.next(), None);
+        assert_eq!(sieve.last_yielded(), Some(251));
+    }
+
+    #[test]
+    fn index_of() {
+        let mut sieve = infinite::<u64>();
+        assert_eq!(sieve.index_of(2), Some(1));
+        assert_eq!(sieve.index_of(541), Some(100));
+        assert_eq!(sieve.index_of(100), None);
+        assert_eq!(sieve.index_of(1), None);
+        assert_eq!(sieve.index_of(0), None);
+        assert_eq!(sieve.next(), Some(547));
+
+        let mut expected = infinite::<u64>();
+        let mut other = infinite::<u64>();
+
+        for (i, p) in expected.by_ref().take(2000).enumerate() {
+            assert_eq!(sieve.index_of(p), Some(i as u64 + 1), "{}", p);
+
+            if p > 2 {
+                assert_eq!(sieve.index_of(p + 1), None, "{}", p);
+            }
+
+            assert_eq!(other.index_of(p), Some(i as u64 + 1), "{}", p);
+        }
+
+        assert_eq!(sieve.next(), expected.next());
+        sieve.skip_to(1_000_000);
+        assert_eq!(sieve.index_of(1_000_003), Some(78_499));
+        assert_eq!(sieve.index_of(999_983), Some(78_498));
+
+        let mut sieve = bounded::<u32>(1000);
+        assert_eq!(sieve.next_back(), Some(997));
+        assert_eq!(sieve.next_back(), Some(991));
+        assert_eq!(sieve.index_of(997), Some(168));
+        assert_eq!(sieve.index_of(1009), Some(169));
+        assert_eq!(sieve.index_of(1_000_003), Some(78_499));
+        assert_eq!(sieve.index_of(1_000_001), None);
+        assert_eq!(sieve.index_of(983), Some(166));
+        assert_eq!(sieve.next(), None);
+
+        let mut sieve = bounded::<u8>(u8::MAX);
+        assert_eq!(sieve.index_of(251), Some(54));
+        assert_eq!(sieve.index_of(u8::MAX), None);
+        assert_eq!(sieve.index_of(127), Some(31));
+    }
+
+    #[test]
+    fn debug() {
+        let mut sieve = infinite::<u64>();
+        sieve.nth(999);
+        let size = sieve.size();
+
+        let debug = format!("{:?}", sieve);
+        assert!(
+            debug.contains(&format!("composites: {}", size)),
+            "{}",
+            debug
+        );
+        assert!(!debug.contains("sample"));
+
+        // The alternate format includes a bounded sample.
+        let debug = format!("{:#?}", sieve);
+        assert!(
+            debug.contains(&format!("composites: {},", size)),
+            "{}",
+            debug
+        );
+        assert_eq!(debug.matches(": ").count(), 6 + 8, "{}", debug);
+
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Wrapper(Sieve<u32, ops::RangeInclusive<u32>>);
+
+        let debug = format!("{:?}", Wrapper(bounded(10)));
+        assert!(
+            debug.starts_with("Wrapper(Sieve { iter: 2..=10,"),
+            "{}",
+            debug
+        );
+    }
+
+    #[test]
+    fn with_capacity() {
+        let mut sieve = infinite_with_capacity::<u64>(1000);
+        #[cfg(feature = "std")]
+        assert!(sieve.composite.capacity() >= 1000);
+        assert!(sieve.by_ref().take(1000).eq(infinite::<u64>().take(1000)));
+
+        sieve.reserve(10_000);
+        #[cfg(feature = "std")]
+        assert!(sieve.composite.capacity() >= sieve.size() + 10_000);
+        assert_eq!(sieve.next(), Some(7927));
+
+        let sieve = bounded_with_capacity::<u32>(1_000_000, 168);
+        assert!(sieve.eq(bounded::<u32>(1_000_000)));
+    }
+
+    #[test]
+    fn memory_usage() {
+        let mut sieve = infinite::<u64>();
+        #[cfg(feature = "std")]
+        let mut last = sieve.memory_usage();
+
+        for _ in 0..100_000 {
+            sieve.next();
+            let usage = sieve.memory_usage();
+            assert!(usage >= sieve.size() * 2 * mem::size_of::<u64>());
+
+            // Only the hash map never gives back the room it has allocated.
+            #[cfg(feature = "std")]
+            {
+                assert!(usage >= last);
+                last = usage;
+            }
+        }
+
+        // Recorded primes and the back end are accounted for.
+        let before = sieve.memory_usage();
+        sieve.factorize_with(1_000_003 * 1_000_033);
+        assert!(sieve.memory_usage() >= before + 78_498 * mem::size_of::<u64>());
+
+        let mut sieve = bounded::<u64>(1_000_000);
+        let before = sieve.memory_usage();
+        sieve.next_back();
+        assert!(sieve.memory_usage() > before);
+    }
+
+    #[test]
+    fn skip_to() {
+        let mut sieve = infinite::<u64>();
+        sieve.skip_to(1000);
+        assert_eq!(sieve.next(), Some(1009));
+        sieve.skip_to(2);
+        assert_eq!(sieve.next(), Some(1013));
+
+        // Primes themselves aren't skipped.
+        sieve.skip_to(1_000_003);
+        assert_eq!(sieve.next(), Some(1_000_003));
+        assert!(sieve
+            .take(1000)
+            .eq(range(1_000_004u64, 2_000_000).take(1000)));
+
+        let mut sieve = bounded::<u32>(10_000);
+        sieve.skip_to(1000);
+        assert_eq!(sieve.len(), 1229 - 168);
+        assert!(sieve.eq(range(1000u32, 10_000)));
+
+        let mut sieve = bounded::<u32>(9973);
+        sieve.skip_to(9973);
+        assert_eq!(sieve.next(), Some(9973));
+
+        for &(upper, n) in &[(9973, 9974), (9975, 10_000), (10, 11)] {
+            let mut sieve = bounded::<u32>(upper);
+            sieve.skip_to(n);
+            assert_eq!(sieve.len(), 0);
+            assert_eq!(sieve.next(), None);
+        }
+    }
+
+    #[test]
+    fn reset() {
+        let mut sieve = crate::infinite::<u64>();
+        let expected = sieve.by_ref().take(10_000).collect::<Vec<_>>();
+        let memory_usage = sieve.memory_usage();
+
+        for _ in 0..3 {
+            sieve.reset();
+            assert_eq!(sieve.size(), 0);
+            assert_eq!(sieve.max_tracked(), None);
+            assert!(sieve.by_ref().take(10_000).eq(expected.iter().copied()));
+            assert_eq!(sieve.memory_usage(), memory_usage);
+        }
+
+        // Resetting part way, after skipping and after factorizing.
+        sieve.reset();
+        sieve.skip_to(1_000_000);
+        assert_eq!(
+            sieve.factorize_with(1_000_006),
+            [(2, 1), (7, 1), (71_429, 1)]
         );
+        sieve.reset();
+        assert!(sieve.take(10_000).eq(expected.iter().copied()));
+
+        let mut sieve = crate::bounded::<u32>(10_000);
+        let expected = sieve.by_ref().collect::<Vec<_>>();
+
+        sieve.reset();
+        assert_eq!(sieve.len(), expected.len());
+        assert!(sieve.by_ref().rev().eq(expected.iter().rev().copied()));
+
+        sieve.reset();
+        assert!(sieve.by_ref().eq(expected.iter().copied()));
+
+        sieve.reset_with_bound(100_000);
+        assert_eq!(sieve.len(), 9592);
+        assert!(sieve.by_ref().eq(crate::bounded(100_000u32)));
+
+        sieve.reset_with_bound(u32::MAX);
+        assert_eq!(sieve.next_back(), Some(4_294_967_291));
+        assert_eq!(sieve.next(), Some(2));
+    }
+
+    #[test]
+    fn composites() {
+        let mut sieve = infinite::<u64>();
+        let primes = sieve.by_ref().take(10_000).collect::<Vec<_>>();
+        let last = primes[primes.len() - 1];
+
+        let mut tracked = sieve.composites().map(|(_, p)| p).collect::<Vec<_>>();
+        tracked.sort_unstable();
+        assert_eq!(tracked, primes[3..]);
+
+        for (c, p) in sieve.composites() {
+            assert!(c > last && c % (2 * p) == p && c % 3 != 0 && c % 5 != 0);
+        }
+
+        assert_eq!(sieve.max_tracked(), Some(last * last));
+
+        let mut sieve = bounded::<u64>(10_000);
+        sieve.by_ref().take(1000).count();
+        assert!(sieve.composites().all(|(c, _)| c <= 10_000));
+        assert_eq!(sieve.composites().count(), sieve.size());
+    }
+
+    #[test]
+    fn parts_round_trip() {
+        let expected = bounded::<u32>(100_000).collect::<Vec<_>>();
+
+        for &at in &[0, 1, 3, 4, 100, 5000] {
+            let mut sieve = bounded::<u32>(100_000);
+            let mut primes = sieve.by_ref().take(at).collect::<Vec<_>>();
+            let size = sieve.size();
+            let parts = sieve.into_parts();
+            assert_eq!(parts.composites.len(), size);
+            primes.extend(Sieve::from_parts(parts));
+            assert_eq!(primes, expected, "{}", at);
+        }
+
+        // The length is exact after restoring, even once primes have been
+        // produced from the back.
+        let mut sieve = bounded::<u32>(100_000);
+        assert_eq!(sieve.by_ref().take(100).count(), 100);
+        let sieve = Sieve::from_parts(sieve.into_parts());
+        assert_eq!(sieve.len(), expected.len() - 100);
+        assert_eq!(sieve.size_hint(), (sieve.len(), Some(sieve.len())));
+
+        let mut sieve = bounded::<u32>(100_000);
+        assert_eq!(sieve.by_ref().take(100).count(), 100);
+        assert_eq!(sieve.by_ref().rev().take(50).count(), 50);
+        let mut sieve = Sieve::from_parts(sieve.into_parts());
+        assert_eq!(sieve.len(), expected.len() - 150);
+        assert_eq!(sieve.next_back(), Some(expected[expected.len() - 51]));
+        let rest = sieve.collect::<Vec<_>>();
+        assert_eq!(rest, expected[100..expected.len() - 51]);
+
+        // A pending number left behind by `is_prime` is preserved.
+        let mut sieve = infinite::<u32>();
+        assert!(!sieve.is_prime(9));
+        let parts = sieve.into_parts();
+        assert_eq!(parts.pending, Some(11));
+        assert!(Sieve::from_parts(parts).take(3).eq([11, 13, 17]));
     }
 }
@@ -0,0 +1,60 @@
+//! Floating point helpers which are available without `std`.
+
+/// Compute the natural logarithm of `x`, which must be positive and finite.
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn ln(x: f64) -> f64 {
+    x.ln()
+}
+
+/// Compute the natural logarithm of `x`, which must be positive and finite.
+#[cfg(not(feature = "std"))]
+pub(crate) fn ln(x: f64) -> f64 {
+    use core::f64::consts::{LN_2, SQRT_2};
+
+    // Split `x` into `m * 2^e` where `m` is in `[1, 2)`.
+    let bits = x.to_bits();
+    let mut e = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    let mut m = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+
+    if m > SQRT_2 {
+        m /= 2.0;
+        e += 1;
+    }
+
+    // ln(m) = 2 atanh(z) where z = (m - 1) / (m + 1), whose series converges
+    // quickly since |z| < 0.18.
+    let z = (m - 1.0) / (m + 1.0);
+    let z2 = z * z;
+    let mut term = z;
+    let mut sum = 0.0;
+    let mut k = 1.0;
+
+    for _ in 0..20 {
+        sum += term / k;
+        term *= z2;
+        k += 2.0;
+    }
+
+    2.0 * sum + e as f64 * LN_2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ln;
+
+    #[test]
+    fn known_values() {
+        let values = [
+            (1.0, 0.0),
+            (2.0, core::f64::consts::LN_2),
+            (10.0, core::f64::consts::LN_10),
+            (1e6, 13.815510557964274),
+            (1e18, 41.44653167389282),
+        ];
+
+        for &(x, expected) in &values {
+            assert!((ln(x) - expected).abs() < 1e-12, "ln({})", x);
+        }
+    }
+}
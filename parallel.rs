@@ -0,0 +1,161 @@
+//! Sieving bounded ranges of primes on multiple threads.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::panic;
+use std::thread;
+
+use crate::flat::{isqrt, Segment};
+use crate::segmented::SEGMENT_LEN;
+use crate::small;
+use crate::FromSmall;
+
+/// Collect every prime up to and including `upper` in increasing order, using
+/// `threads` threads.
+///
+/// The base primes up to `sqrt(upper)` are sieved once and shared, after
+/// which the rest of the range is split into segments like
+/// [`segmented()`][crate::segmented()] which the threads sieve
+/// independently. The result is the same as collecting
+/// [`bounded()`][crate::bounded()], but sieving scales with the number of
+/// threads, up to the number of cores available.
+///
+/// A `threads` value of zero is treated as one.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// let primes = sieve::par_collect_bounded(100u32, 4);
+/// assert_eq!(primes.len(), 25);
+/// assert_eq!(primes[..5], [2, 3, 5, 7, 11]);
+///
+/// let primes = sieve::par_collect_bounded(10_000_000u64, 4);
+/// assert_eq!(primes.len(), 664_579);
+/// assert_eq!(primes.last(), Some(&9_999_991));
+/// ```
+pub fn par_collect_bounded<I>(upper: I, threads: usize) -> Vec<I>
+where
+    I: 'static + Send + Copy + Ord + FromSmall + TryFrom<u64>,
+    u64: TryFrom<I>,
+{
+    if upper < I::from_small(2) {
+        return Vec::new();
+    }
+
+    let upper = match u64::try_from(upper) {
+        Ok(upper) => upper,
+        Err(..) => panic!("bound doesn't fit in a u64"),
+    };
+
+    collect(upper, threads, SEGMENT_LEN)
+}
+
+/// Collect the primes in `2..=upper`, sieving `segment_len` odd numbers at a
+/// time.
+fn collect<I>(upper: u64, threads: usize, segment_len: u64) -> Vec<I>
+where
+    I: 'static + Send + TryFrom<u64>,
+{
+    let mut out = Vec::new();
+
+    if upper < 2 {
+        return out;
+    }
+
+    out.extend(I::try_from(2).ok());
+
+    if upper < 3 {
+        return out;
+    }
+
+    let primes = Arc::new(small::odd_primes(isqrt(upper)));
+
+    let segments = ((upper - 3) / 2 / segment_len) as usize + 1;
+    let next = Arc::new(AtomicUsize::new(0));
+
+    let handles = (0..threads.max(1).min(segments))
+        .map(|_| {
+            let primes = primes.clone();
+            let next = next.clone();
+
+            thread::spawn(move || {
+                let mut segment = Segment::new();
+                let mut done = Vec::new();
+
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+
+                    if index >= segments {
+                        break;
+                    }
+
+                    let lo = 3 + 2 * segment_len * index as u64;
+                    let hi = lo.saturating_add(2 * (segment_len - 1)).min(upper);
+                    segment.sieve(lo, hi, &primes);
+
+                    let mut found = Vec::new();
+
+                    while let Some(p) = segment.pop() {
+                        found.extend(I::try_from(p).ok());
+                    }
+
+                    done.push((index, found));
+                }
+
+                done
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut done = Vec::with_capacity(segments);
+
+    for handle in handles {
+        match handle.join() {
+            Ok(segments) => done.extend(segments),
+            Err(error) => panic::resume_unwind(error),
+        }
+    }
+
+    done.sort_unstable_by_key(|&(index, _)| index);
+    out.reserve(done.iter().map(|(_, found)| found.len()).sum());
+
+    for (_, found) in done {
+        out.extend(found);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{collect, par_collect_bounded};
+
+    #[test]
+    fn matches_bounded() {
+        for upper in 0..=1000u64 {
+            let expected = crate::bounded(upper).collect::<Vec<_>>();
+
+            for &(threads, segment_len) in &[(1, 64), (3, 1), (4, 7), (16, 64)] {
+                let actual = collect::<u64>(upper, threads, segment_len);
+                assert_eq!(actual, expected, "{} {} {}", upper, threads, segment_len);
+            }
+        }
+    }
+
+    #[test]
+    fn large_bounds() {
+        let expected = crate::segmented(20_000_000u32).collect::<Vec<_>>();
+
+        for &threads in &[0, 1, 2, 8] {
+            assert_eq!(par_collect_bounded(20_000_000u32, threads), expected);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! Saving the state of an infinite sieve to a file and loading it back.
+//!
+//! The format is little-endian throughout:
+//!
+//! * The magic bytes `b"SIEV"`.
+//! * A version byte, currently `1`.
+//! * The width in bytes of the integer type the sieve was saved with.
+//! * The next candidate, followed by the pending number and the last prime
+//!   passed, each prefixed by a byte which is `1` if it's present and `0` if
+//!   it isn't.
+//! * The number of tracked primes as a `u64`, followed by each of them as the
+//!   next composite to cross off and the prime it's a multiple of. A composite
+//!   is repeated for every prime sharing it.
+//!
+//! Except for the count of tracked primes, every number is stored in the width
+//! of the integer type.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::hash;
+use core::mem;
+use core::ops;
+use std::io::{self, Write};
+
+use crate::{CheckedOps, Parts, Sieve};
+
+const MAGIC: [u8; 4] = *b"SIEV";
+const VERSION: u8 = 1;
+
+impl<I, S> Sieve<I, ops::RangeFrom<I>, S>
+where
+    I: Copy,
+    u128: TryFrom<I>,
+{
+    /// Save the state of the sieve to `w`, so that it can be resumed with
+    /// [`Sieve::load_from`].
+    ///
+    /// This writes the same state as [`Sieve::into_parts`], so it takes time
+    /// and space proportional to [`Sieve::size`]. Only the state needed to
+    /// continue producing primes is saved, so the sieve doesn't remember the
+    /// primes recorded by [`Sieve::factorize_with`] once it's been loaded.
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing to `w` fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sieve = sieve::infinite::<u32>();
+    /// assert!(sieve.by_ref().take(4).eq([2, 3, 5, 7]));
+    ///
+    /// let mut state = Vec::new();
+    /// sieve.save_to(&mut state)?;
+    ///
+    /// let sieve = sieve::Sieve::<u32, _>::load_from(&state[..])?;
+    /// assert!(sieve.take(4).eq([11, 13, 17, 19]));
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn save_to<W>(&self, w: W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        let mut w = io::BufWriter::new(w);
+        w.write_all(&MAGIC)?;
+        w.write_all(&[VERSION, mem::size_of::<I>() as u8])?;
+        write_int(&mut w, self.iter.start)?;
+
+        for &n in &[self.pending, self.last] {
+            match n {
+                Some(n) => {
+                    w.write_all(&[1])?;
+                    write_int(&mut w, n)?;
+                }
+                None => w.write_all(&[0])?,
+            }
+        }
+
+        w.write_all(&(self.size() as u64).to_le_bytes())?;
+
+        for (key, prime) in self.composites() {
+            write_int(&mut w, key)?;
+            write_int(&mut w, prime)?;
+        }
+
+        w.flush()
+    }
+}
+
+impl<I> Sieve<I, ops::RangeFrom<I>>
+where
+    I: Copy + Ord + hash::Hash + TryFrom<u128> + CheckedOps,
+{
+    /// Load the state of a sieve saved by [`Sieve::save_to`] from `r`, which
+    /// continues producing primes exactly where the saved one left off.
+    ///
+    /// # Errors
+    ///
+    /// Errors with [`io::ErrorKind::InvalidData`] if the data wasn't saved by
+    /// a sieve over an integer type of the same width, or if it holds a number
+    /// which doesn't fit in `I`. Errors with [`io::ErrorKind::UnexpectedEof`]
+    /// if the data is truncated. Any other errors come from reading `r`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    ///
+    /// let mut sieve = sieve::infinite::<u64>();
+    /// assert_eq!(sieve.nth(999), Some(7919));
+    ///
+    /// let mut state = Vec::new();
+    /// sieve.save_to(&mut state)?;
+    ///
+    /// let error = sieve::Sieve::<u32, _>::load_from(&state[..]).unwrap_err();
+    /// assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    ///
+    /// let error = sieve::Sieve::<u64, _>::load_from(&state[..100]).unwrap_err();
+    /// assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    ///
+    /// let mut sieve = sieve::Sieve::<u64, _>::load_from(&state[..])?;
+    /// assert_eq!(sieve.next(), Some(7927));
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    pub fn load_from<R>(r: R) -> io::Result<Self>
+    where
+        R: io::Read,
+    {
+        let mut r = io::BufReader::new(r);
+
+        let mut header = [0u8; 6];
+        read_exact(&mut r, &mut header)?;
+
+        if header[..4] != MAGIC {
+            return Err(invalid("not a saved sieve"));
+        }
+
+        if header[4] != VERSION {
+            return Err(invalid("unsupported sieve state version"));
+        }
+
+        if usize::from(header[5]) != mem::size_of::<I>() {
+            return Err(invalid(
+                "sieve state was saved with a different integer width",
+            ));
+        }
+
+        let start = read_int::<I, _>(&mut r)?;
+        let pending = read_option(&mut r)?;
+        let last = read_option(&mut r)?;
+
+        let mut len = [0u8; 8];
+        read_exact(&mut r, &mut len)?;
+        let len = u64::from_le_bytes(len);
+
+        // Don't trust the length with a large allocation up front, since it
+        // might be corrupt.
+        let mut composites = Vec::with_capacity(len.min(1 << 16) as usize);
+
+        for _ in 0..len {
+            let key = read_int(&mut r)?;
+            let prime = read_int(&mut r)?;
+            composites.push((key, prime));
+        }
+
+        // The number of primes passed isn't saved, since it follows from the
+        // last of them.
+        let count = match last.and_then(CheckedOps::to_u64) {
+            Some(last) => crate::prime_pi(last) as usize,
+            None => 0,
+        };
+
+        Ok(Sieve::from_parts(Parts {
+            iter: start..,
+            pending,
+            last,
+            composites,
+            limit: None,
+            count,
+        }))
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_exact<R>(r: &mut R, buf: &mut [u8]) -> io::Result<()>
+where
+    R: io::Read,
+{
+    r.read_exact(buf).map_err(|error| {
+        if error.kind() == io::ErrorKind::UnexpectedEof {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "sieve state is truncated")
+        } else {
+            error
+        }
+    })
+}
+
+/// Write `n` in the width of its type.
+fn write_int<I, W>(w: &mut W, n: I) -> io::Result<()>
+where
+    u128: TryFrom<I>,
+    W: io::Write,
+{
+    // The sieve never holds negative numbers.
+    let n = match u128::try_from(n) {
+        Ok(n) => n,
+        Err(..) => return Err(invalid("sieve holds a negative number")),
+    };
+
+    w.write_all(&n.to_le_bytes()[..mem::size_of::<I>()])
+}
+
+/// Read a number in the width of its type.
+fn read_int<I, R>(r: &mut R) -> io::Result<I>
+where
+    I: TryFrom<u128>,
+    R: io::Read,
+{
+    let mut bytes = [0u8; 16];
+    read_exact(r, &mut bytes[..mem::size_of::<I>()])?;
+
+    match I::try_from(u128::from_le_bytes(bytes)) {
+        Ok(n) => Ok(n),
+        Err(..) => Err(invalid("sieve state holds a number which doesn't fit")),
+    }
+}
+
+fn read_option<I, R>(r: &mut R) -> io::Result<Option<I>>
+where
+    I: TryFrom<u128>,
+    R: io::Read,
+{
+    let mut present = [0u8; 1];
+    read_exact(r, &mut present)?;
+
+    match present[0] {
+        0 => Ok(None),
+        1 => Ok(Some(read_int(r)?)),
+        _ => Err(invalid("sieve state is corrupt")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::vec::Vec;
+
+    use crate::Sieve;
+
+    #[test]
+    fn resume() {
+        let expected = crate::infinite::<u64>().take(20_000).collect::<Vec<_>>();
+
+        for &at in &[0, 1, 2, 3, 4, 10, 100, 1234, 19_999] {
+            let mut sieve = crate::infinite::<u64>();
+            let mut actual = sieve.by_ref().take(at).collect::<Vec<_>>();
+            assert_eq!(sieve.size(), sieve.composites().count(), "{}", at);
+
+            let mut state = Vec::new();
+            sieve.save_to(&mut state).unwrap();
+            drop(sieve);
+
+            let sieve = Sieve::<u64, _>::load_from(&state[..]).unwrap();
+            actual.extend(sieve.take(20_000 - at));
+            assert_eq!(actual, expected, "{}", at);
+        }
+    }
+
+    #[test]
+    fn widths() {
+        let mut sieve = crate::infinite::<i16>();
+        assert_eq!(sieve.nth(99), Some(541));
+
+        let mut state = Vec::new();
+        sieve.save_to(&mut state).unwrap();
+
+        let sieve = Sieve::<i16, _>::load_from(&state[..]).unwrap();
+        assert!(sieve
+            .take(1000)
+            .eq(crate::infinite::<i16>().skip(100).take(1000)));
+
+        // The primes passed are counted again, so the end of the type is
+        // still in sight.
+        let sieve = Sieve::<i16, _>::load_from(&state[..]).unwrap();
+        assert_eq!(sieve.indexed().next(), Some((101, 547)));
+        let sieve = Sieve::<i16, _>::load_from(&state[..]).unwrap();
+        assert_eq!(sieve.collect::<Vec<_>>().len(), 3512 - 100);
+
+        let sieve = Sieve::<u16, _>::load_from(&state[..]).unwrap();
+        assert!(sieve
+            .take(10)
+            .eq(crate::infinite::<u16>().skip(100).take(10)));
+
+        for error in [
+            Sieve::<u32, _>::load_from(&state[..]).unwrap_err(),
+            Sieve::<u128, _>::load_from(&state[..]).unwrap_err(),
+        ] {
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn invalid_data() {
+        let mut sieve = crate::infinite::<u32>();
+        assert_eq!(sieve.nth(999), Some(7919));
+
+        let mut state = Vec::new();
+        sieve.save_to(&mut state).unwrap();
+
+        for len in 0..state.len() {
+            let error = Sieve::<u32, _>::load_from(&state[..len]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "{}", len);
+        }
+
+        // The magic bytes, the version and the presence of the pending number.
+        for &index in &[0, 4, 10] {
+            let mut state = state.clone();
+            state[index] = 2;
+            let error = Sieve::<u32, _>::load_from(&state[..]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", index);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Powers of primes.
+
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+use core::ops;
+
+use crate::{Bounded, SieveInt};
+
+/// Construct an iterator over the prime powers `p^k` with `k >= 1` up to and
+/// including `upper`, in increasing order.
+///
+/// The primes are drawn from a [`bounded()`][crate::bounded()] sieve and
+/// merged with the higher powers of the primes seen so far, which are kept in
+/// a binary heap. Only primes up to `sqrt(upper)` have higher powers, so the
+/// heap stays small. Powers are computed with checked multiplication, so
+/// `upper` can be anything up to the largest value of `I`.
+///
+/// # Examples
+///
+/// ```
+/// let powers = sieve::prime_powers(32u32);
+/// assert!(powers.eq([2, 3, 4, 5, 7, 8, 9, 11, 13, 16, 17, 19, 23, 25, 27, 29, 31, 32]));
+///
+/// assert_eq!(sieve::prime_powers(u8::MAX).last(), Some(251));
+/// ```
+pub fn prime_powers<I>(upper: I) -> PrimePowers<I>
+where
+    I: SieveInt,
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+{
+    PrimePowers {
+        primes: crate::bounded(upper),
+        upper,
+        next: None,
+        powers: BinaryHeap::new(),
+    }
+}
+
+/// An iterator over the prime powers up to a bound.
+///
+/// See [`prime_powers()`].
+pub struct PrimePowers<I> {
+    primes: Bounded<I>,
+    upper: I,
+    /// The next prime, once it's been taken from the sieve.
+    next: Option<I>,
+    /// The next power of each prime which has one up to the bound, along with
+    /// the prime and the exponent.
+    powers: BinaryHeap<Reverse<(I, I, u32)>>,
+}
+
+impl<I> PrimePowers<I> {
+    /// Include the prime and the exponent of each power, so that `p^k` is
+    /// produced as `(p^k, (p, k))`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut powers = sieve::prime_powers(100u32).with_exponents();
+    /// assert_eq!(powers.next(), Some((2, (2, 1))));
+    /// assert_eq!(powers.next(), Some((3, (3, 1))));
+    /// assert_eq!(powers.next(), Some((4, (2, 2))));
+    /// assert_eq!(powers.last(), Some((97, (97, 1))));
+    ///
+    /// let cubes = sieve::prime_powers(1000u32).with_exponents().filter(|&(_, (_, k))| k == 3);
+    /// assert!(cubes.map(|(n, _)| n).eq([8, 27, 125, 343]));
+    /// ```
+    pub fn with_exponents(self) -> WithExponents<I> {
+        WithExponents { iter: self }
+    }
+}
+
+impl<I> PrimePowers<I>
+where
+    I: SieveInt,
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+{
+    /// Get the next prime power along with its prime and exponent.
+    fn next_power(&mut self) -> Option<(I, (I, u32))> {
+        if self.next.is_none() {
+            self.next = self.primes.next();
+        }
+
+        let (n, p, k) = match (self.next, self.powers.peek()) {
+            (Some(prime), Some(&Reverse(power))) if power.0 < prime => {
+                self.powers.pop();
+                power
+            }
+            (Some(prime), _) => {
+                self.next = None;
+                (prime, prime, 1)
+            }
+            (None, Some(..)) => self.powers.pop()?.0,
+            (None, None) => return None,
+        };
+
+        if let Some(power) = n.checked_mul(p) {
+            if power <= self.upper {
+                self.powers.push(Reverse((power, p, k + 1)));
+            }
+        }
+
+        Some((n, (p, k)))
+    }
+}
+
+impl<I> Iterator for PrimePowers<I>
+where
+    I: SieveInt,
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+{
+    type Item = I;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_power()?.0)
+    }
+}
+
+/// An iterator over the prime powers up to a bound, along with their primes
+/// and exponents.
+///
+/// See [`PrimePowers::with_exponents`].
+pub struct WithExponents<I> {
+    iter: PrimePowers<I>,
+}
+
+impl<I> Iterator for WithExponents<I>
+where
+    I: SieveInt,
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+{
+    type Item = (I, (I, u32));
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next_power()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prime_powers;
+
+    /// Test if `n` is a prime power by trial division.
+    fn is_prime_power(n: u64) -> bool {
+        let p = (2..=n).find(|&d| n % d == 0);
+
+        match p {
+            Some(p) => {
+                let mut n = n;
+
+                while n % p == 0 {
+                    n /= p;
+                }
+
+                n == 1
+            }
+            None => false,
+        }
+    }
+
+    #[test]
+    fn first_terms() {
+        let expected = [
+            2, 3, 4, 5, 7, 8, 9, 11, 13, 16, 17, 19, 23, 25, 27, 29, 31, 32, 37, 41,
+        ];
+
+        assert!(prime_powers(u32::MAX).take(20).eq(expected));
+        assert_eq!(prime_powers(1_000_000u32).count(), 78_734);
+
+        for upper in 0..=1000u64 {
+            let expected = (0..=upper).filter(|&n| is_prime_power(n));
+            assert!(prime_powers(upper).eq(expected), "{}", upper);
+        }
+    }
+
+    #[test]
+    fn exponents() {
+        for (n, (p, k)) in prime_powers(100_000u64).with_exponents() {
+            assert_eq!(p.pow(k), n);
+            assert!(crate::is_prime_u64(p));
+        }
+    }
+
+    #[test]
+    fn end_of_type() {
+        let expected = (0..=u64::from(u8::MAX)).filter(|&n| is_prime_power(n));
+        assert!(prime_powers(u8::MAX).map(u64::from).eq(expected));
+
+        assert_eq!(prime_powers(u16::MAX).count(), 6634);
+        assert_eq!(prime_powers(u16::MAX).last(), Some(65_521));
+
+        // The largest prime power which fits is a square rather than a prime.
+        assert_eq!(prime_powers(i16::MAX).count(), 3589);
+        assert_eq!(prime_powers(i16::MAX).last(), Some(181 * 181));
+    }
+}
@@ -0,0 +1,671 @@
+//! Primality tests for numbers too large to sieve.
+
+use core::convert::TryFrom;
+
+use crate::residue::jacobi_u128;
+use crate::SieveInt;
+
+/// Small primes which are tried as divisors before falling back to
+/// Miller-Rabin, and which double as its witnesses.
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Test if `n` is prime using a deterministic Miller-Rabin test.
+///
+/// Testing against the first 12 primes as witnesses is known to give the
+/// right answer for every 64-bit integer, so unlike a sieve this runs in
+/// `O(log n)` time regardless of how large `n` is.
+///
+/// # Examples
+///
+/// ```
+/// assert!(sieve::is_prime_u64(2));
+/// assert!(!sieve::is_prime_u64(1));
+/// assert!(sieve::is_prime_u64(1_000_000_000_000_000_003));
+/// assert!(sieve::is_prime_u64(18_446_744_073_709_551_557));
+///
+/// // A Carmichael number, which fools the Fermat test for every coprime base.
+/// assert!(!sieve::is_prime_u64(3_215_031_751));
+/// ```
+pub fn is_prime_u64(n: u64) -> bool {
+    if let Some(prime) = crate::small::lookup(n) {
+        return prime;
+    }
+
+    for &p in &WITNESSES {
+        if n % p == 0 {
+            return n == p;
+        }
+    }
+
+    if n < 41 * 41 {
+        return true;
+    }
+
+    // Write `n - 1` as `d * 2^s` with `d` odd.
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+
+    'witness: for &a in &WITNESSES {
+        let mut x = pow_mod(a, d, n);
+
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+
+        return false;
+    }
+
+    true
+}
+
+/// Test if `n` is prime using the Baillie-PSW test.
+///
+/// This is a strong probable prime test to base 2, followed by a strong Lucas
+/// probable prime test with parameters chosen by Selfridge's method. No
+/// composite number is known to pass both, and it has been checked that none
+/// exists below `2^64`, so the answer is exact for everything which fits in a
+/// `u64`. Beyond that it's the standard test used when a proof of primality
+/// isn't needed.
+///
+/// Even numbers and multiples of small primes are ruled out up front, as are
+/// perfect squares, which would otherwise keep the Lucas parameters from
+/// being found.
+///
+/// # Examples
+///
+/// ```
+/// assert!(sieve::is_probable_prime(2));
+/// assert!(!sieve::is_probable_prime(1));
+/// assert!(sieve::is_probable_prime(18_446_744_073_709_551_557));
+///
+/// // Strong pseudoprimes to base 2 are caught by the Lucas test.
+/// assert!(!sieve::is_probable_prime(2047));
+/// assert!(!sieve::is_probable_prime(3277));
+///
+/// // The largest Mersenne prime which fits in a `u128`.
+/// assert!(sieve::is_probable_prime((1 << 127) - 1));
+/// ```
+pub fn is_probable_prime(n: u128) -> bool {
+    if let Ok(n) = u64::try_from(n) {
+        if let Some(prime) = crate::small::lookup(n) {
+            return prime;
+        }
+    }
+
+    for &p in &WITNESSES {
+        if n % u128::from(p) == 0 {
+            return n == u128::from(p);
+        }
+    }
+
+    if n < 41 * 41 {
+        return n > 1;
+    }
+
+    strong_probable_prime(n, 2) && !is_square(n) && strong_lucas(n)
+}
+
+/// Test if the odd number `n` is a strong probable prime to base `a`.
+fn strong_probable_prime(n: u128, a: u128) -> bool {
+    // Write `n - 1` as `d * 2^s` with `d` odd.
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    let mut x = pow_mod_u128(a, d, n);
+
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+
+    for _ in 1..s {
+        x = mul_mod_u128(x, x, n);
+
+        if x == n - 1 {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Test if the odd number `n`, which isn't a perfect square and has no factors
+/// below 41, is a strong Lucas probable prime.
+///
+/// The parameters are `P = 1` and `Q = (1 - D) / 4`, where `D` is the first
+/// of `5, -7, 9, -11, ...` for which the Jacobi symbol `(D/n)` is -1.
+fn strong_lucas(n: u128) -> bool {
+    let mut d = 5i128;
+
+    // Such a `D` always exists since `n` isn't a square, and it's found after
+    // a handful of tries on average.
+    let d = loop {
+        let residue = if d < 0 {
+            n - d.unsigned_abs() % n
+        } else {
+            d as u128 % n
+        };
+
+        match jacobi_u128(residue, n) {
+            -1 => break d,
+            // `n` has a factor in common with `D`, which is smaller than it.
+            0 if d.unsigned_abs() < n => return false,
+            _ => {}
+        }
+
+        d = if d < 0 { -d + 2 } else { -d - 2 };
+    };
+
+    let signed = |x: i128| {
+        if x < 0 {
+            n - x.unsigned_abs() % n
+        } else {
+            x as u128 % n
+        }
+    };
+
+    let q = signed((1 - d) / 4);
+    let d = signed(d);
+
+    // `n` has no small factors, so `n + 1` can't overflow. Write it as
+    // `k * 2^s` with `k` odd.
+    let s = (n + 1).trailing_zeros();
+    let k = (n + 1) >> s;
+
+    // Walk the bits of `k` from the top, doubling the index of `U`, `V` and
+    // `Q^index` at each step and incrementing it for each set bit.
+    let (mut u, mut v, mut qk) = (1, 1, q);
+
+    for bit in (0..127 - k.leading_zeros()).rev() {
+        u = mul_mod_u128(u, v, n);
+        v = sub_mod(mul_mod_u128(v, v, n), add_mod(qk, qk, n), n);
+        qk = mul_mod_u128(qk, qk, n);
+
+        if (k >> bit) & 1 == 1 {
+            let next_u = half_mod(add_mod(u, v, n), n);
+            v = half_mod(add_mod(mul_mod_u128(d, u, n), v, n), n);
+            u = next_u;
+            qk = mul_mod_u128(qk, q, n);
+        }
+    }
+
+    if u == 0 || v == 0 {
+        return true;
+    }
+
+    for _ in 1..s {
+        v = sub_mod(mul_mod_u128(v, v, n), add_mod(qk, qk, n), n);
+        qk = mul_mod_u128(qk, qk, n);
+
+        if v == 0 {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Test if `n` is a perfect square.
+fn is_square(n: u128) -> bool {
+    // Start from a power of two which is at least `sqrt(n)`, from which
+    // Newton's method converges downwards.
+    let mut x = 1u128 << ((128 - n.leading_zeros() + 1) / 2);
+
+    loop {
+        let y = (x + n / x) / 2;
+
+        if y >= x {
+            return x * x == n;
+        }
+
+        x = y;
+    }
+}
+
+/// Get the smallest prime strictly greater than `n`.
+///
+/// Instead of sieving, this tests the odd candidates above `n` one at a time
+/// with [`is_prime_u64()`], which is fast since the gap to the next prime is
+/// `O(ln n)` on average. Candidates which don't fit in a `u64` fall back to
+/// trial division, which is only practical for small gaps.
+///
+/// Returns `None` if the next prime doesn't fit in `I`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::next_prime(0u32), Some(2));
+/// assert_eq!(sieve::next_prime(2u32), Some(3));
+/// assert_eq!(sieve::next_prime(90u32), Some(97));
+/// assert_eq!(sieve::next_prime(1_000_000_000u64), Some(1_000_000_007));
+///
+/// // 251 is the largest prime which fits in a `u8`.
+/// assert_eq!(sieve::next_prime(250u8), Some(251));
+/// assert_eq!(sieve::next_prime(251u8), None);
+/// assert_eq!(sieve::next_prime(u8::MAX), None);
+/// ```
+pub fn next_prime<I>(n: I) -> Option<I>
+where
+    I: SieveInt,
+{
+    let two = I::from_small(2);
+
+    if n < two {
+        return Some(two);
+    }
+
+    let mut candidate = n.checked_add(I::from_small(1))?;
+
+    if candidate % two == I::from_small(0) {
+        candidate = candidate.checked_add(I::from_small(1))?;
+    }
+
+    loop {
+        if is_prime(candidate) {
+            return Some(candidate);
+        }
+
+        candidate = candidate.checked_add(two)?;
+    }
+}
+
+/// Get the largest prime strictly less than `n`.
+///
+/// This tests the odd candidates below `n` one at a time like
+/// [`next_prime()`].
+///
+/// Returns `None` if there is no such prime, which is the case when `n` is 2
+/// or less.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::prev_prime(2u32), None);
+/// assert_eq!(sieve::prev_prime(3u32), Some(2));
+/// assert_eq!(sieve::prev_prime(100u32), Some(97));
+/// assert_eq!(sieve::prev_prime(u64::MAX), Some(18_446_744_073_709_551_557));
+/// ```
+pub fn prev_prime<I>(n: I) -> Option<I>
+where
+    I: SieveInt,
+{
+    let two = I::from_small(2);
+    let three = I::from_small(3);
+
+    if n <= two {
+        return None;
+    }
+
+    if n == three {
+        return Some(two);
+    }
+
+    let mut candidate = n - I::from_small(1);
+
+    if candidate % two == I::from_small(0) {
+        candidate = candidate - I::from_small(1);
+    }
+
+    while candidate >= three {
+        if is_prime(candidate) {
+            return Some(candidate);
+        }
+
+        candidate = candidate - two;
+    }
+
+    Some(two)
+}
+
+/// Test if the Mersenne number `2^p - 1` is prime using the Lucas-Lehmer
+/// test.
+///
+/// The exponent is first checked to be prime against the sieve, since `2^p -
+/// 1` is composite whenever `p` is. The test then iterates `s <- s^2 - 2`
+/// modulo `2^p - 1`, which is reduced with shifts and adds by making use of
+/// the form of the modulus rather than through division.
+///
+/// Returns `None` if `2^p - 1` doesn't fit in a `u128`, which is the case
+/// when `p` is greater than 127.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::lucas_lehmer(7), Some(true));
+/// assert_eq!(sieve::lucas_lehmer(11), Some(false));
+/// assert_eq!(sieve::lucas_lehmer(127), Some(true));
+/// assert_eq!(sieve::lucas_lehmer(521), None);
+/// ```
+pub fn lucas_lehmer(p: u32) -> Option<bool> {
+    if p > 127 {
+        return None;
+    }
+
+    if crate::bounded(p).next_back() != Some(p) {
+        return Some(false);
+    }
+
+    // The recurrence only applies to odd exponents, but `2^2 - 1 = 3` is
+    // prime.
+    if p == 2 {
+        return Some(true);
+    }
+
+    let m = (1u128 << p) - 1;
+    let mut s = 4u128;
+
+    for _ in 0..p - 2 {
+        s = square_mod_mersenne(s, p, m);
+        s = if s < 2 { s + m - 2 } else { s - 2 };
+    }
+
+    Some(s == 0)
+}
+
+/// Compute `s^2 mod m` where `m = 2^p - 1`, `p <= 127` and `s < m`.
+fn square_mod_mersenne(s: u128, p: u32, m: u128) -> u128 {
+    const LOW: u128 = u64::MAX as u128;
+
+    // Square into a 256-bit product `hi * 2^128 + lo`, where the cross term
+    // can't overflow since `s < 2^127`.
+    let (a, b) = (s >> 64, s & LOW);
+    let cross = (a * b) << 1;
+    let (lo, carry) = (b * b).overflowing_add(cross << 64);
+    let hi = a * a + (cross >> 64) + u128::from(carry);
+
+    // Since `2^p = 1 mod m`, the bits above `p` can be folded onto the low
+    // bits. The product is less than `2^(2p)`, so the high part fits after a
+    // single shift.
+    let high = (lo >> p) | (hi << (128 - p));
+    let mut r = (lo & m) + high;
+
+    while r >= m {
+        r -= m;
+    }
+
+    r
+}
+
+/// Test if `n` is prime, using Miller-Rabin if it fits in a `u64` and trial
+/// division otherwise.
+fn is_prime<I>(n: I) -> bool
+where
+    I: SieveInt,
+{
+    if let Some(n) = n.to_u64() {
+        return is_prime_u64(n);
+    }
+
+    let zero = I::from_small(0);
+    let two = I::from_small(2);
+
+    if n < two {
+        return false;
+    }
+
+    if n % two == zero {
+        return n == two;
+    }
+
+    let mut d = I::from_small(3);
+
+    while d <= n / d {
+        if n % d == zero {
+            return false;
+        }
+
+        d = d + two;
+    }
+
+    true
+}
+
+/// Compute `a * b mod m` without overflowing.
+#[inline]
+pub(crate) fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (u128::from(a) * u128::from(b) % u128::from(m)) as u64
+}
+
+/// Compute `a + b mod m` without overflowing, where `a, b < m`.
+#[inline]
+fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    let (sum, overflow) = a.overflowing_add(b);
+
+    if overflow || sum >= m {
+        sum.wrapping_sub(m)
+    } else {
+        sum
+    }
+}
+
+/// Compute `a - b mod m`, where `a, b < m`.
+#[inline]
+fn sub_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= b {
+        a - b
+    } else {
+        m - (b - a)
+    }
+}
+
+/// Compute `a / 2 mod m` for an odd `m`, where `a < m`.
+#[inline]
+fn half_mod(a: u128, m: u128) -> u128 {
+    if a % 2 == 0 {
+        a / 2
+    } else {
+        // `(a + m) / 2`, where both are odd.
+        a / 2 + m / 2 + 1
+    }
+}
+
+/// Compute `a * b mod m` without overflowing, where `a, b < m`.
+///
+/// Products of operands which fit in a `u64` are computed directly, and the
+/// rest by doubling and adding.
+fn mul_mod_u128(a: u128, b: u128, m: u128) -> u128 {
+    if let (Ok(a), Ok(b)) = (u64::try_from(a), u64::try_from(b)) {
+        return u128::from(a) * u128::from(b) % m;
+    }
+
+    let (mut a, mut b, mut result) = (a, b, 0);
+
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a, m);
+        }
+
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+
+    result
+}
+
+/// Compute `base^exp mod m` by repeated squaring.
+fn pow_mod_u128(base: u128, mut exp: u128, m: u128) -> u128 {
+    let mut base = base % m;
+    let mut result = 1;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod_u128(result, base, m);
+        }
+
+        base = mul_mod_u128(base, base, m);
+        exp >>= 1;
+    }
+
+    result
+}
+
+/// Compute `base^exp mod m` by repeated squaring.
+pub(crate) fn pow_mod(base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut base = base % m;
+    let mut result = 1;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_prime_u64, is_probable_prime, lucas_lehmer, next_prime, prev_prime};
+    use crate::flat::Flat;
+
+    #[test]
+    fn matches_sieve() {
+        const UPPER: usize = 10_000_000;
+
+        let mut primes = Flat::new(UPPER).odd_primes().into_iter().peekable();
+
+        for n in 0..=UPPER as u64 {
+            let expected = n == 2 || primes.next_if_eq(&n).is_some();
+            assert_eq!(is_prime_u64(n), expected, "{}", n);
+            assert_eq!(is_probable_prime(n.into()), expected, "{}", n);
+        }
+    }
+
+    #[test]
+    fn large_values() {
+        // Carmichael numbers and strong pseudoprimes to small bases.
+        for &n in &[
+            561,
+            41_041,
+            3_215_031_751,
+            3_825_123_056_546_413_051,
+            1_000_000_007 * 998_244_353,
+            (1 << 32) + 1,
+            u64::MAX,
+        ] {
+            assert!(!is_prime_u64(n), "{}", n);
+        }
+
+        for &n in &[
+            1_000_000_007,
+            4_294_967_291,
+            2_305_843_009_213_693_951,
+            18_446_744_073_709_551_557,
+        ] {
+            assert!(is_prime_u64(n), "{}", n);
+        }
+    }
+
+    #[test]
+    fn baillie_psw() {
+        // Strong pseudoprimes to base 2, and strong Lucas pseudoprimes.
+        for &n in &[
+            2047,
+            3277,
+            4033,
+            4681,
+            8321,
+            3_215_031_751,
+            5459,
+            5777,
+            10_877,
+            16_109,
+            18_971,
+            1_000_000_007 * 998_244_353,
+        ] {
+            assert!(!is_probable_prime(n), "{}", n);
+        }
+
+        // Big values agree with Miller-Rabin.
+        for n in u64::MAX - 10_000..=u64::MAX {
+            assert_eq!(is_probable_prime(n.into()), is_prime_u64(n), "{}", n);
+        }
+
+        // Squares, which are caught before looking for the Lucas parameters.
+        for &p in &[1_000_000_007u128, 4_294_967_291, 18_446_744_073_709_551_557] {
+            assert!(!is_probable_prime(p * p), "{}", p);
+        }
+
+        // Primes past `u64`, and products of them.
+        let p = u128::from(u64::MAX - 58);
+        let q = (1u128 << 89) - 1;
+
+        for &n in &[p, q, (1 << 127) - 1, u128::MAX - 158] {
+            assert!(is_probable_prime(n), "{}", n);
+        }
+
+        for &n in &[p * 3, p * 1_000_000_007, q * 127, u128::MAX, u128::MAX - 1] {
+            assert!(!is_probable_prime(n), "{}", n);
+        }
+
+        for p in 0..=127 {
+            let expected = lucas_lehmer(p) == Some(true);
+            assert_eq!(is_probable_prime((1 << p) - 1), expected, "{}", p);
+        }
+    }
+
+    #[test]
+    fn next_and_prev_match_sieve() {
+        let primes = crate::bounded(100_100u32).collect::<alloc::vec::Vec<_>>();
+
+        for n in 0..100_000u32 {
+            let next = primes[primes.partition_point(|&p| p <= n)];
+            assert_eq!(next_prime(n), Some(next), "{}", n);
+
+            let prev = primes.partition_point(|&p| p < n);
+            let prev = prev.checked_sub(1).map(|i| primes[i]);
+            assert_eq!(prev_prime(n), prev, "{}", n);
+        }
+    }
+
+    #[test]
+    fn next_and_prev_edges() {
+        for n in 0..=u8::MAX {
+            let expected = (u32::from(n) + 1..256).find(|&p| is_prime_u64(u64::from(p)));
+            assert_eq!(next_prime(n).map(u32::from), expected, "{}", n);
+        }
+
+        assert_eq!(next_prime(-100i64), Some(2));
+        assert_eq!(prev_prime(-100i64), None);
+        assert_eq!(prev_prime(0u32), None);
+        assert_eq!(prev_prime(2u32), None);
+        assert_eq!(prev_prime(3u32), Some(2));
+        assert_eq!(prev_prime(4u32), Some(3));
+
+        assert_eq!(next_prime(u32::MAX - 4), None);
+        assert_eq!(next_prime(u32::MAX - 5), Some(u32::MAX - 4));
+        assert_eq!(next_prime(u64::MAX - 58), None);
+        assert_eq!(next_prime(u64::MAX - 59), Some(u64::MAX - 58));
+        assert_eq!(
+            next_prime(u128::from(u64::MAX - 59)),
+            Some(u128::from(u64::MAX - 58))
+        );
+    }
+
+    #[test]
+    fn mersenne_exponents() {
+        const EXPONENTS: [u32; 12] = [2, 3, 5, 7, 13, 17, 19, 31, 61, 89, 107, 127];
+
+        for p in 0..=127 {
+            assert_eq!(lucas_lehmer(p), Some(EXPONENTS.contains(&p)), "{}", p);
+        }
+
+        for p in 1..=63 {
+            let expected = is_prime_u64((1 << p) - 1);
+            assert_eq!(lucas_lehmer(p), Some(expected), "{}", p);
+        }
+
+        assert_eq!(lucas_lehmer(128), None);
+        assert_eq!(lucas_lehmer(521), None);
+        assert_eq!(lucas_lehmer(u32::MAX), None);
+    }
+}
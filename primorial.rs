@@ -0,0 +1,90 @@
+//! Products of consecutive primes.
+
+use core::ops;
+
+use crate::SieveInt;
+
+/// Get the primorial `p(n)#`, which is the product of the first `n` primes.
+///
+/// The primes are drawn from an [`infinite()`][crate::infinite()] sieve, and
+/// multiplied with
+/// [`CheckedOps::checked_mul`][crate::CheckedOps::checked_mul] so that this
+/// returns `None` as soon as the product overflows `I`. The product of no
+/// primes is 1.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::primorial::<u32>(0), Some(1));
+/// assert_eq!(sieve::primorial::<u32>(1), Some(2));
+/// assert_eq!(sieve::primorial::<u32>(3), Some(30));
+///
+/// // The product of the primes up to 47 is the largest which fits in a `u64`.
+/// assert_eq!(sieve::primorial::<u64>(15), Some(614_889_782_588_491_410));
+/// assert_eq!(sieve::primorial::<u64>(16), None);
+/// ```
+pub fn primorial<I>(n: u32) -> Option<I>
+where
+    ops::RangeFrom<I>: Iterator<Item = I>,
+    I: SieveInt,
+{
+    crate::infinite::<I>()
+        .take(n as usize)
+        .try_fold(I::from_small(1), I::checked_mul)
+}
+
+/// Get the primorial `x#`, which is the product of every prime up to and
+/// including `x`.
+///
+/// This returns `None` if the product overflows `I`, like [`primorial()`].
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::primorial_below(1u32), Some(1));
+/// assert_eq!(sieve::primorial_below(10u32), Some(210));
+/// assert_eq!(sieve::primorial_below(11u32), Some(2310));
+/// assert_eq!(sieve::primorial_below(100u64), None);
+/// ```
+pub fn primorial_below<I>(x: I) -> Option<I>
+where
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+    I: SieveInt,
+{
+    crate::bounded(x).try_fold(I::from_small(1), I::checked_mul)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{primorial, primorial_below};
+
+    #[test]
+    fn known_values() {
+        let expected = [1u64, 2, 6, 30, 210, 2310, 30_030, 510_510, 9_699_690];
+
+        for (n, &expected) in expected.iter().enumerate() {
+            assert_eq!(primorial::<u64>(n as u32), Some(expected));
+        }
+
+        assert_eq!(primorial::<u32>(9), Some(223_092_870));
+        assert_eq!(primorial::<u32>(10), None);
+        assert_eq!(primorial::<u64>(15), Some(614_889_782_588_491_410));
+        assert_eq!(primorial::<u64>(16), None);
+        assert_eq!(primorial::<u64>(u32::MAX), None);
+
+        let p26 = primorial::<u128>(26).unwrap();
+        assert_eq!(p26, 232_862_364_358_497_360_900_063_316_880_507_363_070);
+        assert_eq!(primorial::<u128>(27), None);
+    }
+
+    #[test]
+    fn below() {
+        assert_eq!(primorial_below(0u64), Some(1));
+        assert_eq!(primorial_below(2u64), Some(2));
+        assert_eq!(primorial_below(46u64), primorial(14));
+        assert_eq!(primorial_below(47u64), primorial(15));
+        assert_eq!(primorial_below(52u64), primorial(15));
+        assert_eq!(primorial_below(53u64), None);
+        assert_eq!(primorial_below(-5i64), Some(1));
+    }
+}
@@ -0,0 +1,349 @@
+//! Primes in an arithmetic progression.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker;
+
+use crate::flat::{isqrt, Window};
+use crate::segmented::SEGMENT_LEN;
+use crate::small;
+
+/// The error raised when an arithmetic progression is invalid.
+///
+/// See [`in_progression()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidProgression {
+    /// The modulus is zero.
+    ZeroModulus,
+    /// The residue and the modulus share a factor, and the progression
+    /// contains no primes.
+    NotCoprime,
+}
+
+impl fmt::Display for InvalidProgression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidProgression::ZeroModulus => write!(f, "modulus is zero"),
+            InvalidProgression::NotCoprime => {
+                write!(f, "residue and modulus aren't coprime")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidProgression {}
+
+/// An unbounded sieve over the primes in an arithmetic progression.
+///
+/// See [`in_progression()`].
+pub struct InProgression<I> {
+    /// The smallest member of the progression.
+    r: u64,
+    m: u64,
+    /// The largest index whose member fits in a `u64`.
+    last: u64,
+    /// Odd base primes up to `horizon`.
+    primes: Vec<u64>,
+    horizon: u64,
+    /// The indexes `k` of the members `r + k * m` left in the current segment
+    /// which are prime.
+    window: Window,
+    /// The index of the first member of the next segment, or `None` once the
+    /// end of `u64` has been reached.
+    next: Option<u64>,
+    segment_len: u64,
+    /// A single prime to produce, for progressions which aren't coprime.
+    single: Option<u64>,
+    _marker: marker::PhantomData<I>,
+}
+
+impl<I> InProgression<I> {
+    /// Construct a sieve over the primes congruent to `a` modulo `m`, where
+    /// `a` and `m` are coprime.
+    fn new(a: u64, m: u64, segment_len: u64) -> Self {
+        let r = a % m;
+
+        Self {
+            r,
+            m,
+            last: (u64::MAX - r) / m,
+            primes: Vec::new(),
+            horizon: 0,
+            window: Window::new(),
+            next: Some(0),
+            segment_len,
+            single: None,
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Construct a sieve which only produces `p`.
+    fn single(p: u64) -> Self {
+        let mut this = Self::new(0, 1, 1);
+        this.next = None;
+        this.single = Some(p);
+        this
+    }
+
+    /// Sieve the members with indexes `lo..=hi`.
+    fn sieve(&mut self, lo: u64, hi: u64) {
+        let len = (hi - lo + 1) as usize;
+        let top = self.r + hi * self.m;
+        let root = isqrt(top);
+
+        if self.horizon < root {
+            // Grow geometrically like `starting_at()`, so that the base primes
+            // are re-sieved only a logarithmic number of times.
+            self.horizon = root
+                .max(self.horizon.saturating_mul(2))
+                .min(u32::MAX as u64);
+            self.primes = small::odd_primes(self.horizon);
+        }
+
+        self.window.reset(lo, hi);
+
+        // 0 and 1 are only members when they come first.
+        let mut i = 0;
+
+        while i < len && self.r + (lo + i as u64) * self.m < 2 {
+            self.window.clear(i);
+            i += 1;
+        }
+
+        for &p in [2].iter().chain(&self.primes) {
+            if p > top / p {
+                break;
+            }
+
+            // No member is divisible by a factor of the modulus.
+            let m = self.m % p;
+
+            if m == 0 {
+                continue;
+            }
+
+            // Solve `r + k * m = 0 (mod p)` for the first index `k >= lo`.
+            let t = (p - self.r % p) % p * inverse(m, p) % p;
+            let mut k = lo + (t + p - lo % p) % p;
+
+            // Neither the prime itself nor 0 are composite multiples of it.
+            while k <= hi && self.r + k * self.m <= p {
+                k += p;
+            }
+
+            if k <= hi {
+                self.window.clear_multiples((k - lo) as usize, p as usize);
+            }
+        }
+    }
+
+    /// Remove and return the smallest prime remaining in the segment.
+    #[inline]
+    fn pop(&mut self) -> Option<u64> {
+        let k = self.window.pop()?;
+        Some(self.r + k * self.m)
+    }
+}
+
+/// Construct an unbounded sieve over the primes which are congruent to `a`
+/// modulo `m`, in increasing order.
+///
+/// Only the members of the progression are sieved, in segments like
+/// [`starting_at()`][crate::starting_at()], so numbers outside of it are
+/// never considered. This makes it roughly `φ(m)` times faster than filtering
+/// the output of another sieve. It stops once it reaches the end of `u64`, or
+/// when a prime no longer fits in `I`.
+///
+/// If `a` and `m` share a factor, every member of the progression is
+/// divisible by it, so it contains at most one prime. Such a progression is
+/// rejected with [`InvalidProgression::NotCoprime`] unless it does contain a
+/// prime, in which case that is all the sieve produces.
+///
+/// # Errors
+///
+/// Errors if `m` is zero, or if `a` and `m` share a factor and the
+/// progression contains no primes.
+///
+/// # Panics
+///
+/// Panics if `a` or `m` don't fit in a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// let primes = sieve::in_progression(1u32, 4)?.take(8);
+/// assert!(primes.eq([5, 13, 17, 29, 37, 41, 53, 61]));
+///
+/// let primes = sieve::in_progression(3u64, 8)?.take(6);
+/// assert!(primes.eq([3, 11, 19, 43, 59, 67]));
+///
+/// // The only prime congruent to 2 modulo 4.
+/// assert!(sieve::in_progression(2u32, 4)?.eq([2]));
+/// assert!(sieve::in_progression(4u32, 6).is_err());
+/// # Ok::<_, sieve::InvalidProgression>(())
+/// ```
+pub fn in_progression<I>(a: I, m: I) -> Result<InProgression<I>, InvalidProgression>
+where
+    I: Copy,
+    u64: TryFrom<I>,
+{
+    let (a, m) = match (u64::try_from(a), u64::try_from(m)) {
+        (Ok(a), Ok(m)) => (a, m),
+        _ => panic!("progression doesn't fit in a u64"),
+    };
+
+    if m == 0 {
+        return Err(InvalidProgression::ZeroModulus);
+    }
+
+    let g = gcd(a % m, m);
+
+    if g == 1 {
+        return Ok(InProgression::new(a, m, SEGMENT_LEN));
+    }
+
+    // Every member is divisible by `g`, so `g` is the only candidate.
+    if g % m == a % m && crate::is_prime_u64(g) {
+        return Ok(InProgression::single(g));
+    }
+
+    Err(InvalidProgression::NotCoprime)
+}
+
+impl<I> Iterator for InProgression<I>
+where
+    I: TryFrom<u64>,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(p) = self.single.take() {
+            return I::try_from(p).ok();
+        }
+
+        loop {
+            if let Some(p) = self.pop() {
+                let p = I::try_from(p).ok();
+
+                if p.is_none() {
+                    self.next = None;
+                }
+
+                return p;
+            }
+
+            let lo = self.next?;
+            let hi = lo.saturating_add(self.segment_len - 1).min(self.last);
+            self.sieve(lo, hi);
+            self.next = hi.checked_add(1).filter(|&k| k <= self.last);
+        }
+    }
+}
+
+/// Compute the greatest common divisor of `a` and `b`.
+pub(crate) fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+
+    a
+}
+
+/// Compute the inverse of `a` modulo the prime `p`, where `0 < a < p`.
+fn inverse(a: u64, p: u64) -> u64 {
+    let (mut r0, mut r1) = (p as i64, a as i64);
+    let (mut t0, mut t1) = (0i64, 1i64);
+
+    while r1 != 0 {
+        let q = r0 / r1;
+        let r = r0 - q * r1;
+        r0 = r1;
+        r1 = r;
+        let t = t0 - q * t1;
+        t0 = t1;
+        t1 = t;
+    }
+
+    t0.rem_euclid(p as i64) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{in_progression, InProgression, InvalidProgression};
+
+    #[test]
+    fn matches_bounded() {
+        let primes = crate::bounded(100_000u64).collect::<Vec<_>>();
+
+        for &(a, m) in &[
+            (1, 1),
+            (0, 1),
+            (1, 2),
+            (1, 4),
+            (3, 4),
+            (1, 8),
+            (3, 8),
+            (5, 8),
+            (7, 8),
+            (2, 3),
+            (1, 6),
+            (5, 6),
+            (7, 30),
+            (29, 30),
+            (1, 210),
+            (10, 101),
+            (1000, 99_991),
+            (13, 100_003),
+        ] {
+            let expected = primes
+                .iter()
+                .copied()
+                .filter(|&p| p % m == a % m)
+                .collect::<Vec<_>>();
+
+            for &segment_len in &[1, 7, 64, 1000, super::SEGMENT_LEN] {
+                let actual = InProgression::<u64>::new(a, m, segment_len)
+                    .take_while(|&p| p <= 100_000)
+                    .collect::<Vec<_>>();
+
+                assert_eq!(actual, expected, "{} {} {}", a, m, segment_len);
+            }
+        }
+    }
+
+    #[test]
+    fn not_coprime() {
+        assert!(in_progression(2u32, 4).unwrap().eq([2]));
+        assert!(in_progression(0u32, 7).unwrap().eq([7]));
+        assert!(in_progression(10u32, 5).unwrap().eq([5]));
+        assert!(in_progression(3u32, 12).unwrap().eq([3]));
+        assert!(in_progression(2u32, 2).unwrap().eq([2]));
+
+        let err = |a: u32, m: u32| in_progression(a, m).err();
+        assert_eq!(err(4, 6), Some(InvalidProgression::NotCoprime));
+        assert_eq!(err(0, 4), Some(InvalidProgression::NotCoprime));
+        assert_eq!(err(6, 9), Some(InvalidProgression::NotCoprime));
+        assert_eq!(err(1, 0), Some(InvalidProgression::ZeroModulus));
+    }
+
+    #[test]
+    fn type_bounds() {
+        // Stops at the end of the type.
+        let expected = crate::range(0, u32::MAX as u64)
+            .filter(|&p| p % 1_000_000 == 1)
+            .collect::<Vec<_>>();
+
+        let actual = in_progression(1u32, 1_000_000)
+            .unwrap()
+            .map(u64::from)
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, expected);
+    }
+}
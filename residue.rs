@@ -0,0 +1,388 @@
+//! Quadratic residues, and the Legendre and Jacobi symbols.
+
+use crate::flat::isqrt;
+use crate::primality::pow_mod;
+
+/// Compute the Legendre symbol `(a/p)` for an odd prime `p`.
+///
+/// This is 1 if `a` is a nonzero square modulo `p`, -1 if it isn't a square,
+/// and 0 if `p` divides `a`. It's computed with Euler's criterion, since
+/// `a^((p - 1) / 2) mod p` is exactly one of 1, -1 and 0, using
+/// exponentiation by squaring.
+///
+/// # Panics
+///
+/// Panics if `p` isn't an odd prime according to
+/// [`is_prime_u64()`][crate::is_prime_u64()].
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::legendre_symbol(2, 7), 1);
+/// assert_eq!(sieve::legendre_symbol(3, 7), -1);
+/// assert_eq!(sieve::legendre_symbol(14, 7), 0);
+/// assert_eq!(sieve::legendre_symbol(-1, 13), 1);
+/// assert_eq!(sieve::legendre_symbol(-1, 1_000_000_007), -1);
+/// ```
+pub fn legendre_symbol(a: i64, p: u64) -> i8 {
+    if p == 2 || !crate::is_prime_u64(p) {
+        panic!("modulus {} is not an odd prime", p);
+    }
+
+    match pow_mod(residue(a, p), (p - 1) / 2, p) {
+        0 => 0,
+        1 => 1,
+        _ => -1,
+    }
+}
+
+/// Test if `a` is a quadratic residue modulo the odd prime `p`, which is when
+/// it's congruent to a nonzero square.
+///
+/// This is when the [`legendre_symbol()`] is 1, so multiples of `p` don't
+/// count even though they're congruent to `0^2`.
+///
+/// # Panics
+///
+/// Panics if `p` isn't an odd prime according to
+/// [`is_prime_u64()`][crate::is_prime_u64()].
+///
+/// # Examples
+///
+/// ```
+/// let residues = (1..11).filter(|&a| sieve::is_quadratic_residue(a, 11));
+/// assert!(residues.eq([1, 3, 4, 5, 9]));
+///
+/// assert!(!sieve::is_quadratic_residue(0, 11));
+/// assert!(sieve::is_quadratic_residue(-2, 11));
+/// ```
+pub fn is_quadratic_residue(a: i64, p: u64) -> bool {
+    legendre_symbol(a, p) == 1
+}
+
+/// Compute the Jacobi symbol `(a/n)` for an odd positive `n`.
+///
+/// This extends the [`legendre_symbol()`] to composite moduli as the product
+/// of the Legendre symbols over the prime factors of `n`, and is computed
+/// without factoring `n` using quadratic reciprocity. Unlike the Legendre
+/// symbol, `(a/n) = 1` doesn't mean that `a` is a square modulo `n`, but
+/// `(a/n) = -1` does mean that it isn't.
+///
+/// # Panics
+///
+/// Panics if `n` is even.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::jacobi(2, 15), 1);
+/// assert_eq!(sieve::jacobi(7, 15), -1);
+/// assert_eq!(sieve::jacobi(6, 15), 0);
+/// assert_eq!(sieve::jacobi(-5, 1), 1);
+///
+/// // Agrees with the Legendre symbol for primes.
+/// assert_eq!(sieve::jacobi(3, 7), sieve::legendre_symbol(3, 7));
+/// ```
+pub fn jacobi(a: i64, n: u64) -> i8 {
+    if n % 2 == 0 {
+        panic!("modulus {} is not odd", n);
+    }
+
+    jacobi_u128(u128::from(residue(a, n)), u128::from(n))
+}
+
+/// Compute the Jacobi symbol `(a/n)` for an odd `n`.
+pub(crate) fn jacobi_u128(mut a: u128, mut n: u128) -> i8 {
+    let mut t = 1;
+    a %= n;
+
+    while a != 0 {
+        while a % 2 == 0 {
+            a /= 2;
+
+            if n % 8 == 3 || n % 8 == 5 {
+                t = -t;
+            }
+        }
+
+        core::mem::swap(&mut a, &mut n);
+
+        if a % 4 == 3 && n % 4 == 3 {
+            t = -t;
+        }
+
+        a %= n;
+    }
+
+    if n == 1 {
+        t
+    } else {
+        0
+    }
+}
+
+/// Find the smallest primitive root modulo the prime `p`, which is a `g`
+/// whose powers run through every nonzero residue modulo `p`.
+///
+/// The multiplicative group modulo `p` has order `p - 1`, so `g` generates it
+/// exactly when `g^((p - 1) / q) != 1 (mod p)` for each prime `q` dividing
+/// `p - 1`. The prime factors are found with
+/// [`factorize_u64()`][crate::factorize_u64()], after which candidates are
+/// tried in increasing order. The smallest primitive root is small in
+/// practice, so only a handful of them are tried.
+///
+/// Returns `None` if `p` isn't prime according to
+/// [`is_prime_u64()`][crate::is_prime_u64()]. Some composite moduli have
+/// primitive roots as well, but they aren't searched for.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::primitive_root(7), Some(3));
+/// assert_eq!(sieve::primitive_root(761), Some(6));
+/// assert_eq!(sieve::primitive_root(998_244_353), Some(3));
+/// assert_eq!(sieve::primitive_root(2), Some(1));
+/// assert_eq!(sieve::primitive_root(9), None);
+/// ```
+pub fn primitive_root(p: u64) -> Option<u64> {
+    if !crate::is_prime_u64(p) {
+        return None;
+    }
+
+    if p == 2 {
+        return Some(1);
+    }
+
+    let factors = crate::factorize_u64(p - 1);
+
+    (2..p).find(|&g| {
+        factors
+            .iter()
+            .all(|&(q, _)| pow_mod(g, (p - 1) / q, p) != 1)
+    })
+}
+
+/// Write the prime `p` as a sum of two squares `a^2 + b^2` with `a <= b`.
+///
+/// By Fermat's theorem on sums of two squares, an odd prime is such a sum
+/// exactly when it's congruent to 1 modulo 4, in which case the decomposition
+/// is unique. It's found by taking a square root `x` of -1 modulo `p`, which
+/// is `c^((p - 1) / 4)` for any quadratic non-residue `c`, and running the
+/// Euclidean algorithm on `p` and `x` until the remainder is below `sqrt(p)`.
+/// That remainder is one of the roots, and the other follows from it.
+///
+/// Returns `None` for primes congruent to 3 modulo 4, and for any `p` which
+/// isn't prime according to [`is_prime_u64()`][crate::is_prime_u64()].
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sieve::two_squares(5), Some((1, 2)));
+/// assert_eq!(sieve::two_squares(13), Some((2, 3)));
+/// assert_eq!(sieve::two_squares(2), Some((1, 1)));
+/// assert_eq!(sieve::two_squares(7), None);
+/// assert_eq!(sieve::two_squares(25), None);
+/// ```
+pub fn two_squares(p: u64) -> Option<(u64, u64)> {
+    if p != 2 && (p % 4 != 1 || !crate::is_prime_u64(p)) {
+        return None;
+    }
+
+    Some(decompose(p))
+}
+
+/// Write `p`, which is 2 or a prime congruent to 1 modulo 4, as a sum of two
+/// squares.
+pub(crate) fn decompose(p: u64) -> (u64, u64) {
+    if p == 2 {
+        return (1, 1);
+    }
+
+    // Half of the residues are non-residues, so one is found quickly.
+    let c = (2..p)
+        .find(|&c| pow_mod(c, (p - 1) / 2, p) == p - 1)
+        .unwrap_or(2);
+
+    let root = isqrt(p);
+    let (mut r0, mut r1) = (p, pow_mod(c, (p - 1) / 4, p));
+
+    while r1 > root {
+        let r = r0 % r1;
+        r0 = r1;
+        r1 = r;
+    }
+
+    let other = isqrt(p - r1 * r1);
+    (r1.min(other), r1.max(other))
+}
+
+/// Reduce `a` modulo `m` into `0..m`.
+fn residue(a: i64, m: u64) -> u64 {
+    i128::from(a).rem_euclid(i128::from(m)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::{is_quadratic_residue, jacobi, legendre_symbol, primitive_root, two_squares};
+
+    #[test]
+    fn residue_tables() {
+        for p in crate::bounded(500u64).skip(1) {
+            let mut squares = vec![false; p as usize];
+
+            for x in 1..p {
+                squares[(x * x % p) as usize] = true;
+            }
+
+            for a in -(p as i64)..2 * p as i64 {
+                let r = a.rem_euclid(p as i64) as usize;
+                let expected = if r == 0 {
+                    0
+                } else if squares[r] {
+                    1
+                } else {
+                    -1
+                };
+
+                assert_eq!(legendre_symbol(a, p), expected, "{} {}", a, p);
+                assert_eq!(is_quadratic_residue(a, p), expected == 1, "{} {}", a, p);
+                assert_eq!(jacobi(a, p), expected, "{} {}", a, p);
+            }
+        }
+    }
+
+    #[test]
+    fn multiplicative() {
+        for p in crate::bounded(200u64).skip(1) {
+            for a in -50..50 {
+                for b in -50..50 {
+                    let ab = legendre_symbol(a, p) * legendre_symbol(b, p);
+                    assert_eq!(legendre_symbol(a * b, p), ab, "{} {} {}", a, b, p);
+                }
+            }
+        }
+
+        // The Jacobi symbol is the product of the Legendre symbols over the
+        // factorization of the modulus.
+        for n in (1..2000u64).step_by(2) {
+            for a in -100..100 {
+                let expected = crate::factorize(n)
+                    .iter()
+                    .map(|&(p, k)| legendre_symbol(a, p).pow(k))
+                    .product::<i8>();
+
+                assert_eq!(jacobi(a, n), expected, "{} {}", a, n);
+            }
+        }
+    }
+
+    #[test]
+    fn reciprocity() {
+        for state in crate::xorshift(0x9e37_79b9_7f4a_7c15).take(1000) {
+            let p = crate::next_prime((state >> 34) | 3).unwrap();
+            let q = crate::next_prime(state as u32 as u64 | 3).unwrap();
+
+            if p == q {
+                continue;
+            }
+
+            let sign = if (p - 1) / 2 * ((q - 1) / 2) % 2 == 0 {
+                1
+            } else {
+                -1
+            };
+            let product = legendre_symbol(p as i64, q) * legendre_symbol(q as i64, p);
+            assert_eq!(product, sign, "{} {}", p, q);
+            assert_eq!(jacobi(p as i64, q), legendre_symbol(p as i64, q));
+        }
+    }
+
+    #[test]
+    fn large_moduli() {
+        let p = 18_446_744_073_709_551_557;
+        assert_eq!(legendre_symbol(-1, p), jacobi(-1, p));
+        assert_eq!(legendre_symbol(i64::MIN, p), jacobi(i64::MIN, p));
+        assert_eq!(legendre_symbol(4, p), 1);
+        assert_eq!(
+            jacobi(i64::MAX, u64::MAX),
+            jacobi(i64::MAX, 3) * jacobi(i64::MAX, u64::MAX / 3)
+        );
+    }
+
+    #[test]
+    fn primitive_roots() {
+        assert_eq!(primitive_root(3), Some(2));
+        assert_eq!(primitive_root(7), Some(3));
+        assert_eq!(primitive_root(23), Some(5));
+        assert_eq!(primitive_root(761), Some(6));
+        assert_eq!(primitive_root(1_000_000_007), Some(5));
+        assert_eq!(primitive_root(4_294_967_291), Some(2));
+        assert_eq!(primitive_root((1 << 61) - 1), Some(37));
+        assert_eq!(primitive_root(18_446_744_073_709_551_557), Some(2));
+
+        for &n in &[0, 1, 4, 9, 561, u64::MAX] {
+            assert_eq!(primitive_root(n), None, "{}", n);
+        }
+
+        /// Get the multiplicative order of `g` modulo `p`.
+        fn order(g: u64, p: u64) -> u64 {
+            let mut x = g % p;
+            let mut order = 1;
+
+            while x != 1 {
+                x = x * g % p;
+                order += 1;
+            }
+
+            order
+        }
+
+        for p in crate::bounded(10_000u64) {
+            let g = primitive_root(p).unwrap();
+            assert_eq!(order(g, p), p - 1, "{}", p);
+            assert!((1..g).all(|h| order(h, p) < p - 1), "{}", p);
+        }
+    }
+
+    #[test]
+    fn sums_of_two_squares() {
+        assert_eq!(two_squares(5), Some((1, 2)));
+        assert_eq!(two_squares(13), Some((2, 3)));
+        assert_eq!(two_squares(1_000_033), Some((408, 913)));
+
+        for &n in &[0, 1, 3, 4, 9, 25, 65, 1_000_003, u64::MAX] {
+            assert_eq!(two_squares(n), None, "{}", n);
+        }
+
+        for p in crate::bounded(100_000u64) {
+            match two_squares(p) {
+                Some((a, b)) => {
+                    assert!(a <= b, "{}", p);
+                    assert_eq!(a * a + b * b, p);
+                }
+                None => assert_eq!(p % 4, 3, "{}", p),
+            }
+        }
+
+        let p = 18_446_744_073_709_551_557;
+        let (a, b) = two_squares(p).unwrap();
+        assert_eq!(
+            u128::from(a) * u128::from(a) + u128::from(b) * u128::from(b),
+            u128::from(p)
+        );
+    }
+
+    #[test]
+    #[should_panic = "modulus 15 is not an odd prime"]
+    fn composite_modulus() {
+        legendre_symbol(2, 15);
+    }
+
+    #[test]
+    #[should_panic = "modulus 10 is not odd"]
+    fn even_modulus() {
+        jacobi(1, 10);
+    }
+}
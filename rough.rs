@@ -0,0 +1,203 @@
+//! Rough numbers, whose prime factors are all large.
+
+use alloc::vec::Vec;
+use core::marker;
+
+use crate::flat::{isqrt, Window};
+use crate::segmented::SEGMENT_LEN;
+use crate::small;
+use crate::SieveInt;
+
+/// An iterator over the rough numbers up to a bound.
+///
+/// See [`rough_numbers()`].
+pub struct RoughNumbers<I> {
+    /// The primes up to the bound whose multiples are cleared, which only go
+    /// as far as the square root of the upper bound.
+    primes: Vec<u64>,
+    upper: u64,
+    /// The rough numbers left in the current segment.
+    window: Window,
+    /// The first number of the next segment, or `None` if all segments have
+    /// been sieved.
+    next: Option<u64>,
+    segment_len: u64,
+    _marker: marker::PhantomData<I>,
+}
+
+impl<I> RoughNumbers<I> {
+    /// Construct a sieve over the numbers in `2..=upper` with no prime factors
+    /// up to `bound`.
+    fn new(bound: u64, upper: u64, segment_len: u64) -> Self {
+        let mut primes = Vec::new();
+        let limit = bound.min(isqrt(upper));
+
+        if limit >= 2 {
+            primes.push(2);
+            primes.extend(small::odd_primes(limit));
+        }
+
+        // Every number from 2 up to the bound has a prime factor no larger
+        // than itself, so sieving starts right after it.
+        let next = if bound < upper {
+            Some(bound.max(1) + 1)
+        } else {
+            None
+        };
+
+        Self {
+            primes,
+            upper,
+            window: Window::new(),
+            next,
+            segment_len,
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Sieve the numbers in `lo..=hi`.
+    fn sieve(&mut self, lo: u64, hi: u64) {
+        self.window.reset(lo, hi);
+
+        // A number without a prime factor up to its square root is a prime,
+        // and is past the bound since sieving starts after it.
+        for &p in &self.primes {
+            if p > hi / p {
+                break;
+            }
+
+            let offset = (p - lo % p) % p;
+            self.window.clear_multiples(offset as usize, p as usize);
+        }
+    }
+
+    /// Remove and return the smallest rough number remaining in the segment.
+    #[inline]
+    fn pop(&mut self) -> Option<u64> {
+        self.window.pop()
+    }
+}
+
+/// Construct an iterator over the `bound`-rough numbers in `2..=upper` in
+/// increasing order, which are the numbers with no prime factors up to and
+/// including `bound`.
+///
+/// The range is sieved in segments like [`segmented()`][crate::segmented()],
+/// clearing the multiples of each prime up to `bound`. Since a number without
+/// a prime factor up to its square root is prime, only the primes up to
+/// `sqrt(upper)` are needed however large `bound` is, and once it's past
+/// that the rough numbers are the primes above it.
+///
+/// 1 has no prime factors at all, but it isn't produced.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// let rough = sieve::rough_numbers(5u32, 50);
+/// assert!(rough.eq([7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 49]));
+///
+/// assert!(sieve::rough_numbers(0u32, 5).eq([2, 3, 4, 5]));
+/// assert!(sieve::rough_numbers(10u32, 100).eq(sieve::range(11u32, 100)));
+/// ```
+pub fn rough_numbers<I>(bound: I, upper: I) -> RoughNumbers<I>
+where
+    I: SieveInt,
+{
+    if upper < I::from_small(2) {
+        return RoughNumbers::new(0, 0, SEGMENT_LEN);
+    }
+
+    let upper = match upper.to_u64() {
+        Some(upper) => upper,
+        None => panic!("bound doesn't fit in a u64"),
+    };
+
+    let bound = match bound.to_u64() {
+        Some(bound) => bound,
+        None if bound < I::from_small(0) => 0,
+        None => u64::MAX,
+    };
+
+    RoughNumbers::new(bound, upper, SEGMENT_LEN)
+}
+
+impl<I> Iterator for RoughNumbers<I>
+where
+    I: SieveInt,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(n) = self.pop() {
+                return I::from_u64(n);
+            }
+
+            let lo = self.next?;
+            let hi = lo.saturating_add(self.segment_len - 1).min(self.upper);
+            self.sieve(lo, hi);
+            self.next = hi.checked_add(1).filter(|&k| k <= self.upper);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{rough_numbers, RoughNumbers};
+
+    /// Test if `n` has no prime factors up to `bound` by trial division.
+    fn is_rough(n: u64, bound: u64) -> bool {
+        n >= 2 && (2..=bound.min(n)).all(|d| n % d != 0)
+    }
+
+    #[test]
+    fn first_terms() {
+        let expected = [
+            7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 49, 53, 59, 61, 67, 71, 73, 77, 79, 83,
+            89, 91, 97,
+        ];
+
+        assert!(rough_numbers(5u32, 100).eq(expected));
+        assert!(rough_numbers(6u32, u32::MAX)
+            .take(expected.len())
+            .eq(expected));
+    }
+
+    #[test]
+    fn matches_filter() {
+        for &bound in &[0, 1, 2, 3, 5, 7, 30, 100, 316, 317, 1000, 99_999, 100_000] {
+            let expected = (0..=100_000u64)
+                .filter(|&n| is_rough(n, bound))
+                .collect::<Vec<_>>();
+
+            for &segment_len in &[1, 7, 64, 1000, super::SEGMENT_LEN] {
+                let actual = RoughNumbers::<u64>::new(bound, 100_000, segment_len);
+                assert!(
+                    actual.eq(expected.iter().copied()),
+                    "{} {}",
+                    bound,
+                    segment_len
+                );
+            }
+        }
+
+        assert_eq!(rough_numbers(5u32, 100_000).count(), 26_665);
+    }
+
+    #[test]
+    fn end_of_type() {
+        assert!(rough_numbers(15u8, u8::MAX).eq(crate::bounded(u8::MAX).skip(6)));
+        assert!(rough_numbers(-1i8, i8::MAX).eq(2..=i8::MAX));
+        assert_eq!(rough_numbers(2i32, -5).next(), None);
+        assert_eq!(rough_numbers(u32::MAX, u32::MAX).next(), None);
+
+        let lo = u32::MAX - 1000;
+        assert!(rough_numbers(lo, u32::MAX).eq(crate::range(lo + 1, u32::MAX)));
+    }
+}
@@ -0,0 +1,403 @@
+//! A segmented sieve of Eratosthenes, which sieves fixed-size blocks at a
+//! time to keep memory usage bounded.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::marker;
+
+use crate::flat::{isqrt, Segment};
+use crate::small;
+use crate::FromSmall;
+
+/// Number of odd numbers sieved per segment, which corresponds to 256 KiB of
+/// flags.
+pub(crate) const SEGMENT_LEN: u64 = 256 * 1024 * 8;
+
+/// A segmented prime sieve.
+///
+/// See [`segmented()`] and [`range()`].
+pub struct Segmented<I> {
+    /// Odd base primes up to the square root of the upper bound.
+    primes: Vec<u64>,
+    segment: Segment,
+    /// The next odd number to sieve, or `None` if all segments have been
+    /// sieved.
+    next: Option<u64>,
+    upper: u64,
+    segment_len: u64,
+    two: bool,
+    _marker: marker::PhantomData<I>,
+}
+
+impl<I> Segmented<I> {
+    /// Construct a segmented sieve over `lo..=hi`.
+    fn new(lo: u64, hi: u64, segment_len: u64) -> Self {
+        let primes = small::odd_primes(isqrt(hi));
+
+        let two = lo <= 2 && hi >= 2;
+        let lo = lo.max(3) | 1;
+
+        Self {
+            primes,
+            segment: Segment::new(),
+            next: if lo <= hi { Some(lo) } else { None },
+            upper: hi,
+            segment_len,
+            two,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+/// Construct a bounded sieve which yields the same primes as
+/// [`bounded()`][crate::bounded()], but sieves fixed-size segments of 256 KiB
+/// at a time.
+///
+/// Only the primes up to `sqrt(upper)` are kept around, so memory usage stays
+/// roughly constant regardless of the bound.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// let iter = sieve::segmented::<u32>(100).skip(10);
+///
+/// assert!(
+///     iter.eq([31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97])
+/// );
+///
+/// assert_eq!(sieve::segmented(10_000_000u64).count(), 664_579);
+/// ```
+pub fn segmented<I>(upper: I) -> Segmented<I>
+where
+    I: Copy + Ord + FromSmall,
+    u64: TryFrom<I>,
+{
+    if upper < I::from_small(2) {
+        return Segmented::new(1, 0, SEGMENT_LEN);
+    }
+
+    let upper = match u64::try_from(upper) {
+        Ok(upper) => upper,
+        Err(..) => panic!("bound doesn't fit in a u64"),
+    };
+
+    Segmented::new(2, upper, SEGMENT_LEN)
+}
+
+/// Construct a sieve over the primes in `lo..=hi`.
+///
+/// Only the window itself is sieved, using the primes up to `sqrt(hi)`, so
+/// this is efficient even for large values of `lo`. The window is processed in
+/// segments like [`segmented()`].
+///
+/// If `lo > hi` the returned iterator is empty.
+///
+/// # Panics
+///
+/// Panics if `hi` doesn't fit in a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// assert!(sieve::range(10u32, 30).eq([11, 13, 17, 19, 23, 29]));
+/// assert!(sieve::range(0u32, 5).eq([2, 3, 5]));
+///
+/// let primes = sieve::range(1_000_000_000_000u64, 1_000_000_000_100);
+///
+/// assert!(primes.eq([
+///     1_000_000_000_039,
+///     1_000_000_000_061,
+///     1_000_000_000_063,
+///     1_000_000_000_091,
+/// ]));
+/// ```
+pub fn range<I>(lo: I, hi: I) -> Segmented<I>
+where
+    I: Copy + Ord + FromSmall,
+    u64: TryFrom<I>,
+{
+    let two = I::from_small(2);
+
+    if hi < two || lo > hi {
+        return Segmented::new(1, 0, SEGMENT_LEN);
+    }
+
+    let (lo, hi) = match (u64::try_from(lo.max(two)), u64::try_from(hi)) {
+        (Ok(lo), Ok(hi)) => (lo, hi),
+        _ => panic!("bound doesn't fit in a u64"),
+    };
+
+    Segmented::new(lo, hi, SEGMENT_LEN)
+}
+
+impl<I> Iterator for Segmented<I>
+where
+    I: TryFrom<u64>,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.two {
+            self.two = false;
+            return I::try_from(2).ok();
+        }
+
+        loop {
+            if let Some(p) = self.segment.pop() {
+                return I::try_from(p).ok();
+            }
+
+            let lo = self.next?;
+
+            self.sieve(lo);
+        }
+    }
+
+    /// Count the remaining primes by counting the bits of each segment,
+    /// without producing any of them.
+    fn count(mut self) -> usize {
+        let mut count = usize::from(self.two) + self.segment.count();
+
+        while let Some(lo) = self.next {
+            self.sieve(lo);
+            count += self.segment.count();
+        }
+
+        count
+    }
+}
+
+impl<I> Segmented<I> {
+    /// Sieve the segment starting at `lo`.
+    fn sieve(&mut self, lo: u64) {
+        let hi = lo
+            .saturating_add(2 * (self.segment_len - 1))
+            .min(self.upper);
+
+        self.segment.sieve(lo, hi, &self.primes);
+        self.next = hi
+            .checked_add(1)
+            .map(|n| n | 1)
+            .filter(|&n| n <= self.upper);
+    }
+}
+
+/// An unbounded segmented sieve which starts from a lower bound.
+///
+/// See [`starting_at()`].
+pub struct StartingAt<I> {
+    /// Odd base primes up to `horizon`.
+    primes: Vec<u64>,
+    horizon: u64,
+    segment: Segment,
+    /// The next odd number to sieve, or `None` once the end of `u64` has been
+    /// reached.
+    next: Option<u64>,
+    segment_len: u64,
+    two: bool,
+    _marker: marker::PhantomData<I>,
+}
+
+impl<I> StartingAt<I> {
+    /// Construct a sieve over `lo..`.
+    fn new(lo: u64, segment_len: u64) -> Self {
+        Self {
+            primes: Vec::new(),
+            horizon: 0,
+            segment: Segment::new(),
+            next: Some(lo.max(3) | 1),
+            segment_len,
+            two: lo <= 2,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+/// Construct an unbounded sieve over the primes which are at least `lo`.
+///
+/// This sieves windows of 256 KiB at a time like [`segmented()`], sliding
+/// them upwards forever. The base primes needed to sieve them are only
+/// computed up to the square root of the current window, growing as it moves
+/// up, so starting far from 2 is cheap. It stops once it reaches the end of
+/// `u64`, or when a prime no longer fits in `I`.
+///
+/// # Panics
+///
+/// Panics if `lo` doesn't fit in a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// let primes = sieve::starting_at(1_000_000_000u64).take(3);
+/// assert!(primes.eq([1_000_000_007, 1_000_000_009, 1_000_000_021]));
+///
+/// assert!(sieve::starting_at(0u32).take(3).eq([2, 3, 5]));
+/// assert!(sieve::starting_at(7u32).take(3).eq([7, 11, 13]));
+/// ```
+pub fn starting_at<I>(lo: I) -> StartingAt<I>
+where
+    I: Copy + Ord + FromSmall,
+    u64: TryFrom<I>,
+{
+    let lo = lo.max(I::from_small(2));
+
+    let lo = match u64::try_from(lo) {
+        Ok(lo) => lo,
+        Err(..) => panic!("bound doesn't fit in a u64"),
+    };
+
+    StartingAt::new(lo, SEGMENT_LEN)
+}
+
+impl<I> Iterator for StartingAt<I>
+where
+    I: TryFrom<u64>,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.two {
+            self.two = false;
+            return I::try_from(2).ok();
+        }
+
+        loop {
+            if let Some(p) = self.segment.pop() {
+                let p = I::try_from(p).ok();
+
+                if p.is_none() {
+                    self.next = None;
+                }
+
+                return p;
+            }
+
+            let lo = self.next?;
+            let hi = lo.saturating_add(2 * (self.segment_len - 1));
+            let root = isqrt(hi);
+
+            if self.horizon < root {
+                // Grow geometrically so that the base primes are re-sieved
+                // only a logarithmic number of times.
+                self.horizon = root
+                    .max(self.horizon.saturating_mul(2))
+                    .min(u32::MAX as u64);
+                self.primes = small::odd_primes(self.horizon);
+            }
+
+            self.segment.sieve(lo, hi, &self.primes);
+            self.next = hi.checked_add(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{Segmented, StartingAt};
+
+    #[test]
+    fn matches_bounded() {
+        for upper in 0..=2000u64 {
+            let expected = crate::bounded(upper);
+            assert!(
+                Segmented::<u64>::new(2, upper, 64).eq(expected),
+                "{}",
+                upper
+            );
+        }
+    }
+
+    #[test]
+    fn segment_boundaries() {
+        let expected = crate::bounded(100_000u64).collect::<Vec<_>>();
+
+        for &segment_len in &[1, 2, 3, 63, 64, 65, 1000] {
+            let actual = Segmented::<u64>::new(2, 100_000, segment_len).collect::<Vec<_>>();
+            assert_eq!(actual, expected, "{}", segment_len);
+        }
+    }
+
+    #[test]
+    fn count() {
+        for &segment_len in &[1, 64, 1000] {
+            for upper in (0..=5000u64).step_by(7) {
+                let expected = crate::bounded(upper).count();
+                let sieve = Segmented::<u64>::new(2, upper, segment_len);
+                assert_eq!(sieve.count(), expected, "{} {}", upper, segment_len);
+            }
+        }
+
+        // Primes which have already been produced aren't counted.
+        let mut sieve = Segmented::<u64>::new(2, 100_000, 1000);
+        assert_eq!(sieve.by_ref().take(1000).count(), 1000);
+        assert_eq!(sieve.count(), 9592 - 1000);
+    }
+
+    #[test]
+    fn prime_bounds() {
+        for &upper in &[2u64, 3, 5, 7, 8191, 65_521, 65_537, 131_071] {
+            let last = super::segmented(upper).last();
+            assert_eq!(last, Some(upper));
+        }
+    }
+
+    #[test]
+    fn range_matches_bounded() {
+        let primes = crate::bounded(1000u64).collect::<Vec<_>>();
+
+        for lo in 0..=200u64 {
+            for hi in lo.saturating_sub(2)..=lo + 100 {
+                let expected = primes.iter().copied().filter(|&p| lo <= p && p <= hi);
+                assert!(super::range(lo, hi).eq(expected), "{}..={}", lo, hi);
+            }
+        }
+    }
+
+    #[test]
+    fn range_edges() {
+        assert!(super::range(7u32, 7).eq([7]));
+        assert!(super::range(8u32, 8).eq([]));
+        assert!(super::range(10u32, 2).eq([]));
+        assert!(super::range(-10i64, 3).eq([2, 3]));
+        assert!(super::range(-10i64, -2).eq([]));
+    }
+
+    #[test]
+    fn starting_at_matches_infinite() {
+        let primes = crate::bounded(20_000u64).collect::<Vec<_>>();
+
+        for lo in (0..=2000u64).chain([9973, 10_000]) {
+            let expected = primes.iter().copied().filter(|&p| p >= lo).take(200);
+
+            // Small segments leave many windows without any primes.
+            for &segment_len in &[1, 4, 64, 1000] {
+                let actual = StartingAt::<u64>::new(lo.max(2), segment_len).take(200);
+                assert!(actual.eq(expected.clone()), "{} {}", lo, segment_len);
+            }
+        }
+
+        let expected = crate::infinite::<u64>().skip_while(|&p| p < 1_000_000);
+        assert!(super::starting_at(1_000_000u64)
+            .take(10_000)
+            .eq(expected.take(10_000)));
+    }
+
+    #[test]
+    fn starting_at_edges() {
+        assert!(super::starting_at(-10i64).take(3).eq([2, 3, 5]));
+        assert!(super::starting_at(2u32).take(3).eq([2, 3, 5]));
+        assert!(super::starting_at(3u32).take(3).eq([3, 5, 7]));
+
+        // Stops at the end of the type.
+        let expected = super::range(4_294_967_000u64, u32::MAX as u64);
+        assert!(super::starting_at(4_294_967_000u32)
+            .map(u64::from)
+            .eq(expected));
+    }
+}
@@ -0,0 +1,238 @@
+//! A set of primes which can be queried without sieving.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::flat::Flat;
+use crate::FromSmall;
+
+/// The primes up to and including some bound, which can be queried in
+/// constant or logarithmic time.
+///
+/// The primes are stored both as a sorted vector for indexing, and as a bit
+/// set over the odd numbers for testing membership in constant time.
+///
+/// Numbers beyond the bound of the set are never assumed to be composite:
+/// queries about them either return an answer which says that it's unknown,
+/// or panic.
+///
+/// See [`PrimeSet::below`].
+#[derive(Clone)]
+pub struct PrimeSet<I> {
+    flat: Flat,
+    primes: Vec<I>,
+    upper: usize,
+}
+
+impl<I> PrimeSet<I>
+where
+    I: Copy + Ord + FromSmall + TryFrom<u64>,
+    usize: TryFrom<I>,
+{
+    /// Construct a set of every prime up to and including `upper`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `upper` doesn't fit in a `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let set = sieve::PrimeSet::below(100u32);
+    ///
+    /// assert_eq!(set.len(), 25);
+    /// assert_eq!(set.contains(97), Some(true));
+    /// assert_eq!(set.contains(91), Some(false));
+    /// assert_eq!(set.contains(101), None);
+    /// ```
+    pub fn below(upper: I) -> Self {
+        let upper = if upper < I::from_small(2) {
+            0
+        } else {
+            match usize::try_from(upper) {
+                Ok(upper) => upper,
+                Err(..) => panic!("bound doesn't fit in a usize"),
+            }
+        };
+
+        let flat = Flat::new(upper);
+        let odd = flat.odd_primes();
+        let mut primes = Vec::with_capacity(usize::from(upper >= 2) + odd.len());
+
+        if upper >= 2 {
+            primes.extend(I::try_from(2).ok());
+        }
+
+        primes.extend(odd.into_iter().filter_map(|p| I::try_from(p).ok()));
+
+        Self {
+            flat,
+            primes,
+            upper,
+        }
+    }
+
+    /// Test if `n` is prime.
+    ///
+    /// Returns `None` if `n` is beyond the bound of the set, since it's not
+    /// known whether it's prime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let set = sieve::PrimeSet::below(10u32);
+    ///
+    /// assert_eq!(set.contains(0), Some(false));
+    /// assert_eq!(set.contains(7), Some(true));
+    /// assert_eq!(set.contains(10), Some(false));
+    /// assert_eq!(set.contains(11), None);
+    /// ```
+    pub fn contains(&self, n: I) -> Option<bool> {
+        if n < I::from_small(2) {
+            return Some(false);
+        }
+
+        match usize::try_from(n) {
+            Ok(n) if n <= self.upper => Some(self.flat.contains(n)),
+            _ => None,
+        }
+    }
+
+    /// Get the `n`th prime in the set, counting from zero.
+    ///
+    /// Returns `None` if the set has `n` primes or fewer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let set = sieve::PrimeSet::below(100u32);
+    ///
+    /// assert_eq!(set.nth(0), Some(2));
+    /// assert_eq!(set.nth(24), Some(97));
+    /// assert_eq!(set.nth(25), None);
+    /// ```
+    #[inline]
+    pub fn nth(&self, n: usize) -> Option<I> {
+        self.primes.get(n).copied()
+    }
+
+    /// Get the index of the prime `p` in the set, counting from zero, or
+    /// `None` if `p` isn't prime.
+    ///
+    /// This is the inverse of [`PrimeSet::nth`]. Like [`PrimeSet::contains`],
+    /// it returns `None` if `p` is beyond the bound of the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let set = sieve::PrimeSet::below(100u32);
+    ///
+    /// assert_eq!(set.index_of(2), Some(0));
+    /// assert_eq!(set.index_of(97), Some(24));
+    /// assert_eq!(set.index_of(91), None);
+    /// assert_eq!(set.index_of(101), None);
+    /// ```
+    pub fn index_of(&self, p: I) -> Option<usize> {
+        match self.contains(p)? {
+            true => self.primes.binary_search(&p).ok(),
+            false => None,
+        }
+    }
+
+    /// Count the primes less than or equal to `x`, also known as `π(x)`.
+    ///
+    /// Returns `None` if `x` is beyond the bound of the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let set = sieve::PrimeSet::below(100u32);
+    ///
+    /// assert_eq!(set.count_below(10), Some(4));
+    /// assert_eq!(set.count_below(100), Some(25));
+    /// assert_eq!(set.count_below(1000), None);
+    /// ```
+    pub fn count_below(&self, x: I) -> Option<usize> {
+        self.contains(x)?;
+        Some(self.primes.partition_point(|&p| p <= x))
+    }
+}
+
+impl<I> PrimeSet<I> {
+    /// Get the number of primes in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.primes.len()
+    }
+
+    /// Test if the set has no primes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.primes.is_empty()
+    }
+
+    /// Get the primes in the set in increasing order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let set = sieve::PrimeSet::below(20u32);
+    /// assert_eq!(set.as_slice(), [2, 3, 5, 7, 11, 13, 17, 19]);
+    /// ```
+    #[inline]
+    pub fn as_slice(&self) -> &[I] {
+        &self.primes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::PrimeSet;
+
+    #[test]
+    fn matches_sieve() {
+        let set = PrimeSet::below(10_000u32);
+        let primes = crate::bounded(10_000u32).collect::<Vec<_>>();
+        assert_eq!(set.as_slice(), &primes[..]);
+
+        let mut sieve = crate::infinite::<u32>();
+
+        for n in 0..=10_000 {
+            assert_eq!(set.contains(n), Some(sieve.is_prime(n)), "{}", n);
+            assert_eq!(set.count_below(n), Some(crate::prime_count(n)), "{}", n);
+        }
+
+        for (i, &p) in primes.iter().enumerate() {
+            assert_eq!(set.nth(i), Some(p));
+            assert_eq!(set.index_of(p), Some(i));
+        }
+
+        assert_eq!(set.nth(primes.len()), None);
+        assert_eq!(set.contains(10_001), None);
+        assert_eq!(set.count_below(10_001), None);
+    }
+
+    #[test]
+    fn index_of_beyond_bound() {
+        let set = PrimeSet::below(100u32);
+        assert_eq!(set.index_of(97), Some(24));
+        assert_eq!(set.index_of(101), None);
+        assert_eq!(set.index_of(u32::MAX), None);
+    }
+
+    #[test]
+    fn small_bounds() {
+        let set = PrimeSet::below(-5i64);
+        assert!(set.is_empty());
+        assert_eq!(set.contains(-7), Some(false));
+        assert_eq!(set.contains(1), Some(false));
+        assert_eq!(set.contains(2), None);
+
+        let set = PrimeSet::below(2u32);
+        assert_eq!(set.as_slice(), [2]);
+        assert_eq!(set.contains(2), Some(true));
+        assert_eq!(set.contains(3), None);
+    }
+}
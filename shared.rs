@@ -0,0 +1,501 @@
+//! A cache of primes which can be shared between threads.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Mutex, Once, PoisonError, RwLock, RwLockReadGuard};
+
+use crate::{Infinite, SieveInt};
+
+/// The fewest primes added to the cache at a time.
+const MIN_CHUNK: usize = 1024;
+
+/// A cache of the primes in increasing order, which is extended on demand
+/// and can be shared between threads.
+///
+/// Cloning the cache is cheap and shares it, so each thread can be handed its
+/// own clone. Whichever thread first needs primes beyond what's been computed
+/// extends the cache for everyone, while the others keep reading the primes
+/// which are already there without waiting for it.
+///
+/// Primes are stored in chunks which are never modified once they've been
+/// added, and which grow with the cache so that there are only a logarithmic
+/// number of them.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+///
+/// let primes = sieve::SharedPrimes::<u64>::new();
+///
+/// let handles = (0..4)
+///     .map(|n| {
+///         let primes = primes.clone();
+///         thread::spawn(move || primes.get(n * 1000))
+///     })
+///     .collect::<Vec<_>>();
+///
+/// let found = handles
+///     .into_iter()
+///     .map(|handle| handle.join().unwrap())
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(found, [2, 7927, 17393, 27457]);
+/// assert!(primes.iter().take(5).eq([2, 3, 5, 7, 11]));
+/// ```
+pub struct SharedPrimes<I> {
+    inner: Arc<Inner<I>>,
+}
+
+struct Inner<I> {
+    /// The chunks of primes computed so far, each with the index of the
+    /// prime following it.
+    chunks: RwLock<Vec<(usize, Arc<[I]>)>>,
+    /// The sieve used to extend the cache, which is only locked by the
+    /// thread extending it.
+    state: Mutex<State<I>>,
+}
+
+struct State<I> {
+    sieve: Infinite<I>,
+    /// The number of primes in the cache.
+    len: usize,
+    /// The first prime which isn't in the cache yet, or `None` if it hasn't
+    /// been taken from the sieve.
+    next: Option<I>,
+}
+
+impl<I> SharedPrimes<I>
+where
+    I: SieveInt,
+    ops::RangeFrom<I>: Iterator<Item = I>,
+{
+    /// Construct an empty cache.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let primes = sieve::SharedPrimes::<u32>::new();
+    /// assert_eq!(primes.get(99), 541);
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                chunks: RwLock::new(Vec::new()),
+                state: Mutex::new(State {
+                    sieve: crate::infinite(),
+                    len: 0,
+                    next: None,
+                }),
+            }),
+        }
+    }
+
+    /// Get the `index`th prime, counting from zero.
+    ///
+    /// The cache is extended if it doesn't have that many primes yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no more than `index` primes which fit in `I`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let primes = sieve::SharedPrimes::<u32>::new();
+    /// assert_eq!(primes.get(0), 2);
+    /// assert_eq!(primes.get(9_999), 104_729);
+    /// ```
+    pub fn get(&self, index: usize) -> I {
+        match self.chunk(index) {
+            Some((start, chunk)) => chunk[index - start],
+            None => panic!("there is no prime at index {}", index),
+        }
+    }
+
+    /// Extend the cache so that it holds every prime up to and including
+    /// `bound`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let primes = sieve::SharedPrimes::<u32>::new();
+    /// primes.extend_to(1_000_000);
+    /// assert_eq!(primes.get(78_497), 999_983);
+    /// ```
+    pub fn extend_to(&self, bound: I) {
+        self.extend(|_, next| next > bound);
+    }
+
+    /// Test if `n` is prime, extending the cache up to `n` if needed.
+    ///
+    /// Since every prime up to `n` ends up in the cache, this is only suited
+    /// to numbers up to the horizon the cache is meant to reach anyway. Use
+    /// [`is_prime_u64()`][crate::is_prime_u64()] for large one-off queries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let primes = sieve::SharedPrimes::<u32>::new();
+    /// assert!(primes.is_prime(7919));
+    /// assert!(!primes.is_prime(7921));
+    /// assert!(!primes.is_prime(1));
+    /// ```
+    pub fn is_prime(&self, n: I) -> bool {
+        self.extend_to(n);
+
+        let chunks = self.read();
+        let index = chunks.partition_point(|(_, chunk)| chunk[chunk.len() - 1] < n);
+
+        match chunks.get(index) {
+            Some((_, chunk)) => chunk.binary_search(&n).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Get the number of primes currently in the cache.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let primes = sieve::SharedPrimes::<u32>::new();
+    /// assert!(primes.is_empty());
+    ///
+    /// primes.extend_to(100);
+    /// assert!(primes.len() >= 25);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.read().last().map_or(0, |&(end, _)| end)
+    }
+
+    /// Test if the cache holds no primes yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let primes = sieve::SharedPrimes::<u32>::new();
+    /// assert!(primes.is_empty());
+    /// assert_eq!(primes.get(0), 2);
+    /// assert!(!primes.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the primes currently in the cache, without extending it.
+    ///
+    /// Primes added while iterating aren't included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let primes = sieve::SharedPrimes::<u32>::new();
+    /// assert_eq!(primes.cached().count(), 0);
+    ///
+    /// primes.extend_to(100);
+    /// assert!(primes.cached().take(25).eq(sieve::bounded(100)));
+    /// assert_eq!(primes.cached().count(), primes.len());
+    /// ```
+    pub fn cached(&self) -> impl Iterator<Item = I> + '_ {
+        let chunks = self
+            .read()
+            .iter()
+            .map(|(_, chunk)| chunk.clone())
+            .collect::<Vec<_>>();
+
+        chunks
+            .into_iter()
+            .flat_map(|chunk| (0..chunk.len()).map(move |index| chunk[index]))
+    }
+
+    /// Iterate over every prime in increasing order, extending the cache as
+    /// needed.
+    ///
+    /// The iterator ends once there are no more primes which fit in `I`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let primes = sieve::SharedPrimes::<u8>::new();
+    /// assert_eq!(primes.iter().count(), 54);
+    /// assert!(primes.iter().skip(10).take(3).eq([31, 37, 41]));
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = I> + '_ {
+        let mut index = 0;
+        let mut current = None::<(usize, Arc<[I]>)>;
+
+        core::iter::from_fn(move || {
+            let stale = match &current {
+                Some((start, chunk)) => index - start >= chunk.len(),
+                None => true,
+            };
+
+            if stale {
+                current = Some(self.chunk(index)?);
+            }
+
+            let (start, chunk) = current.as_ref()?;
+            let p = chunk[index - start];
+            index += 1;
+            Some(p)
+        })
+    }
+
+    /// Get the chunk holding the `index`th prime along with the index of its
+    /// first prime, extending the cache if needed.
+    fn chunk(&self, index: usize) -> Option<(usize, Arc<[I]>)> {
+        loop {
+            if let Some(found) = self.find(index) {
+                return Some(found);
+            }
+
+            if !self.extend(|len, _| len > index) {
+                return None;
+            }
+        }
+    }
+
+    /// Find the chunk holding the `index`th prime, if it's been computed.
+    fn find(&self, index: usize) -> Option<(usize, Arc<[I]>)> {
+        let chunks = self.read();
+        let n = chunks.partition_point(|&(end, _)| end <= index);
+        let (end, chunk) = chunks.get(n)?;
+        Some((end - chunk.len(), chunk.clone()))
+    }
+
+    /// Lock the chunks for reading.
+    ///
+    /// Chunks are complete once they've been added, so they're still usable
+    /// if another thread panicked while holding the lock.
+    fn read(&self) -> RwLockReadGuard<'_, Vec<(usize, Arc<[I]>)>> {
+        self.inner
+            .chunks
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Extend the cache until `done` returns `true` for the number of primes
+    /// in it and the first prime which isn't.
+    ///
+    /// Returns `false` if the cache can't be extended since there are no more
+    /// primes which fit in `I`.
+    fn extend(&self, mut done: impl FnMut(usize, I) -> bool) -> bool {
+        let mut state = self
+            .inner
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Chunks grow with the cache, so that there are only a logarithmic
+        // number of them to search through.
+        let min = MIN_CHUNK.max(state.len);
+        let mut chunk = Vec::new();
+
+        while let Some(p) = state.next.take().or_else(|| state.sieve.next()) {
+            // Another thread might have extended the cache far enough while
+            // this one was waiting for the lock, in which case nothing is
+            // added.
+            if done(state.len + chunk.len(), p) && (chunk.is_empty() || chunk.len() >= min) {
+                state.next = Some(p);
+                break;
+            }
+
+            chunk.push(p);
+        }
+
+        if chunk.is_empty() {
+            return state.next.is_some();
+        }
+
+        state.len += chunk.len();
+
+        self.inner
+            .chunks
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((state.len, Arc::from(chunk)));
+
+        true
+    }
+}
+
+impl<I> Default for SharedPrimes<I>
+where
+    I: SieveInt,
+    ops::RangeFrom<I>: Iterator<Item = I>,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the process-wide cache of primes.
+///
+/// The cache is created the first time this is called, and every later call
+/// in the same process returns the same one. So separate parts of a program,
+/// including separate crates which depend on this one, share the primes
+/// computed by any of them instead of building their own sieves.
+///
+/// The cache only ever grows, and its memory isn't released until the
+/// process exits. Holding every prime up to `n` takes roughly
+/// `8 n / ln n` bytes, which is about 5 MB for the primes up to 10 million. If
+/// the horizon is known up front, the cache can be warmed ahead of time with
+/// [`SharedPrimes::extend_to`] so that later queries never have to sieve.
+///
+/// # Examples
+///
+/// ```
+/// sieve::global().extend_to(100_000);
+///
+/// assert_eq!(sieve::global().get(1000), 7927);
+/// assert!(sieve::global().is_prime(99_991));
+/// assert!(sieve::global().iter().take(4).eq([2, 3, 5, 7]));
+/// ```
+pub fn global() -> &'static SharedPrimes<u64> {
+    static INIT: Once = Once::new();
+    static GLOBAL: AtomicPtr<SharedPrimes<u64>> = AtomicPtr::new(ptr::null_mut());
+
+    INIT.call_once(|| {
+        let primes = Box::leak(Box::new(SharedPrimes::new()));
+        GLOBAL.store(primes, Ordering::Release);
+    });
+
+    // SAFETY: The pointer is stored exactly once, above, from a box which is
+    // leaked so that it lives for the rest of the process. `call_once` only
+    // returns after it has been stored, and it's never written again.
+    unsafe { &*GLOBAL.load(Ordering::Acquire) }
+}
+
+/// Cloning shares the cache, rather than copying the primes in it.
+impl<I> Clone for SharedPrimes<I> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use std::panic;
+    use std::thread;
+
+    use super::{global, SharedPrimes};
+
+    #[test]
+    fn hammer() {
+        const N: usize = 100_000;
+
+        let expected = crate::infinite::<u64>().take(N).collect::<Vec<_>>();
+        let expected = Arc::new(expected);
+        let primes = SharedPrimes::<u64>::new();
+
+        let handles = (0..8)
+            .map(|t| {
+                let primes = primes.clone();
+                let expected = expected.clone();
+
+                thread::spawn(move || {
+                    for i in 0..200 {
+                        let index = (i * 7919 + t * 104_729) % N;
+                        assert_eq!(primes.get(index), expected[index]);
+
+                        if i % 50 == t {
+                            primes.extend_to(expected[(index * 3) % N]);
+                        }
+                    }
+
+                    assert!(primes
+                        .iter()
+                        .take(N / 10)
+                        .eq(expected[..N / 10].iter().copied()));
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert!(primes.iter().take(N).eq(expected.iter().copied()));
+    }
+
+    #[test]
+    fn extend_to() {
+        let primes = SharedPrimes::<u32>::new();
+
+        for &bound in &[0, 2, 10, 7919, 7920, 1_000_000] {
+            primes.extend_to(bound);
+            let len = primes.inner.state.lock().unwrap().len;
+            assert!(len >= crate::bounded(bound).count(), "{}", bound);
+        }
+
+        assert!(primes
+            .iter()
+            .take(80_000)
+            .eq(crate::infinite::<u32>().take(80_000)));
+    }
+
+    #[test]
+    fn end_of_type() {
+        let primes = SharedPrimes::<u16>::new();
+        primes.extend_to(u16::MAX);
+        assert!(primes.iter().eq(crate::bounded(u16::MAX)));
+        assert_eq!(primes.get(6541), 65521);
+
+        let result = panic::catch_unwind(|| primes.get(6542));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn is_prime() {
+        let primes = SharedPrimes::<u32>::new();
+        let expected = crate::bounded(10_000u32).collect::<Vec<_>>();
+
+        for n in (0..=10_000).rev() {
+            assert_eq!(
+                primes.is_prime(n),
+                expected.binary_search(&n).is_ok(),
+                "{}",
+                n
+            );
+        }
+
+        assert!(primes.cached().take(expected.len()).eq(expected));
+    }
+
+    #[test]
+    fn global_is_shared() {
+        // Other tests might be warming the cache concurrently, so this only
+        // checks what they can't change.
+        assert!(core::ptr::eq(global(), global()));
+
+        let handles = (0..8u64)
+            .map(|t| {
+                thread::spawn(move || {
+                    global().extend_to(100_000 * (t + 1));
+                    global() as *const SharedPrimes<u64> as usize
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), global() as *const _ as usize);
+        }
+
+        // Everything computed on the other threads is visible here.
+        assert!(global().len() >= crate::bounded(800_000u64).count());
+        assert!(global()
+            .cached()
+            .eq(crate::infinite::<u64>().take(global().len())));
+        assert!(global().is_prime(799_999) == crate::is_prime_u64(799_999));
+    }
+}
@@ -0,0 +1,342 @@
+//! Smooth numbers, whose prime factors are all small.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::ops;
+
+use crate::{Bounded, SieveInt};
+
+/// Test if `n` is `bound`-smooth, which is when none of its prime factors are
+/// larger than `bound`.
+///
+/// The primes up to `bound` are divided out of `n` in increasing order, taken
+/// from [`SMALL_PRIMES`][crate::SMALL_PRIMES] and beyond that from an
+/// [`infinite()`][crate::infinite()] sieve. This stops as soon as the
+/// remaining cofactor is at most `bound`, in which case `n` is smooth, or when
+/// the cofactor is a prime larger than `bound`, in which case it isn't. So the
+/// primes are only sieved as far as they're needed.
+///
+/// 1 is smooth for every bound since it has no prime factors. Neither 0 nor
+/// negative numbers are smooth.
+///
+/// To test many numbers against the same bound, see [`is_smooth_with()`].
+///
+/// # Examples
+///
+/// ```
+/// assert!(sieve::is_smooth(360u32, 5));
+/// assert!(!sieve::is_smooth(360u32, 3));
+/// assert!(sieve::is_smooth(1u32, 0));
+/// assert!(!sieve::is_smooth(0u32, 100));
+///
+/// // 2^61 - 1 is prime, so it's only smooth against itself.
+/// assert!(!sieve::is_smooth((1u64 << 61) - 1, 1_000_000));
+/// ```
+pub fn is_smooth<I>(n: I, bound: I) -> bool
+where
+    I: SieveInt,
+    ops::RangeFrom<I>: Iterator<Item = I>,
+{
+    let primes =
+        crate::small::primes::<I>().chain(crate::infinite::<I>().skip(crate::SMALL_PRIMES.len()));
+
+    smooth(n, bound, primes.take_while(|&p| p <= bound))
+}
+
+/// Test if `n` is smooth over `primes`, which is when none of its prime
+/// factors are larger than the largest of them.
+///
+/// `primes` must hold every prime up to some bound in increasing order, like
+/// those collected from [`bounded()`][crate::bounded()]. This is otherwise the
+/// same as [`is_smooth()`], but the primes are only produced once when
+/// testing many numbers.
+///
+/// # Examples
+///
+/// ```
+/// let primes = sieve::bounded(7u32).collect::<Vec<_>>();
+///
+/// let humble = (1..=30u32).filter(|&n| sieve::is_smooth_with(n, &primes));
+/// assert!(humble.eq([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 14, 15, 16, 18, 20, 21, 24, 25, 27, 28, 30]));
+///
+/// assert!(sieve::is_smooth_with(1u32, &[]));
+/// assert!(!sieve::is_smooth_with(2u32, &[]));
+/// ```
+pub fn is_smooth_with<I>(n: I, primes: &[I]) -> bool
+where
+    I: SieveInt,
+{
+    let bound = match primes.last() {
+        Some(&bound) => bound,
+        None => return n == I::from_small(1),
+    };
+
+    smooth(n, bound, primes.iter().copied())
+}
+
+/// Construct an iterator over the `bound`-smooth numbers up to and including
+/// `upper` in increasing order, which are the numbers with no prime factors
+/// larger than `bound`.
+///
+/// Rather than testing every number, the smooth numbers are generated from
+/// each other as a k-way merge over the multiples of the primes. Every smooth
+/// number `m > 1` with largest prime factor `p` comes from `m / p` exactly
+/// once, so nothing is produced twice. The numbers waiting to be produced are
+/// kept in a binary heap, and the primes are drawn from a
+/// [`bounded()`][crate::bounded()] sieve as they're needed. Products are
+/// computed with checked multiplication, so `upper` can be anything up to the
+/// largest value of `I`.
+///
+/// 1 is produced first, since it has no prime factors.
+///
+/// # Examples
+///
+/// ```
+/// let humble = sieve::smooth_numbers(7u32, 30);
+/// assert!(humble.eq([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 14, 15, 16, 18, 20, 21, 24, 25, 27, 28, 30]));
+///
+/// assert!(sieve::smooth_numbers(2u8, u8::MAX).eq([1, 2, 4, 8, 16, 32, 64, 128]));
+/// assert!(sieve::smooth_numbers(0u32, 100).eq([1]));
+/// ```
+pub fn smooth_numbers<I>(bound: I, upper: I) -> SmoothNumbers<I>
+where
+    I: SieveInt,
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+{
+    SmoothNumbers {
+        primes: Vec::new(),
+        sieve: crate::bounded(bound.min(upper)),
+        upper,
+        started: false,
+        heap: BinaryHeap::new(),
+    }
+}
+
+/// An iterator over the smooth numbers up to a bound.
+///
+/// See [`smooth_numbers()`].
+pub struct SmoothNumbers<I> {
+    /// The primes drawn from the sieve so far.
+    primes: Vec<I>,
+    sieve: Bounded<I>,
+    upper: I,
+    /// Whether 1 has been produced.
+    started: bool,
+    /// The smooth numbers waiting to be produced, along with the index of
+    /// their largest prime factor.
+    heap: BinaryHeap<Reverse<(I, usize)>>,
+}
+
+impl<I> SmoothNumbers<I>
+where
+    I: SieveInt,
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+{
+    /// Get the prime at `index`, drawing it from the sieve if needed.
+    fn prime(&mut self, index: usize) -> Option<I> {
+        while self.primes.len() <= index {
+            let p = self.sieve.next()?;
+            self.primes.push(p);
+        }
+
+        Some(self.primes[index])
+    }
+
+    /// Queue `base * p`, where `p` is the prime at `index`, if it's in range.
+    fn push(&mut self, base: I, index: usize) {
+        if let Some(p) = self.prime(index) {
+            if let Some(n) = base.checked_mul(p) {
+                if n <= self.upper {
+                    self.heap.push(Reverse((n, index)));
+                }
+            }
+        }
+    }
+}
+
+impl<I> Iterator for SmoothNumbers<I>
+where
+    I: SieveInt,
+    ops::RangeInclusive<I>: Iterator<Item = I>,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let one = I::from_small(1);
+
+        if !self.started {
+            self.started = true;
+
+            if self.upper < one {
+                return None;
+            }
+
+            self.push(one, 0);
+            return Some(one);
+        }
+
+        let Reverse((n, index)) = self.heap.pop()?;
+
+        // The children of `n = base * p` are `n * p` and `base * q`, where `q`
+        // is the prime after `p`. Following them from 1 reaches every smooth
+        // number exactly once, by way of its largest prime factor.
+        let p = self.primes[index];
+        self.push(n, index);
+        self.push(n / p, index + 1);
+        Some(n)
+    }
+}
+
+/// Test if `n` is `bound`-smooth by dividing out `primes`, which must be the
+/// primes up to `bound` in increasing order.
+fn smooth<I, P>(n: I, bound: I, primes: P) -> bool
+where
+    I: SieveInt,
+    P: IntoIterator<Item = I>,
+{
+    let zero = I::from_small(0);
+    let one = I::from_small(1);
+
+    if n <= zero {
+        return false;
+    }
+
+    if n == one || n <= bound {
+        return true;
+    }
+
+    if is_known_prime(n) {
+        return false;
+    }
+
+    let mut n = n;
+
+    for p in primes {
+        if n % p != zero {
+            continue;
+        }
+
+        while n % p == zero {
+            n = n / p;
+        }
+
+        if n <= bound {
+            return true;
+        }
+
+        if is_known_prime(n) {
+            return false;
+        }
+    }
+
+    false
+}
+
+/// Test if `n` is a prime which fits in a `u64`.
+///
+/// Larger numbers are treated as composite, since trial division doesn't
+/// scale to them and they're left to the division by primes instead.
+fn is_known_prime<I>(n: I) -> bool
+where
+    I: SieveInt,
+{
+    n.to_u64().map_or(false, crate::is_prime_u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{is_smooth, is_smooth_with, smooth_numbers};
+
+    /// Get the largest prime factor of `n` by trial division.
+    fn largest_factor(mut n: u64) -> u64 {
+        let mut largest = 1;
+        let mut d = 2;
+
+        while n > 1 {
+            while n % d == 0 {
+                n /= d;
+                largest = d;
+            }
+
+            d += 1;
+        }
+
+        largest
+    }
+
+    #[test]
+    fn matches_factorization() {
+        for &bound in &[0, 1, 2, 3, 4, 5, 7, 10, 31, 97, 100, 1000, 10_000] {
+            let primes = crate::bounded(bound).collect::<Vec<_>>();
+
+            for n in 1..10_000u64 {
+                let expected = n == 1 || largest_factor(n) <= bound;
+                assert_eq!(is_smooth(n, bound), expected, "{} {}", n, bound);
+                assert_eq!(is_smooth_with(n, &primes), expected, "{} {}", n, bound);
+            }
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        assert!(!is_smooth(0u32, 0));
+        assert!(!is_smooth(-8i32, 5));
+        assert!(!is_smooth_with(0u32, &[2, 3]));
+        assert!(is_smooth(1i32, -1));
+
+        assert!(is_smooth(u64::MAX, 6_700_417));
+        assert!(!is_smooth(u64::MAX, 6_700_416));
+        assert!(is_smooth(1u128 << 100, 2));
+        assert!(is_smooth(3u128.pow(80), 3));
+        assert!(!is_smooth(3u128.pow(70) * 5, 3));
+        assert!(is_smooth(u8::MAX, 17));
+        assert!(!is_smooth(251u8, 250));
+    }
+
+    #[test]
+    fn hamming_numbers() {
+        let expected = [
+            1, 2, 3, 4, 5, 6, 8, 9, 10, 12, 15, 16, 18, 20, 24, 25, 27, 30, 32, 36, 40, 45, 48, 50,
+            54, 60, 64, 72, 75, 80, 81, 90, 96, 100,
+        ];
+
+        assert!(smooth_numbers(5u32, 100).eq(expected));
+        assert!(smooth_numbers(6u32, u32::MAX)
+            .take(expected.len())
+            .eq(expected));
+    }
+
+    #[test]
+    fn matches_filter() {
+        let primes = crate::bounded(7u32).collect::<Vec<_>>();
+        let expected = (1..=1_000_000u32).filter(|&n| is_smooth_with(n, &primes));
+        assert!(smooth_numbers(7u32, 1_000_000).eq(expected));
+        assert_eq!(smooth_numbers(7u32, 1_000_000).count(), 1273);
+
+        for &bound in &[0, 1, 2, 3, 10, 97, 1000, 3000] {
+            let expected = (1..=2000u64).filter(|&n| is_smooth(n, bound));
+            assert!(smooth_numbers(bound, 2000).eq(expected), "{}", bound);
+        }
+    }
+
+    #[test]
+    fn end_of_type() {
+        assert!(smooth_numbers(u8::MAX, u8::MAX).eq(1..=u8::MAX));
+        assert!(smooth_numbers(i8::MAX, i8::MAX).eq(1..=i8::MAX));
+        assert_eq!(smooth_numbers(5i32, -1).next(), None);
+
+        // Every power of two and three which fits.
+        let expected = (0..64u32)
+            .map(|i| {
+                (0..41u32)
+                    .filter(|&j| 3u128.pow(j) << i <= u128::from(u64::MAX))
+                    .count()
+            })
+            .sum::<usize>();
+        assert_eq!(smooth_numbers(3u64, u64::MAX).count(), expected);
+        assert_eq!(smooth_numbers(2u64, u64::MAX).last(), Some(1 << 63));
+    }
+}
@@ -0,0 +1,252 @@
+//! Squarefree numbers, which aren't divisible by the square of any prime.
+
+use alloc::vec::Vec;
+use core::marker;
+use core::ops;
+
+use crate::flat::{isqrt, Window};
+use crate::segmented::SEGMENT_LEN;
+use crate::small;
+use crate::SieveInt;
+
+/// An iterator over the squarefree numbers up to a bound.
+///
+/// See [`squarefree()`].
+pub struct Squarefree<I> {
+    /// The primes up to the square root of the upper bound.
+    primes: Vec<u64>,
+    upper: u64,
+    /// The squarefree numbers left in the current segment.
+    window: Window,
+    /// The first number of the next segment, or `None` if all segments have
+    /// been sieved.
+    next: Option<u64>,
+    segment_len: u64,
+    _marker: marker::PhantomData<I>,
+}
+
+impl<I> Squarefree<I> {
+    /// Construct a sieve over the squarefree numbers in `1..=upper`.
+    fn new(upper: u64, segment_len: u64) -> Self {
+        let mut primes = Vec::new();
+        let root = isqrt(upper);
+
+        if root >= 2 {
+            primes.push(2);
+            primes.extend(small::odd_primes(root));
+        }
+
+        Self {
+            primes,
+            upper,
+            window: Window::new(),
+            next: if upper >= 1 { Some(1) } else { None },
+            segment_len,
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Sieve the numbers in `lo..=hi`.
+    fn sieve(&mut self, lo: u64, hi: u64) {
+        self.window.reset(lo, hi);
+
+        for &p in &self.primes {
+            if p > hi / p {
+                break;
+            }
+
+            let square = p * p;
+            let offset = (square - lo % square) % square;
+            self.window
+                .clear_multiples(offset as usize, square as usize);
+        }
+    }
+
+    /// Remove and return the smallest squarefree number remaining in the
+    /// segment.
+    #[inline]
+    fn pop(&mut self) -> Option<u64> {
+        self.window.pop()
+    }
+}
+
+/// Construct an iterator over the squarefree numbers up to and including
+/// `upper` in increasing order, which are the numbers not divisible by the
+/// square of any prime.
+///
+/// The range is sieved in segments like [`segmented()`][crate::segmented()],
+/// clearing the multiples of `p^2` for each prime `p` up to `sqrt(upper)`.
+/// That's much faster than testing each number with [`is_squarefree()`].
+/// 1 is squarefree, since it has no prime factors.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// let numbers = sieve::squarefree(20u32);
+/// assert!(numbers.eq([1, 2, 3, 5, 6, 7, 10, 11, 13, 14, 15, 17, 19]));
+///
+/// // Roughly 6 / pi^2 of all numbers are squarefree.
+/// assert_eq!(sieve::squarefree(1_000_000u32).count(), 607_926);
+/// ```
+pub fn squarefree<I>(upper: I) -> Squarefree<I>
+where
+    I: SieveInt,
+{
+    if upper < I::from_small(1) {
+        return Squarefree::new(0, SEGMENT_LEN);
+    }
+
+    let upper = match upper.to_u64() {
+        Some(upper) => upper,
+        None => panic!("bound doesn't fit in a u64"),
+    };
+
+    Squarefree::new(upper, SEGMENT_LEN)
+}
+
+impl<I> Iterator for Squarefree<I>
+where
+    I: SieveInt,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(n) = self.pop() {
+                return I::from_u64(n);
+            }
+
+            let lo = self.next?;
+            let hi = lo.saturating_add(self.segment_len - 1).min(self.upper);
+            self.sieve(lo, hi);
+            self.next = hi.checked_add(1).filter(|&k| k <= self.upper);
+        }
+    }
+}
+
+/// Test if `n` is squarefree, which is when it isn't divisible by the square
+/// of any prime.
+///
+/// This performs trial division like [`factorize()`][crate::factorize()], but
+/// stops as soon as a prime divides `n` twice. Once the primes pass the cube
+/// root of the remaining cofactor, it has at most two prime factors and is
+/// squarefree unless it's a square, so the primes only go up to the cube root
+/// of `n` when it fits in a `u64`.
+///
+/// 1 is squarefree, while neither 0 nor negative numbers are.
+///
+/// # Examples
+///
+/// ```
+/// assert!(sieve::is_squarefree(30u32));
+/// assert!(!sieve::is_squarefree(18u32));
+/// assert!(sieve::is_squarefree(1u32));
+/// assert!(!sieve::is_squarefree(0u32));
+///
+/// let p = 4_294_967_291u64;
+/// assert!(!sieve::is_squarefree(p * p));
+/// assert!(sieve::is_squarefree(u64::MAX));
+/// ```
+pub fn is_squarefree<I>(n: I) -> bool
+where
+    I: SieveInt,
+    ops::RangeFrom<I>: Iterator<Item = I>,
+{
+    let zero = I::from_small(0);
+
+    if n <= zero {
+        return false;
+    }
+
+    let primes =
+        crate::small::primes::<I>().chain(crate::infinite::<I>().skip(crate::SMALL_PRIMES.len()));
+
+    let mut n = n;
+
+    for p in primes {
+        if p > n / p {
+            // The cofactor is 1 or a prime.
+            return true;
+        }
+
+        if p > n / p / p {
+            if let Some(n) = n.to_u64() {
+                let root = isqrt(n);
+                return root * root != n;
+            }
+        }
+
+        if n % p == zero {
+            n = n / p;
+
+            if n % p == zero {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{is_squarefree, squarefree, Squarefree};
+
+    #[test]
+    fn first_terms() {
+        let expected = [
+            1, 2, 3, 5, 6, 7, 10, 11, 13, 14, 15, 17, 19, 21, 22, 23, 26, 29, 30,
+        ];
+        assert!(squarefree(30u32).eq(expected));
+        assert!((1..=30u32).filter(|&n| is_squarefree(n)).eq(expected));
+        assert_eq!(squarefree(1_000_000u64).count(), 607_926);
+    }
+
+    #[test]
+    fn matches_single() {
+        let expected = (0..=100_000u64)
+            .filter(|&n| is_squarefree(n))
+            .collect::<Vec<_>>();
+
+        for &segment_len in &[1, 7, 64, 1000, super::SEGMENT_LEN] {
+            let actual = Squarefree::<u64>::new(100_000, segment_len);
+            assert!(actual.eq(expected.iter().copied()), "{}", segment_len);
+        }
+
+        for n in 1..10_000u64 {
+            let expected = crate::factorize(n).iter().all(|&(_, k)| k == 1);
+            assert_eq!(is_squarefree(n), expected, "{}", n);
+        }
+    }
+
+    #[test]
+    fn large_values() {
+        let p = crate::prev_prime(1u64 << 32).unwrap();
+        let q = crate::prev_prime(p - 1).unwrap();
+
+        assert!(is_squarefree(p * q));
+        assert!(!is_squarefree(p * p));
+        assert!(!is_squarefree(q * q));
+        assert!(!is_squarefree(7 * 7 * 1_000_003u64));
+        assert!(is_squarefree((1u64 << 61) - 1));
+        assert!(!is_squarefree(1u64 << 63));
+        assert!(!is_squarefree(-6i32));
+        assert!(is_squarefree(u128::from(u64::MAX) * 7));
+        assert!(!is_squarefree(u128::from(u64::MAX) * 3));
+    }
+
+    #[test]
+    fn end_of_type() {
+        let expected = (1..=u8::MAX).filter(|&n| is_squarefree(n));
+        assert!(squarefree(u8::MAX).eq(expected));
+        assert!(squarefree(i8::MAX).eq((1..=i8::MAX).filter(|&n| is_squarefree(n))));
+        assert_eq!(squarefree(0u32).next(), None);
+        assert_eq!(squarefree(-5i32).next(), None);
+    }
+}
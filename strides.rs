@@ -0,0 +1,273 @@
+//! The primes whose next multiple is the same composite.
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+use alloc::vec::{self, Vec};
+use core::hash;
+#[cfg(not(feature = "std"))]
+use core::marker::PhantomData;
+use core::mem;
+use core::slice;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+/// The primes which cross off a single composite.
+///
+/// Almost every composite is crossed off by just one tracked prime, so that
+/// case is stored inline. Keeping every prime which lands on a composite
+/// means each one advances exactly once per composite it hits, instead of
+/// probing ahead for a composite nothing else is tracked at.
+#[derive(Debug, Clone)]
+pub(crate) enum Strides<I> {
+    One(I),
+    Many(Vec<I>),
+}
+
+impl<I> Strides<I> {
+    /// Get the number of primes.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Strides::One(..) => 1,
+            Strides::Many(primes) => primes.len(),
+        }
+    }
+
+    /// Iterate over the primes.
+    #[inline]
+    pub(crate) fn iter(&self) -> slice::Iter<'_, I> {
+        match self {
+            Strides::One(prime) => slice::from_ref(prime).iter(),
+            Strides::Many(primes) => primes.iter(),
+        }
+    }
+
+    /// Add a prime.
+    fn push(&mut self, prime: I) {
+        match self {
+            Strides::One(..) => {
+                let mut primes = Vec::with_capacity(2);
+
+                if let Strides::One(first) = mem::replace(self, Strides::Many(Vec::new())) {
+                    primes.push(first);
+                }
+
+                primes.push(prime);
+                *self = Strides::Many(primes);
+            }
+            Strides::Many(primes) => primes.push(prime),
+        }
+    }
+}
+
+impl<I> IntoIterator for Strides<I> {
+    type Item = I;
+    type IntoIter = IntoIter<I>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            Strides::One(prime) => IntoIter::One(Some(prime)),
+            Strides::Many(primes) => IntoIter::Many(primes.into_iter()),
+        }
+    }
+}
+
+/// An owning iterator over [`Strides`].
+pub(crate) enum IntoIter<I> {
+    One(Option<I>),
+    Many(vec::IntoIter<I>),
+}
+
+impl<I> Iterator for IntoIter<I> {
+    type Item = I;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            IntoIter::One(prime) => prime.take(),
+            IntoIter::Many(primes) => primes.next(),
+        }
+    }
+}
+
+/// The composites being tracked by the sieve, each with the primes which
+/// cross it off.
+///
+/// Without `std` there's no hash map to use, so the composites are kept in a
+/// `BTreeMap` instead and the hash builder goes unused.
+#[derive(Clone)]
+pub(crate) struct Composites<I, S> {
+    #[cfg(feature = "std")]
+    map: HashMap<I, Strides<I>, S>,
+    #[cfg(not(feature = "std"))]
+    map: BTreeMap<I, Strides<I>>,
+    #[cfg(not(feature = "std"))]
+    hash_builder: PhantomData<S>,
+    /// The number of primes being tracked.
+    len: usize,
+}
+
+impl<I, S> Composites<I, S> {
+    /// Construct an empty map which uses the given hash builder.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            map: HashMap::with_hasher(hash_builder),
+            len: 0,
+        }
+    }
+
+    /// Construct an empty map, ignoring the hash builder.
+    #[cfg(not(feature = "std"))]
+    #[inline]
+    pub(crate) fn with_hasher(_: S) -> Self {
+        Self {
+            map: BTreeMap::new(),
+            hash_builder: PhantomData,
+            len: 0,
+        }
+    }
+
+    /// Get the number of composites the map can hold without growing.
+    #[cfg(all(test, feature = "std"))]
+    #[inline]
+    pub(crate) fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Estimate the number of bytes allocated by the map.
+    #[cfg(feature = "std")]
+    pub(crate) fn memory_usage(&self) -> usize {
+        // The map keeps a power of two number of buckets which are at most 7/8
+        // full, each of which needs a control byte next to its entry.
+        let buckets = match self.map.capacity() {
+            0 => 0,
+            1..=3 => 4,
+            4..=7 => 8,
+            capacity => (capacity / 7 * 8).next_power_of_two(),
+        };
+
+        buckets * (mem::size_of::<(I, Strides<I>)>() + 1)
+    }
+
+    /// Estimate the number of bytes allocated by the map.
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn memory_usage(&self) -> usize {
+        // Each node has room for 11 entries and is at least half full, next to
+        // a pointer to its parent and two indexes.
+        const ENTRIES: usize = 11;
+        let nodes = (self.map.len() + ENTRIES / 2 - 1) / (ENTRIES / 2);
+        nodes * (ENTRIES * mem::size_of::<(I, Strides<I>)>() + 2 * mem::size_of::<usize>())
+    }
+
+    /// Get the number of primes being tracked, which is at least the number
+    /// of composites.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Iterate over the composites in arbitrary order.
+    #[inline]
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &I> {
+        self.map.keys()
+    }
+
+    /// Iterate over every composite and a prime which crosses it off, in
+    /// arbitrary order.
+    #[inline]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&I, &I)> {
+        self.map
+            .iter()
+            .flat_map(|(key, strides)| strides.iter().map(move |prime| (key, prime)))
+    }
+
+    /// Collect every composite and a prime which crosses it off, in arbitrary
+    /// order.
+    pub(crate) fn into_vec(self) -> Vec<(I, I)>
+    where
+        I: Clone,
+    {
+        let mut pairs = Vec::with_capacity(self.len);
+
+        for (key, strides) in self.map {
+            pairs.extend(strides.into_iter().map(|prime| (key.clone(), prime)));
+        }
+
+        pairs
+    }
+
+    /// Remove every composite, keeping the allocated room where the map
+    /// allows it.
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        self.map.clear();
+        self.len = 0;
+    }
+}
+
+impl<I, S> Composites<I, S>
+where
+    I: Ord + hash::Hash,
+    S: hash::BuildHasher,
+{
+    /// Track `prime` at the composite `key`, alongside any other primes which
+    /// are already there.
+    #[inline]
+    pub(crate) fn insert(&mut self, key: I, prime: I) {
+        match self.map.get_mut(&key) {
+            Some(strides) => strides.push(prime),
+            None => {
+                self.map.insert(key, Strides::One(prime));
+            }
+        }
+
+        self.len += 1;
+    }
+
+    /// Test if `key` is a composite being tracked.
+    #[inline]
+    pub(crate) fn contains(&self, key: &I) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Remove the composite `key`, returning the primes which cross it off.
+    #[inline]
+    pub(crate) fn remove(&mut self, key: &I) -> Option<Strides<I>> {
+        let strides = self.map.remove(key)?;
+        self.len -= strides.len();
+        Some(strides)
+    }
+
+    /// Reserve room for at least `additional` more composites. This does
+    /// nothing without `std`, since the nodes of a `BTreeMap` are allocated
+    /// as they're needed.
+    #[inline]
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        #[cfg(feature = "std")]
+        self.map.reserve(additional);
+        #[cfg(not(feature = "std"))]
+        let _ = additional;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::Strides;
+
+    #[test]
+    fn push() {
+        let mut strides = Strides::One(7u32);
+        assert_eq!(strides.len(), 1);
+
+        strides.push(11);
+        strides.push(13);
+        assert_eq!(strides.len(), 3);
+        assert!(strides.iter().copied().eq([7, 11, 13]));
+        assert_eq!(strides.into_iter().collect::<Vec<_>>(), [7, 11, 13]);
+    }
+}
@@ -0,0 +1,622 @@
+//! Tables of arithmetic functions for every number up to a bound.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::flat::Flat;
+
+/// Run a linear sieve over `2..=upper`, calling `visit(n, p, m)` for every
+/// number `n` where `p` is its smallest prime factor and `n = p * m`.
+///
+/// Every number is visited exactly once, and always after `m` has been, so
+/// multiplicative functions can be built up from the values for `m`. Primes
+/// are visited with `m = 1`.
+fn linear(upper: usize, mut visit: impl FnMut(usize, usize, usize)) {
+    let mut marked = vec![0u64; upper / 64 + 1];
+    let mut primes = Vec::new();
+
+    for m in 2..=upper {
+        if marked[m / 64] & (1 << (m % 64)) == 0 {
+            visit(m, m, 1);
+            primes.push(m);
+        }
+
+        for &p in &primes {
+            let n = match m.checked_mul(p) {
+                Some(n) if n <= upper => n,
+                _ => break,
+            };
+
+            marked[n / 64] |= 1 << (n % 64);
+            visit(n, p, m);
+
+            // Past this point `p` would no longer be the smallest prime factor.
+            if m % p == 0 {
+                break;
+            }
+        }
+    }
+}
+
+/// Convert a number up to the bound of a table.
+#[inline]
+fn cast<I>(n: usize) -> I
+where
+    I: TryFrom<usize>,
+{
+    match I::try_from(n) {
+        Ok(n) => n,
+        Err(..) => panic!("bound doesn't fit in the table type"),
+    }
+}
+
+/// Compute the smallest prime factor of every number up to and including
+/// `upper`, indexed by the number itself.
+///
+/// This uses a linear sieve which visits every composite exactly once. Since
+/// 0 and 1 have no prime factors, they're given the sentinel 0. With the table
+/// any number up to `upper` can be factorized in `O(log n)` steps by
+/// repeatedly dividing out its smallest prime factor.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in `I`.
+///
+/// # Examples
+///
+/// ```
+/// let spf = sieve::spf_table::<u32>(100);
+///
+/// assert_eq!(spf.len(), 101);
+/// assert_eq!(spf[..10], [0, 0, 2, 3, 2, 5, 2, 7, 2, 3]);
+/// assert_eq!(spf[91], 7);
+/// assert_eq!(spf[97], 97);
+///
+/// // Factorize 84 by dividing out smallest prime factors.
+/// let mut n = 84;
+/// let mut factors = Vec::new();
+///
+/// while n > 1 {
+///     factors.push(spf[n]);
+///     n /= spf[n] as usize;
+/// }
+///
+/// assert_eq!(factors, [2, 2, 3, 7]);
+/// ```
+pub fn spf_table<I>(upper: usize) -> Vec<I>
+where
+    I: Copy + Default + TryFrom<usize>,
+{
+    cast::<I>(upper);
+
+    let mut spf = vec![I::default(); upper + 1];
+    linear(upper, |n, p, _| spf[n] = cast(p));
+    spf
+}
+
+/// Compute Euler's totient `φ(n)` of every number up to and including `upper`,
+/// indexed by the number itself.
+///
+/// `φ(n)` counts the numbers in `1..=n` which are coprime to `n`. This uses
+/// the same linear sieve as [`spf_table()`], where the totient of each number
+/// follows from that of the number with its smallest prime factor divided
+/// out. `φ(0)` is taken to be 0.
+///
+/// # Examples
+///
+/// ```
+/// let phi = sieve::totient_table(36);
+///
+/// assert_eq!(phi[..11], [0, 1, 1, 2, 2, 4, 2, 6, 4, 6, 4]);
+/// assert_eq!(phi[36], 12);
+/// ```
+pub fn totient_table(upper: usize) -> Vec<u64> {
+    let mut phi = vec![0u64; upper + 1];
+
+    if let Some(one) = phi.get_mut(1) {
+        *one = 1;
+    }
+
+    linear(upper, |n, p, m| {
+        let p = p as u64;
+
+        phi[n] = if m as u64 % p == 0 {
+            phi[m] * p
+        } else {
+            phi[m] * (p - 1)
+        };
+    });
+
+    phi
+}
+
+/// Compute the Möbius function `μ(n)` of every number up to and including
+/// `upper`, indexed by the number itself.
+///
+/// `μ(n)` is 0 if `n` is divisible by a square, and otherwise 1 or -1
+/// depending on whether `n` has an even or odd number of prime factors. This
+/// uses the same linear sieve as [`spf_table()`]. `μ(0)` is taken to be 0.
+///
+/// # Examples
+///
+/// ```
+/// let mu = sieve::mobius_table(30);
+///
+/// assert_eq!(mu[..11], [0, 1, -1, -1, 0, -1, 1, -1, 0, 0, 1]);
+/// assert_eq!(mu[30], -1);
+///
+/// // The Mertens function is the running sum.
+/// assert_eq!(mu.iter().map(|&m| i64::from(m)).sum::<i64>(), -3);
+/// ```
+pub fn mobius_table(upper: usize) -> Vec<i8> {
+    let mut mu = vec![0i8; upper + 1];
+
+    if let Some(one) = mu.get_mut(1) {
+        *one = 1;
+    }
+
+    linear(upper, |n, p, m| {
+        mu[n] = if m % p == 0 { 0 } else { -mu[m] };
+    });
+
+    mu
+}
+
+/// Compute the number of divisors `d(n)` of every number up to and including
+/// `upper`, indexed by the number itself.
+///
+/// This uses the same linear sieve as [`spf_table()`], while also tracking
+/// the exponent of the smallest prime factor of each number. That takes
+/// about 5 bytes per number on top of the sieve's own bit per number, so a
+/// bound of 10⁹ needs around 5 GiB and bounds in the low hundreds of millions
+/// are the practical limit. `d(0)` is taken to be 0.
+///
+/// # Examples
+///
+/// ```
+/// let d = sieve::divisor_count_table(360);
+///
+/// assert_eq!(d[..13], [0, 1, 2, 2, 3, 2, 4, 2, 4, 3, 4, 2, 6]);
+/// assert_eq!(d[360], 24);
+/// ```
+pub fn divisor_count_table(upper: usize) -> Vec<u32> {
+    let mut d = vec![0u32; upper + 1];
+    // The exponent of the smallest prime factor.
+    let mut e = vec![0u8; upper + 1];
+
+    if let Some(one) = d.get_mut(1) {
+        *one = 1;
+    }
+
+    linear(upper, |n, p, m| {
+        if m % p == 0 {
+            e[n] = e[m] + 1;
+            d[n] = d[m] / (u32::from(e[m]) + 1) * (u32::from(e[n]) + 1);
+        } else {
+            e[n] = 1;
+            d[n] = d[m] * 2;
+        }
+    });
+
+    d
+}
+
+/// Compute the sum of divisors `σ(n)` of every number up to and including
+/// `upper`, indexed by the number itself.
+///
+/// This uses the same linear sieve as [`spf_table()`], while also tracking
+/// `1 + p + ... + p^e` for the smallest prime factor `p` of each number. Sums
+/// are kept as `u64`, which can't overflow for any `upper` which fits the
+/// table in memory. `σ(0)` is taken to be 0.
+///
+/// # Examples
+///
+/// ```
+/// let sigma = sieve::divisor_sum_table(28);
+///
+/// assert_eq!(sigma[..7], [0, 1, 3, 4, 7, 6, 12]);
+///
+/// // 6 and 28 are perfect.
+/// assert_eq!(sigma[28], 56);
+/// ```
+pub fn divisor_sum_table(upper: usize) -> Vec<u64> {
+    let mut sigma = vec![0u64; upper + 1];
+    // The sum of the powers of the smallest prime factor.
+    let mut powers = vec![0u64; upper + 1];
+
+    if let Some(one) = sigma.get_mut(1) {
+        *one = 1;
+    }
+
+    linear(upper, |n, p, m| {
+        let p = p as u64;
+
+        if m as u64 % p == 0 {
+            powers[n] = powers[m] * p + 1;
+            sigma[n] = sigma[m] / powers[m] * powers[n];
+        } else {
+            powers[n] = p + 1;
+            sigma[n] = sigma[m] * (p + 1);
+        }
+    });
+
+    sigma
+}
+
+/// Compute the prime-counting function `π(n)` of every number up to and
+/// including `upper`, indexed by the number itself.
+///
+/// The primes are sieved into a flat bit array like
+/// [`bounded_bitset()`][crate::bounded_bitset()], and the table is filled in
+/// as a running count in one pass over it. The number of primes in `lo..=hi`
+/// is then `table[hi] - table[lo - 1]`. Counts are kept as `u32`, which can't
+/// overflow for any `upper` which fits the table in memory.
+///
+/// # Examples
+///
+/// ```
+/// let pi = sieve::prime_pi_table(100);
+///
+/// assert_eq!(pi[..11], [0, 0, 1, 2, 2, 3, 3, 4, 4, 4, 4]);
+/// assert_eq!(pi[100], 25);
+///
+/// // There are 4 primes in 50..=70.
+/// assert_eq!(pi[70] - pi[49], 4);
+/// ```
+pub fn prime_pi_table(upper: usize) -> Vec<u32> {
+    let flat = Flat::new(upper);
+    let mut count = 0;
+
+    (0..=upper)
+        .map(|n| {
+            count += u32::from(flat.contains(n));
+            count
+        })
+        .collect()
+}
+
+/// Compute the sum of the primes up to `n` for every number `n` up to and
+/// including `upper`, indexed by the number itself.
+///
+/// This is built in one pass over a flat sieve like [`prime_pi_table()`], so
+/// the sum of the primes in `lo..=hi` is `table[hi] - table[lo - 1]`. Sums are
+/// kept as `u128` like [`sum_of_primes_below()`][crate::sum_of_primes_below()],
+/// which can't overflow.
+///
+/// # Examples
+///
+/// ```
+/// let sums = sieve::prime_sum_table(100);
+///
+/// assert_eq!(sums[..11], [0, 0, 2, 5, 5, 10, 10, 17, 17, 17, 17]);
+/// assert_eq!(sums[100], 1060);
+///
+/// // The primes in 50..=70 are 53, 59, 61 and 67.
+/// assert_eq!(sums[70] - sums[49], 240);
+/// ```
+pub fn prime_sum_table(upper: usize) -> Vec<u128> {
+    let flat = Flat::new(upper);
+    let mut sum = 0;
+
+    (0..=upper)
+        .map(|n| {
+            if flat.contains(n) {
+                sum += n as u128;
+            }
+
+            sum
+        })
+        .collect()
+}
+
+/// Compute the number of distinct prime factors `ω(n)` of every number up to
+/// and including `upper`, indexed by the number itself.
+///
+/// This uses the same linear sieve as [`spf_table()`], where dividing out the
+/// smallest prime factor removes a distinct factor unless it's repeated. No
+/// number which fits in a `u64` has more than 15 distinct prime factors, so
+/// they fit in a byte. `ω(0)` is taken to be 0.
+///
+/// # Examples
+///
+/// ```
+/// let omega = sieve::omega_table(30);
+///
+/// assert_eq!(omega[..13], [0, 0, 1, 1, 1, 1, 2, 1, 1, 1, 2, 1, 2]);
+/// assert_eq!(omega[30], 3);
+/// ```
+pub fn omega_table(upper: usize) -> Vec<u8> {
+    let mut omega = vec![0u8; upper + 1];
+    linear(upper, |n, p, m| omega[n] = omega[m] + u8::from(m % p != 0));
+    omega
+}
+
+/// Compute the number of prime factors counted with multiplicity `Ω(n)` of
+/// every number up to and including `upper`, indexed by the number itself.
+///
+/// This is the same as [`omega_table()`], except that repeated prime factors
+/// are counted every time. No number which fits in a `u64` has more than 63
+/// prime factors, so they fit in a byte. `Ω(0)` is taken to be 0.
+///
+/// # Examples
+///
+/// ```
+/// let omega = sieve::big_omega_table(30);
+///
+/// assert_eq!(omega[..13], [0, 0, 1, 1, 2, 1, 2, 1, 3, 2, 2, 1, 3]);
+/// assert_eq!(omega[16], 4);
+/// ```
+pub fn big_omega_table(upper: usize) -> Vec<u8> {
+    let mut omega = vec![0u8; upper + 1];
+    linear(upper, |n, _, m| omega[n] = omega[m] + 1);
+    omega
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{
+        big_omega_table, divisor_count_table, divisor_sum_table, mobius_table, omega_table,
+        prime_pi_table, prime_sum_table, spf_table, totient_table,
+    };
+
+    #[test]
+    fn spf() {
+        const UPPER: usize = 100_000;
+
+        let spf = spf_table::<u32>(UPPER);
+        assert_eq!(spf.len(), UPPER + 1);
+        assert_eq!(spf[..2], [0, 0]);
+
+        for p in crate::bounded(UPPER as u32) {
+            assert_eq!(spf[p as usize], p);
+        }
+
+        for n in (2..=UPPER).step_by(2) {
+            assert_eq!(spf[n], 2);
+        }
+
+        for n in (2..=UPPER).step_by(97) {
+            let mut factors = Vec::<(u32, u32)>::new();
+            let mut m = n;
+
+            while m > 1 {
+                let p = spf[m];
+
+                match factors.last_mut() {
+                    Some((q, e)) if *q == p => *e += 1,
+                    _ => factors.push((p, 1)),
+                }
+
+                m /= p as usize;
+            }
+
+            assert_eq!(factors, crate::factorize(n as u32), "{}", n);
+        }
+
+        assert_eq!(spf_table::<u64>(1), [0, 0]);
+        assert_eq!(spf_table::<u64>(0), [0]);
+        assert_eq!(spf_table::<u8>(255)[251], 251);
+    }
+
+    #[test]
+    #[should_panic = "bound doesn't fit in the table type"]
+    fn spf_overflow() {
+        spf_table::<u8>(256);
+    }
+
+    #[test]
+    fn totient() {
+        const UPPER: usize = 100_000;
+
+        let phi = totient_table(UPPER);
+        assert_eq!(phi.len(), UPPER + 1);
+        assert_eq!((phi[0], phi[1], phi[10], phi[36]), (0, 1, 4, 12));
+
+        for p in crate::bounded(UPPER as u64) {
+            assert_eq!(phi[p as usize], p - 1);
+        }
+
+        // The totients of the divisors of `n` sum to `n`.
+        for &n in &[1, 12, 360, 1001, 65_536, 99_990] {
+            let sum = (1..=n).filter(|d| n % d == 0).map(|d| phi[d]).sum::<u64>();
+            assert_eq!(sum, n as u64);
+        }
+
+        for n in (2..=UPPER).step_by(89) {
+            let expected = crate::factorize(n as u64)
+                .into_iter()
+                .map(|(p, e)| (p - 1) * p.pow(e - 1))
+                .product::<u64>();
+
+            assert_eq!(phi[n], expected, "{}", n);
+        }
+
+        assert_eq!(totient_table(0), [0]);
+        assert_eq!(totient_table(1), [0, 1]);
+    }
+
+    #[test]
+    fn mobius() {
+        const UPPER: usize = 100_000;
+
+        let mu = mobius_table(UPPER);
+        assert_eq!(mu.len(), UPPER + 1);
+        assert_eq!((mu[0], mu[1], mu[4]), (0, 1, 0));
+
+        for p in crate::bounded(UPPER as u64) {
+            assert_eq!(mu[p as usize], -1);
+        }
+
+        // The Mertens function at powers of ten.
+        let mut mertens = 0;
+
+        for (n, &m) in mu.iter().enumerate() {
+            mertens += i64::from(m);
+
+            match n {
+                10 => assert_eq!(mertens, -1),
+                100 => assert_eq!(mertens, 1),
+                1000 => assert_eq!(mertens, 2),
+                10_000 => assert_eq!(mertens, -23),
+                100_000 => assert_eq!(mertens, -48),
+                _ => {}
+            }
+        }
+
+        for n in (2..=UPPER).step_by(83) {
+            let factors = crate::factorize(n as u64);
+
+            let expected = if factors.iter().any(|&(_, e)| e > 1) {
+                0
+            } else if factors.len() % 2 == 0 {
+                1
+            } else {
+                -1
+            };
+
+            assert_eq!(mu[n], expected, "{}", n);
+        }
+
+        assert_eq!(mobius_table(0), [0]);
+        assert_eq!(mobius_table(1), [0, 1]);
+    }
+
+    #[test]
+    fn divisor_count() {
+        const UPPER: usize = 100_000;
+
+        let d = divisor_count_table(UPPER);
+        assert_eq!(d.len(), UPPER + 1);
+        assert_eq!((d[0], d[1], d[12], d[360]), (0, 1, 6, 24));
+        assert_eq!(d[1 << 16], 17);
+        assert_eq!(d[83_160], 128);
+
+        for p in crate::bounded(UPPER as u64) {
+            assert_eq!(d[p as usize], 2);
+        }
+
+        for n in (2..=UPPER).step_by(79) {
+            let expected = crate::factorize(n as u64)
+                .into_iter()
+                .map(|(_, e)| e + 1)
+                .product::<u32>();
+
+            assert_eq!(d[n], expected, "{}", n);
+        }
+
+        for (n, &d) in d.iter().enumerate().take(1001).skip(1) {
+            let expected = (1..=n).filter(|k| n % k == 0).count() as u32;
+            assert_eq!(d, expected, "{}", n);
+        }
+
+        assert_eq!(divisor_count_table(0), [0]);
+        assert_eq!(divisor_count_table(1), [0, 1]);
+    }
+
+    #[test]
+    fn divisor_sum() {
+        const UPPER: usize = 100_000;
+
+        let sigma = divisor_sum_table(UPPER);
+        assert_eq!(sigma.len(), UPPER + 1);
+        assert_eq!((sigma[0], sigma[1], sigma[6], sigma[28]), (0, 1, 12, 56));
+        assert_eq!(sigma[8128], 2 * 8128);
+        assert_eq!(sigma[1 << 16], (1 << 17) - 1);
+
+        for p in crate::bounded(UPPER as u64) {
+            assert_eq!(sigma[p as usize], p + 1);
+        }
+
+        for n in (2..=UPPER).step_by(73) {
+            let expected = crate::factorize(n as u64)
+                .into_iter()
+                .map(|(p, e)| (p.pow(e + 1) - 1) / (p - 1))
+                .product::<u64>();
+
+            assert_eq!(sigma[n], expected, "{}", n);
+        }
+
+        assert_eq!(divisor_sum_table(0), [0]);
+        assert_eq!(divisor_sum_table(1), [0, 1]);
+    }
+
+    #[test]
+    fn omega() {
+        const UPPER: usize = 100_000;
+
+        let omega = omega_table(UPPER);
+        let big = big_omega_table(UPPER);
+        assert_eq!((omega.len(), big.len()), (UPPER + 1, UPPER + 1));
+        assert_eq!((omega[0], omega[1], omega[12]), (0, 0, 2));
+        assert_eq!((big[0], big[1], big[12]), (0, 0, 3));
+        assert_eq!((omega[1 << 16], big[1 << 16]), (1, 16));
+        assert_eq!((omega[30_030], big[30_030]), (6, 6));
+
+        for p in crate::bounded(UPPER as u64) {
+            assert_eq!((omega[p as usize], big[p as usize]), (1, 1));
+        }
+
+        for n in (2..=UPPER).step_by(67) {
+            let factors = crate::factorize(n as u64);
+            let expected = factors.iter().map(|&(_, e)| e as u8).sum::<u8>();
+
+            assert_eq!(omega[n] as usize, factors.len(), "{}", n);
+            assert_eq!(big[n], expected, "{}", n);
+        }
+
+        assert_eq!(omega_table(0), [0]);
+        assert_eq!(big_omega_table(1), [0, 0]);
+    }
+
+    #[test]
+    fn prime_pi() {
+        const UPPER: usize = 1_000_000;
+
+        let pi = prime_pi_table(UPPER);
+        assert_eq!(pi.len(), UPPER + 1);
+        assert_eq!((pi[0], pi[1], pi[2], pi[100]), (0, 0, 1, 25));
+        assert_eq!(pi[UPPER], 78_498);
+        assert!(pi.windows(2).all(|w| w[1] - w[0] <= 1));
+
+        for state in crate::xorshift(0x2545_f491_4f6c_dd1d).take(50) {
+            let n = state % UPPER as u64;
+            assert_eq!(
+                pi[n as usize] as usize,
+                crate::segmented(n).count(),
+                "{}",
+                n
+            );
+        }
+
+        assert_eq!(prime_pi_table(0), [0]);
+        assert_eq!(prime_pi_table(1), [0, 0]);
+        assert_eq!(prime_pi_table(2), [0, 0, 1]);
+    }
+
+    #[test]
+    fn prime_sum() {
+        const UPPER: usize = 1_000_000;
+
+        let sums = prime_sum_table(UPPER);
+        assert_eq!(sums.len(), UPPER + 1);
+        assert_eq!(
+            (sums[0], sums[1], sums[2], sums[10], sums[100]),
+            (0, 0, 2, 17, 1060)
+        );
+        assert_eq!(sums[UPPER], crate::sum_of_primes_below(UPPER as u64));
+
+        for state in crate::xorshift(0x2545_f491_4f6c_dd1d).take(50) {
+            let lo = state % UPPER as u64;
+            let hi = (lo + (state >> 32) % 10_000).min(UPPER as u64);
+            let expected = crate::range(lo, hi).map(u128::from).sum::<u128>();
+            let below = lo.checked_sub(1).map_or(0, |n| sums[n as usize]);
+            assert_eq!(sums[hi as usize] - below, expected, "{} {}", lo, hi);
+        }
+
+        assert_eq!(prime_sum_table(0), [0]);
+        assert_eq!(prime_sum_table(2), [0, 0, 2]);
+    }
+}
@@ -0,0 +1,165 @@
+//! Writing primes to an output stream.
+
+use core::convert::TryFrom;
+use core::mem;
+use std::io::{self, Write};
+
+use crate::FromSmall;
+
+/// The format used by [`write_primes()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    /// One decimal number per line, each terminated by a newline.
+    Text,
+    /// Each prime as a little-endian integer as wide as the integer type,
+    /// with no separators.
+    LittleEndianBinary,
+}
+
+/// Write every prime up to and including `upper` to `w` in increasing order,
+/// returning the number of primes written.
+///
+/// The primes are produced by a [`segmented()`][crate::segmented()] sieve
+/// and written through a buffer, so neither the primes nor the output are
+/// held in memory. Errors from `w` are returned as soon as they occur,
+/// without sieving any further.
+///
+/// # Panics
+///
+/// Panics if `upper` doesn't fit in a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// use sieve::Format;
+///
+/// let mut out = Vec::new();
+/// assert_eq!(sieve::write_primes(20u32, &mut out, Format::Text)?, 8);
+/// assert_eq!(out, b"2\n3\n5\n7\n11\n13\n17\n19\n");
+///
+/// let mut out = Vec::new();
+/// assert_eq!(sieve::write_primes(5u16, &mut out, Format::LittleEndianBinary)?, 3);
+/// assert_eq!(out, [2, 0, 3, 0, 5, 0]);
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn write_primes<I, W>(upper: I, w: W, format: Format) -> io::Result<u64>
+where
+    I: Copy + Ord + FromSmall,
+    u64: TryFrom<I>,
+    W: io::Write,
+{
+    if upper < I::from_small(2) {
+        return Ok(0);
+    }
+
+    let upper = match u64::try_from(upper) {
+        Ok(upper) => upper,
+        Err(..) => panic!("bound doesn't fit in a u64"),
+    };
+
+    let width = mem::size_of::<I>();
+    let mut w = io::BufWriter::new(w);
+    let mut count = 0;
+
+    // Every prime up to the bound fits in `I`, so only its width matters.
+    for p in crate::segmented::<u64>(upper) {
+        match format {
+            Format::Text => writeln!(w, "{}", p)?,
+            Format::LittleEndianBinary => {
+                let bytes = u128::from(p).to_le_bytes();
+                w.write_all(&bytes[..width])?;
+            }
+        }
+
+        count += 1;
+    }
+
+    w.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::str;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    use super::{write_primes, Format};
+
+    #[test]
+    fn text_round_trip() {
+        let mut out = Vec::new();
+        let count = write_primes(100_000u32, &mut out, Format::Text).unwrap();
+        assert_eq!(count, 9592);
+
+        let primes = str::from_utf8(&out)
+            .unwrap()
+            .lines()
+            .map(|line| line.parse::<u32>().unwrap());
+        assert!(primes.eq(crate::bounded(100_000u32)));
+
+        let mut out = Vec::new();
+        assert_eq!(write_primes(1u32, &mut out, Format::Text).unwrap(), 0);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let mut out = Vec::new();
+        let count = write_primes(100_000u32, &mut out, Format::LittleEndianBinary).unwrap();
+        assert_eq!(count, 9592);
+        assert_eq!(out.len(), 9592 * 4);
+
+        let primes = out
+            .chunks(4)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]));
+        assert!(primes.eq(crate::bounded(100_000u32)));
+
+        let mut out = Vec::new();
+        write_primes(100u128, &mut out, Format::LittleEndianBinary).unwrap();
+        assert_eq!(out.len(), 25 * 16);
+        assert_eq!(
+            out[..17],
+            [2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3]
+        );
+
+        let mut out = Vec::new();
+        write_primes(255u8, &mut out, Format::LittleEndianBinary).unwrap();
+        assert!(out.iter().copied().eq(crate::bounded(255u8)));
+    }
+
+    /// A writer which fails once it has accepted `limit` bytes.
+    struct Failing {
+        written: usize,
+        limit: usize,
+    }
+
+    impl io::Write for Failing {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.written + buf.len() > self.limit {
+                return Err(io::Error::new(io::ErrorKind::Other, "full"));
+            }
+
+            self.written += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn errors_are_propagated() {
+        let mut w = Failing {
+            written: 0,
+            limit: 1 << 16,
+        };
+
+        let error = write_primes(u32::MAX, &mut w, Format::Text).unwrap_err();
+        assert_eq!(error.to_string(), "full");
+        assert!(w.written <= 1 << 16);
+    }
+}